@@ -0,0 +1,123 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! `#[derive(Random)]` for `splittable-random`.
+//!
+//! Every field is filled with `Random::random`, and
+//! enums pick a variant uniformly with `fair_roll`.
+//! `#[derive(Variants)]` lists the variants of
+//! a fieldless enum for `pick_variant`.
+//! Use through the `derive` feature of the main crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields};
+
+#[proc_macro_derive(Random)]
+pub fn derive_random(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(Variants)]
+pub fn derive_variants(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_variants(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_variants(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "Variants can only be derived for enums",
+            ))
+        }
+    };
+    let mut variants = Vec::with_capacity(data.variants.len());
+    for v in data.variants.iter() {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                v,
+                "Variants can only be derived for fieldless enums",
+            ));
+        }
+        variants.push(&v.ident);
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::splittable_random::Variants for #name #ty_generics #where_clause {
+            const ALL: &'static [Self] = &[#(Self::#variants),*];
+        }
+    })
+}
+
+fn construct(path: TokenStream2, fields: &Fields) -> TokenStream2 {
+    let random = quote!(::splittable_random::Random::random(rng));
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote!(#path { #(#names: #random),* })
+        }
+        Fields::Unnamed(unnamed) => {
+            let values = unnamed.unnamed.iter().map(|_| &random);
+            quote!(#path ( #(#values),* ))
+        }
+        Fields::Unit => path,
+    }
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    // Every type parameter must itself be Random
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::splittable_random::Random));
+    }
+    let body = match &input.data {
+        Data::Struct(data) => construct(quote!(Self), &data.fields),
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                return Err(Error::new_spanned(
+                    name,
+                    "can't derive Random for an enum with no variants",
+                ));
+            }
+            let count = data.variants.len() as u32;
+            let arms = data.variants.iter().enumerate().map(|(i, v)| {
+                let i = i as u32;
+                let ident = &v.ident;
+                let value = construct(quote!(Self::#ident), &v.fields);
+                quote!(#i => #value,)
+            });
+            quote! {
+                match rng.fair_roll(#count) {
+                    #(#arms)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(name, "can't derive Random for a union"));
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::splittable_random::Random for #name #ty_generics #where_clause {
+            fn random<R: ::splittable_random::__private::RngCore + ::splittable_random::__private::SeedableRng>(
+                rng: &mut ::splittable_random::SplittingRng<R>,
+            ) -> Self {
+                #body
+            }
+        }
+    })
+}
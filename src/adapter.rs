@@ -0,0 +1,107 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Lending the rng to code written for `rand`.
+//!
+//! Third-party crates take any `rand::RngCore`, and use the
+//! `rand::Rng` methods on it. Handing them the backend directly
+//! would draw past the rng without counting the steps, so its
+//! position, audit and step limit would all be wrong. The adapter
+//! here draws through the rng instead.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// A borrowed rng, as a `rand::RngCore`
+///
+/// Words come from `get_u32` and `get_u64`, so every draw is
+/// counted in the rng it borrows, and bytes are those of
+/// `into_reader`, drawn by `get_u64`, as the `rand09` bridge does.
+///
+/// ```
+/// use rand::Rng;
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::SplittingRng;
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let mut lent = rng.as_rng_core();
+/// let x: f32 = lent.gen_range(0.0..1.0);
+/// assert!(x < 1.0);
+/// assert_eq!(lent.steps_taken(), 1);
+/// assert_eq!(rng.steps(), 1);
+/// ```
+#[derive(Debug)]
+pub struct AsRngCore<'a, T: RngCore + SeedableRng> {
+    rng: &'a mut SplittingRng<T>,
+    start: u64,
+}
+
+impl<'a, T: RngCore + SeedableRng> AsRngCore<'a, T> {
+    /// Lend an rng
+    pub fn new(rng: &'a mut SplittingRng<T>) -> Self {
+        let start = rng.steps();
+        AsRngCore { rng, start }
+    }
+
+    /// How many steps have been drawn since the rng was lent
+    pub fn steps_taken(&self) -> u64 {
+        self.rng.steps() - self.start
+    }
+
+    /// The borrowed rng
+    pub fn rng(&mut self) -> &mut SplittingRng<T> {
+        self.rng
+    }
+}
+
+impl<T: RngCore + SeedableRng> RngCore for AsRngCore<'_, T> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.get_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.get_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes_recorded(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Lend this rng to code which takes a `rand::RngCore`,
+    /// counting every step it draws
+    pub fn as_rng_core(&mut self) -> AsRngCore<'_, T> {
+        AsRngCore::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_lent_rng() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let mut items: Vec<u32> = (0..10).collect();
+        let mut lent = rng.as_rng_core();
+        items.shuffle(&mut lent);
+        let mut bytes = [0u8; 12];
+        lent.fill_bytes(&mut bytes);
+        let taken = lent.steps_taken();
+        assert_eq!(lent.rng().audit().total(), taken);
+        assert_eq!(rng.steps(), taken);
+        // Lending draws the same stream the rng would
+        copy.seek(taken - 2);
+        assert_eq!(bytes[..7], copy.get_u64().to_le_bytes()[1..]);
+    }
+}
@@ -0,0 +1,126 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Shuffle bags, for randomness which feels fair.
+//!
+//! A bag hands out every item once per cycle, in a random
+//! order, so no item can be missing for long. This is what
+//! falling-block games use to pick the next piece.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// The state of a `ShuffleBag`, from `to_state`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BagState<V> {
+    /// The items, in the order of the current cycle
+    pub items: Vec<V>,
+    /// How many items of the cycle have been handed out
+    pub drawn: usize,
+}
+
+/// Hands out each item exactly once per cycle, reshuffling
+/// between cycles
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::bag::ShuffleBag;
+/// use splittable_random::SplittingRng;
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let mut bag = ShuffleBag::new(vec!['I', 'O', 'T', 'S', 'Z', 'J', 'L']);
+/// let mut cycle: Vec<char> = (0..7).map(|_| *bag.next(&mut rng).unwrap()).collect();
+/// cycle.sort_unstable();
+/// assert_eq!(cycle, ['I', 'J', 'L', 'O', 'S', 'T', 'Z']);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShuffleBag<V> {
+    items: Vec<V>,
+    drawn: usize,
+}
+
+impl<V> ShuffleBag<V> {
+    /// Create a bag, which is shuffled on the first draw
+    pub fn new(items: Vec<V>) -> Self {
+        let drawn = items.len();
+        ShuffleBag { items, drawn }
+    }
+
+    /// Hand out the next item, or `None` if the bag has no items
+    pub fn next<T: RngCore + SeedableRng>(&mut self, rng: &mut SplittingRng<T>) -> Option<&V> {
+        if self.items.is_empty() {
+            return None;
+        }
+        if self.drawn == self.items.len() {
+            self.items = rng.shuffle_vec(std::mem::take(&mut self.items));
+            self.drawn = 0;
+        }
+        self.drawn += 1;
+        Some(&self.items[self.drawn - 1])
+    }
+
+    /// How many items are left before the next reshuffle
+    pub fn remaining(&self) -> usize {
+        self.items.len() - self.drawn
+    }
+
+    /// The number of items in a cycle
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the bag has no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Restore a bag from its state
+    ///
+    /// A count drawn past the end of the items starts a new cycle.
+    pub fn from_state(state: BagState<V>) -> Self {
+        let drawn = state.drawn.min(state.items.len());
+        ShuffleBag {
+            items: state.items,
+            drawn,
+        }
+    }
+}
+
+impl<V: Clone> ShuffleBag<V> {
+    /// The full state of the bag, to save and restore with `from_state`
+    pub fn to_state(&self) -> BagState<V> {
+        BagState {
+            items: self.items.clone(),
+            drawn: self.drawn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_shuffle_bag_cycles() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut bag = ShuffleBag::new((0..5).collect::<Vec<u32>>());
+        let mut orders = Vec::new();
+        for _ in 0..3 {
+            let mut cycle: Vec<u32> = (0..5).map(|_| *bag.next(&mut rng).unwrap()).collect();
+            orders.push(cycle.clone());
+            cycle.sort_unstable();
+            assert_eq!(cycle, [0, 1, 2, 3, 4]);
+        }
+        assert_ne!(orders[0], orders[1]);
+        bag.next(&mut rng);
+        assert_eq!(bag.remaining(), 4);
+        let mut copy = ShuffleBag::from_state(bag.to_state());
+        let mut rng_copy = rng.clone();
+        for _ in 0..10 {
+            assert_eq!(bag.next(&mut rng), copy.next(&mut rng_copy));
+        }
+        assert_eq!(ShuffleBag::<u8>::new(Vec::new()).next(&mut rng), None);
+    }
+}
@@ -0,0 +1,469 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! A stream of raw random bits, pools of bools, masks
+//! with a fixed number of bits set, and bit-packed grids.
+//!
+//! Bits are read from whole words, using the bits allowed
+//! by the bool policy from lowest to highest, so reading
+//! `n` bits always uses the same number of steps.
+
+use crate::{Algorithm, BoolPolicy, SplittingRng, DENSITY_BITS};
+use rand::{RngCore, SeedableRng};
+
+/// The bools of a single word, handed out one at a time
+///
+/// This is the pool `get_bool` draws from, so a bool is
+/// true when its bit is clear. Any word may be pooled,
+/// and the pool counts how many of its bools are used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitPool {
+    pub(crate) inner: u64,
+    pub(crate) last: u8,
+    algorithm: Algorithm,
+    pub(crate) policy: BoolPolicy,
+}
+
+impl BitPool {
+    /// Pool every bit of a word, from lowest to highest
+    pub fn new(word: u64) -> Self {
+        Self::with_policy(word, BoolPolicy::All64)
+    }
+
+    /// Pool the bits of a word allowed by a bool policy
+    ///
+    /// Panics if a custom mask has no bits set
+    pub fn with_policy(word: u64, policy: BoolPolicy) -> Self {
+        assert!(
+            policy.capacity() > 0,
+            "A bool policy needs at least one bit"
+        );
+        Self::for_rng(word, Algorithm::V3, policy)
+    }
+
+    pub(crate) fn for_rng(word: u64, algorithm: Algorithm, policy: BoolPolicy) -> Self {
+        BitPool {
+            inner: word,
+            last: 0,
+            algorithm,
+            policy,
+        }
+    }
+
+    /// The word the bools are drawn from
+    pub fn word(&self) -> u64 {
+        self.inner
+    }
+
+    /// How many bools the word holds in all
+    pub fn capacity(&self) -> u32 {
+        self.policy.capacity() as u32
+    }
+
+    /// How many bools have been used
+    pub fn used(&self) -> u32 {
+        (self.last as u32).min(self.capacity())
+    }
+
+    /// How many bools are left
+    pub fn remaining(&self) -> u32 {
+        self.capacity() - self.used()
+    }
+}
+
+impl Iterator for BitPool {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.last < self.policy.capacity() {
+            //We should avoid the last 3 bits because they aren't really random
+            let mask = match (self.policy, self.algorithm) {
+                // Starts at bit 12, so the last 9 masks shift out to zero
+                (BoolPolicy::Standard, Algorithm::V1) => 0x1000 << self.last,
+                (BoolPolicy::Standard, Algorithm::V2 | Algorithm::V3) | (BoolPolicy::Skip3, _) => {
+                    0x8 << self.last
+                }
+                (BoolPolicy::All64, _) => 1 << self.last,
+                (BoolPolicy::Custom(mut bits), _) => {
+                    for _ in 0..self.last {
+                        bits &= bits - 1;
+                    }
+                    bits & bits.wrapping_neg()
+                }
+            };
+            let result = (mask & self.inner) == 0;
+            self.last += 1;
+            return Some(result);
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.remaining() as usize;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for BitPool {}
+
+/// An iterator over random bits, from `SplittingRng::bits`
+///
+/// As with `get_bool`, a bit is true when it is clear in the
+/// word, so the bits are the bools `fill_bools` would give.
+/// Any bits left in the current word are discarded on drop
+pub struct Bits<'a, T: RngCore + SeedableRng> {
+    rng: &'a mut SplittingRng<T>,
+    mask: u64,
+    word: u64,
+    left: u64,
+    bits_read: u64,
+    words_read: u64,
+}
+
+impl<T: RngCore + SeedableRng> Bits<'_, T> {
+    /// Read `n` bits, up to 64, with the first bit read lowest
+    /// and each true bit as a one
+    ///
+    /// Panics if `n` is more than 64
+    pub fn take_bits(&mut self, n: u32) -> u64 {
+        assert!(n <= 64, "Can only take up to 64 bits at once");
+        (0..n).fold(0, |acc, i| acc | (self.next_bit() as u64) << i)
+    }
+
+    /// How many bits have been read
+    pub fn bits_read(&self) -> u64 {
+        self.bits_read
+    }
+
+    /// How many words, and so rng steps, have been used
+    pub fn words_read(&self) -> u64 {
+        self.words_read
+    }
+
+    fn next_bit(&mut self) -> bool {
+        if self.left == 0 {
+            self.word = self.rng.step();
+            self.left = self.mask;
+            self.words_read += 1;
+        }
+        let bit = self.left & self.left.wrapping_neg();
+        self.left &= self.left - 1;
+        self.bits_read += 1;
+        self.word & bit == 0
+    }
+}
+
+impl<T: RngCore + SeedableRng> Iterator for Bits<'_, T> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        Some(self.next_bit())
+    }
+}
+
+/// A 2D grid of bools, packed 64 cells to a word
+///
+/// Each row starts on a fresh word, with the cell at `x`
+/// in bit `x % 64` of word `x / 64`. Bits past the width
+/// of a row are always clear.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// A grid with every cell false
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        BitGrid {
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height],
+        }
+    }
+
+    /// The number of cells in each row
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether a cell is set, or false outside the grid
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.row(y)[x / 64] & (1 << (x % 64)) != 0
+    }
+
+    /// Set or clear a cell
+    ///
+    /// Panics if the cell is outside the grid
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(x < self.width && y < self.height, "Cell outside the grid");
+        let word = &mut self.words[y * self.stride + x / 64];
+        if value {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    /// The words of a row
+    ///
+    /// Panics if `y` is not below the height
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// The words of every row in turn
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// How many cells are set
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a grid with each cell set with probability `density`
+    ///
+    /// Cells are made a word at a time, by combining random words
+    /// with AND and OR for each bit of the density, which is clamped
+    /// to [0, 1] and rounded to the nearest 1/65536. Each word takes
+    /// one step per bit, from the lowest set bit of the density,
+    /// so a density of 0.5 takes one step and 0.25 takes two.
+    pub fn get_bitgrid(&mut self, width: usize, height: usize, density: f64) -> BitGrid {
+        let mut grid = BitGrid::new(width, height);
+        let scale = 1u32 << DENSITY_BITS;
+        let level = (density.clamp(0.0, 1.0) * scale as f64).round() as u32;
+        if level == 0 || width == 0 {
+            return grid;
+        }
+        let tail = match width % 64 {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        };
+        for (i, word) in grid.words.iter_mut().enumerate() {
+            *word = if level == scale {
+                u64::MAX
+            } else {
+                (level.trailing_zeros()..DENSITY_BITS).fold(0, |cells, bit| {
+                    let w = self.get_u64();
+                    if level & (1 << bit) != 0 {
+                        cells | w
+                    } else {
+                        cells & w
+                    }
+                })
+            };
+            if i % grid.stride == grid.stride - 1 {
+                *word &= tail;
+            }
+        }
+        grid
+    }
+
+    /// Provide a fresh pool of bools from one step, using
+    /// the bool policy and algorithm version of this rng
+    ///
+    /// Taking bools from the pool gives the same values as
+    /// `get_bool` would from a fresh word, without a call per bool.
+    pub fn bit_pool(&mut self) -> BitPool {
+        BitPool::for_rng(self.step(), self.algorithm, self.bool_policy)
+    }
+
+    /// How many bools `get_bool` can give before it next steps
+    pub fn bools_remaining(&self) -> u32 {
+        self.bool_pool.remaining()
+    }
+
+    /// Step now to refill the bool pool, dropping any bools left
+    ///
+    /// Lockstep code can refill at a fixed point, such as the start
+    /// of each frame, so no `get_bool` in the frame steps, as long
+    /// as the frame uses no more than a pool of bools.
+    pub fn refill_bool_pool(&mut self) {
+        self.bool_pool = self.bit_pool();
+    }
+
+    /// Provide a never ending stream of random bits
+    ///
+    /// The standard bool policy reads 61 bits per word,
+    /// skipping the lowest three
+    pub fn bits(&mut self) -> Bits<'_, T> {
+        Bits {
+            mask: self.bool_policy.bulk_mask(),
+            rng: self,
+            word: 0,
+            left: 0,
+            bits_read: 0,
+            words_read: 0,
+        }
+    }
+
+    /// Provide a mask with exactly `popcount` of its lowest
+    /// `bits` bits set, uniformly chosen
+    ///
+    /// Panics if `bits` is over 64 or `popcount` is over `bits`
+    pub fn get_mask(&mut self, bits: u32, popcount: u32) -> u64 {
+        assert!(bits <= 64, "A mask has at most 64 bits");
+        self.get_mask_words(bits as usize, popcount as usize)
+            .first()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Provide a bit vector of `bits` bits, as little-endian
+    /// words, with exactly `popcount` bits set
+    ///
+    /// Uses Floyd's subset sampling, so it takes one roll
+    /// per set bit however long the vector is.
+    /// Panics if `popcount` is over `bits`
+    pub fn get_mask_words(&mut self, bits: usize, popcount: usize) -> Vec<u64> {
+        assert!(popcount <= bits, "More bits set than there are bits");
+        let mut words = vec![0u64; bits.div_ceil(64)];
+        for j in bits - popcount..bits {
+            let t = self.below_u128(j as u128 + 1) as usize;
+            let pick = if words[t / 64] & (1 << (t % 64)) != 0 {
+                j
+            } else {
+                t
+            };
+            words[pick / 64] |= 1 << (pick % 64);
+        }
+        words
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_bit_accounting() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let word = copy.get_u64();
+        let mut bits = rng.bits();
+        assert_eq!(bits.take_bits(5), !(word >> 3) & 0b11111);
+        assert_eq!(bits.by_ref().take(56).count(), 56);
+        assert_eq!(bits.words_read(), 1);
+        bits.next();
+        assert_eq!((bits.bits_read(), bits.words_read()), (62, 2));
+        assert_eq!(rng.steps(), 2);
+    }
+
+    #[test]
+    fn test_bits_match_fill_bools() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut expected = [false; 150];
+        copy.fill_bools(&mut expected);
+        let got: Vec<bool> = rng.bits().take(150).collect();
+        assert_eq!(got, expected);
+        assert_eq!(rng.steps(), copy.steps());
+    }
+
+    #[test]
+    fn test_bit_pools() {
+        let mut pool = BitPool::new(0b1010);
+        assert_eq!(
+            pool.by_ref().take(4).collect::<Vec<_>>(),
+            [true, false, true, false]
+        );
+        assert_eq!((pool.used(), pool.remaining(), pool.len()), (4, 60, 60));
+        assert_eq!(pool.by_ref().count(), 60);
+        assert_eq!(pool.next(), None);
+        assert_eq!(
+            BitPool::with_policy(!0, BoolPolicy::Custom(0b11)).count(),
+            2
+        );
+
+        // A pool gives what get_bool would, once the first pool is used up
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V2);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V2);
+        for _ in 0..61 {
+            copy.get_bool();
+        }
+        let pool = rng.bit_pool();
+        assert_eq!(pool.capacity(), 61);
+        assert!(pool.into_iter().all(|b| b == copy.get_bool()));
+        assert_eq!(rng.steps(), copy.steps());
+    }
+
+    #[test]
+    fn test_masks() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut hits = [0; 10];
+        for _ in 0..10000 {
+            let mask = rng.get_mask(10, 3);
+            assert_eq!(mask.count_ones(), 3);
+            assert!(mask < 1 << 10);
+            for (i, h) in hits.iter_mut().enumerate() {
+                *h += (mask >> i) & 1;
+            }
+        }
+        // Every bit is set 3 times in 10
+        assert!(
+            hits.iter().all(|h| (*h as i64 - 3000).abs() < 150),
+            "{:?}",
+            hits
+        );
+        assert_eq!(rng.get_mask(64, 64), u64::MAX);
+        let words = rng.get_mask_words(1000, 500);
+        assert_eq!(words.len(), 16);
+        assert_eq!(words.iter().map(|w| w.count_ones()).sum::<u32>(), 500);
+    }
+
+    #[test]
+    fn test_bitgrids() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let grid = rng.get_bitgrid(100, 50, 0.3);
+        assert_eq!(
+            (grid.width(), grid.height(), grid.words().len()),
+            (100, 50, 100)
+        );
+        // 100 words at 16 steps each, as 0.3 has no short expansion
+        assert_eq!(rng.steps(), 1600);
+        assert!(grid.words().chunks(2).all(|row| row[1] >> 36 == 0));
+        let frac = grid.count_ones() as f64 / 5000.0;
+        assert!((frac - 0.3).abs() < 0.02, "{}", frac);
+        let half = rng.get_bitgrid(64, 10, 0.5);
+        assert_eq!(rng.steps(), 1610);
+        assert_eq!(half.row(3).len(), 1);
+
+        let mut full = rng.get_bitgrid(70, 2, 1.5);
+        assert_eq!(full.count_ones(), 140);
+        assert!(!full.get(70, 0) && full.get(69, 1));
+        full.set(69, 1, false);
+        assert!(!full.get(69, 1));
+        assert_eq!(rng.get_bitgrid(70, 2, 0.0), BitGrid::new(70, 2));
+        assert_eq!(rng.steps(), 1610);
+    }
+
+    #[test]
+    fn test_refill_bool_pool() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(rng.bools_remaining(), 61);
+        rng.get_bool();
+        assert_eq!(rng.bools_remaining(), 60);
+        rng.refill_bool_pool();
+        assert_eq!((rng.bools_remaining(), rng.steps()), (61, 1));
+        for _ in 0..61 {
+            rng.get_bool();
+        }
+        assert_eq!((rng.bools_remaining(), rng.steps()), (0, 1));
+        rng.get_bool();
+        assert_eq!((rng.bools_remaining(), rng.steps()), (60, 2));
+    }
+}
@@ -0,0 +1,138 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Step limits, for hosts which run untrusted scripts.
+//!
+//! Unlike a `FuzzBudget`, a step limit lives in the rng
+//! itself, so it applies however the rng is passed around.
+//! A host sets the limit, refills it each tick, and either
+//! uses `try_draw` or a callback to notice overspending.
+
+use crate::fuzz::Exhausted;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+pub(crate) struct StepLimit {
+    max: u64,
+    used: u64,
+    on_exhausted: Option<Box<dyn FnMut(u64) + Send + Sync>>,
+}
+
+impl StepLimit {
+    pub(crate) fn used(&self) -> u64 {
+        self.used
+    }
+
+    pub(crate) fn set_used(&mut self, used: u64) {
+        self.used = used;
+    }
+
+    pub(crate) fn without_callback(&self) -> StepLimit {
+        StepLimit {
+            max: self.max,
+            used: self.used,
+            on_exhausted: None,
+        }
+    }
+
+    pub(crate) fn charge(&mut self) {
+        // Only the first step past the limit calls back
+        if self.used == self.max {
+            if let Some(f) = self.on_exhausted.as_mut() {
+                f(self.max);
+            }
+        }
+        self.used = self.used.saturating_add(1);
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Limit this rng to `max_steps` steps until refilled
+    ///
+    /// Unchecked draws still succeed once the limit is passed;
+    /// use `try_draw` to have them refused.
+    pub fn limit_steps(&mut self, max_steps: u64) {
+        self.limit = Some(StepLimit {
+            max: max_steps,
+            used: 0,
+            on_exhausted: None,
+        });
+    }
+
+    /// Limit this rng to `max_steps` steps until refilled, calling
+    /// `on_exhausted` with the limit at the first step beyond it
+    ///
+    /// The callback runs at most once per refill. It may panic
+    /// to abort a script, or set a flag for the host to check.
+    pub fn limit_steps_with<F>(&mut self, max_steps: u64, on_exhausted: F)
+    where
+        F: FnMut(u64) + Send + Sync + 'static,
+    {
+        self.limit = Some(StepLimit {
+            max: max_steps,
+            used: 0,
+            on_exhausted: Some(Box::new(on_exhausted)),
+        });
+    }
+
+    /// Restore the full limit, for example at the start of each tick
+    pub fn refill_steps(&mut self) {
+        if let Some(limit) = self.limit.as_mut() {
+            limit.used = 0;
+        }
+    }
+
+    /// Remove any step limit
+    pub fn remove_step_limit(&mut self) {
+        self.limit = None;
+    }
+
+    /// How many steps may still be used, if limited
+    pub fn steps_remaining(&self) -> Option<u64> {
+        self.limit
+            .as_ref()
+            .map(|limit| limit.max.saturating_sub(limit.used))
+    }
+
+    /// Make any draw, unless the step limit is already spent
+    ///
+    /// As with `FuzzBudget::draw`, a draw which starts within
+    /// the limit is allowed to finish.
+    pub fn try_draw<V>(&mut self, f: impl FnOnce(&mut Self) -> V) -> Result<V, Exhausted> {
+        if self.steps_remaining() == Some(0) {
+            return Err(Exhausted);
+        }
+        Ok(f(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_step_limit() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let hits = Arc::new(AtomicU64::new(0));
+        let counter = hits.clone();
+        rng.limit_steps_with(2, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        assert!(rng.try_draw(|r| r.get_u64()).is_ok());
+        assert!(rng.try_draw(|r| r.get_u64()).is_ok());
+        assert_eq!(rng.try_draw(|r| r.get_u64()), Err(Exhausted));
+        assert_eq!(hits.load(Ordering::Relaxed), 0);
+        // Unchecked draws still work, but call back once
+        rng.get_u64();
+        rng.get_u64();
+        assert_eq!(hits.load(Ordering::Relaxed), 1);
+        rng.refill_steps();
+        assert_eq!(rng.steps_remaining(), Some(2));
+        rng.remove_step_limit();
+        assert_eq!(rng.steps_remaining(), None);
+    }
+}
@@ -0,0 +1,250 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Filling whole buffers at once.
+//!
+//! These skip the per-value bookkeeping of the single
+//! value methods, and are meant for workloads which
+//! need millions of values at a time.
+
+use crate::{unit_f64, Finalizer, SplittingRng};
+use rand::{RngCore, SeedableRng};
+
+/// A primitive type whose slices `fill` can fill
+///
+/// Each value matches the single value method for its type:
+/// `get_u32` for `u32`, `get_u64` for `u64`, `get_f64` for `f64`,
+/// and `get_random` for `f32`. Bytes are the same as read
+/// from `into_reader`.
+pub trait PrimitiveFill: Sized {
+    /// Fill a slice with random values
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [Self]);
+}
+
+impl PrimitiveFill for u8 {
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [u8]) {
+        let skip = byte_skip(rng.finalizer);
+        for chunk in out.chunks_mut(8 - skip) {
+            let bytes = rng.step().to_le_bytes();
+            chunk.copy_from_slice(&bytes[skip..skip + chunk.len()]);
+        }
+    }
+}
+
+/// Bytes skip the lowest byte of each word unless a finalizer
+/// is in use, as its bits are the weakest
+fn byte_skip(finalizer: Finalizer) -> usize {
+    match finalizer {
+        Finalizer::None => 1,
+        Finalizer::Mix64 => 0,
+    }
+}
+
+impl PrimitiveFill for u32 {
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [u32]) {
+        rng.fill_mapped(out, |word| (word >> 32) as u32);
+    }
+}
+
+impl PrimitiveFill for u64 {
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [u64]) {
+        rng.fill_u64(out);
+    }
+}
+
+impl PrimitiveFill for f32 {
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [f32]) {
+        rng.fill_mapped(out, |word| {
+            (word >> 40) as f32 * (1.0 / (1u32 << 24) as f32)
+        });
+    }
+}
+
+impl PrimitiveFill for f64 {
+    fn fill_slice<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, out: &mut [f64]) {
+        rng.fill_f64_unit(out);
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Fill a slice with random words, the same as calling
+    /// `get_u64` for each but without the per-call bookkeeping
+    ///
+    /// Step limits, clone and thread watching and prefetching are still
+    /// honoured, at the cost of the fast path.
+    pub fn fill_u64(&mut self, out: &mut [u64]) {
+        if self.limit.is_some()
+            || self.clone_watch.is_some()
+            || self.thread_watch.is_some()
+            || self.prefetch.is_some()
+        {
+            for slot in out.iter_mut() {
+                *slot = self.step();
+            }
+            return;
+        }
+        let finalizer = self.finalizer;
+        for slot in out.iter_mut() {
+            *slot = finalizer.apply(self.prng.next_u64());
+        }
+        self.steps += out.len() as u64;
+    }
+
+    /// Fill a slice with floats in [0, 1), the same as
+    /// calling `get_f64` for each
+    ///
+    /// Words are converted in chunks after they are drawn,
+    /// which keeps the conversion loop simple enough for the
+    /// compiler to vectorize.
+    pub fn fill_f64_unit(&mut self, out: &mut [f64]) {
+        let mirrored = self.mirrored;
+        self.fill_mapped(out, |word| unit_f64(word, mirrored));
+    }
+
+    /// Fill a slice of any primitive type, one value per step
+    /// except for bytes, which are packed several to a step
+    ///
+    /// See `PrimitiveFill` for which values each type gets.
+    pub fn fill<V: PrimitiveFill>(&mut self, out: &mut [V]) {
+        V::fill_slice(self, out);
+    }
+
+    /// Fill bytes as `fill` does, but drawing each word by
+    /// `get_u64`, so lending the rng to other `rand` versions
+    /// records every draw
+    #[cfg(any(feature = "rand08", feature = "rand09"))]
+    pub(crate) fn fill_bytes_recorded(&mut self, out: &mut [u8]) {
+        let skip = byte_skip(self.finalizer);
+        for chunk in out.chunks_mut(8 - skip) {
+            let bytes = self.get_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[skip..skip + chunk.len()]);
+        }
+    }
+
+    /// Fill a slice with a conversion of each word, in chunks
+    fn fill_mapped<V>(&mut self, out: &mut [V], convert: impl Fn(u64) -> V) {
+        const CHUNK: usize = 64;
+        let mut words = [0u64; CHUNK];
+        for chunk in out.chunks_mut(CHUNK) {
+            let words = &mut words[..chunk.len()];
+            self.fill_u64(words);
+            for (slot, word) in chunk.iter_mut().zip(words.iter()) {
+                *slot = convert(*word);
+            }
+        }
+    }
+
+    /// Provide a fixed-size array of floats in [0, 1),
+    /// without allocating
+    pub fn get_f64_array<const N: usize>(&mut self) -> [f64; N] {
+        let mut out = [0.0; N];
+        self.fill_f64_unit(&mut out);
+        out
+    }
+
+    /// Provide `n` random words
+    pub fn get_u64s(&mut self, n: usize) -> Vec<u64> {
+        let mut out = vec![0; n];
+        self.fill_u64(&mut out);
+        out
+    }
+
+    /// Fill a slice with random bools, straight from whole words
+    ///
+    /// Uses the bits allowed by the bool policy, except that the
+    /// standard policy uses every bit but the lowest three for all
+    /// algorithm versions. The bool pool is left untouched.
+    pub fn fill_bools(&mut self, out: &mut [bool]) {
+        let mask = self.bool_policy.bulk_mask();
+        let mut rest = out;
+        while !rest.is_empty() {
+            let word = self.step();
+            let mut bits = mask;
+            let mut used = 0;
+            for slot in rest.iter_mut() {
+                if bits == 0 {
+                    break;
+                }
+                *slot = word & bits & bits.wrapping_neg() == 0;
+                bits &= bits - 1;
+                used += 1;
+            }
+            rest = &mut rest[used..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_fill_u64_matches_get_u64() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let words = rng.get_u64s(100);
+        for w in words {
+            assert_eq!(w, copy.get_u64());
+        }
+        assert_eq!(rng.steps(), copy.steps());
+    }
+
+    #[test]
+    fn test_fill_f64_unit_matches_get_f64() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut floats = vec![0.0; 150];
+        rng.fill_f64_unit(&mut floats);
+        for f in floats {
+            assert_eq!(f, copy.get_f64());
+        }
+    }
+
+    #[test]
+    fn test_fixed_size_arrays() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let gradients: [f64; 3] = rng.get_f64_array();
+        assert_eq!(gradients, copy.get_array::<f64, 3>());
+        assert!(gradients.iter().all(|g| (0.0..1.0).contains(g)));
+        let words: [u64; 4] = rng.get_array();
+        assert_eq!(words.to_vec(), copy.get_u64s(4));
+    }
+
+    #[test]
+    fn test_fill_primitives() {
+        use std::io::Read;
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut bytes = [0u8; 20];
+        rng.fill(&mut bytes);
+        let mut expected = [0u8; 20];
+        copy.clone()
+            .into_reader()
+            .read_exact(&mut expected)
+            .unwrap();
+        assert_eq!(bytes, expected);
+        copy.seek(3);
+        let mut words = [0u32; 70];
+        rng.fill(&mut words);
+        assert!(words.iter().all(|w| *w == copy.get_u32()));
+        let mut floats = [0f32; 5];
+        rng.fill(&mut floats);
+        assert!(floats.iter().all(|f| *f == copy.get_random::<f32>()));
+    }
+
+    #[test]
+    fn test_fill_bools() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut bools = vec![false; 61 * 1000 + 7];
+        rng.fill_bools(&mut bools);
+        assert_eq!(rng.steps(), 1001);
+        let trues = bools.iter().filter(|b| **b).count();
+        let frac = trues as f64 / bools.len() as f64;
+        assert!((frac - 0.5).abs() < 0.01, "{}", frac);
+        // The last bools of each word are not stuck
+        assert!(bools.chunks(61).any(|c| !c[60]));
+    }
+}
@@ -0,0 +1,156 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random colors for procedural art.
+//!
+//! Fully random RGB tends to look muddy, so colors
+//! can also be drawn in HSL with the saturation and
+//! lightness held within a band.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::ops::RangeInclusive;
+
+// Spacing hues by the golden ratio keeps
+// neighbouring palette entries far apart
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// An 8-bit per channel color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// A color as hue, saturation and lightness
+///
+/// The hue is in degrees, [0, 360), and the
+/// saturation and lightness are in [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl Hsl {
+    /// Convert to 8-bit RGB
+    pub fn to_rgb(&self) -> Rgb {
+        let s = self.s.clamp(0.0, 1.0);
+        let l = self.l.clamp(0.0, 1.0);
+        let h = self.h.rem_euclid(360.0) / 60.0;
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        let channel = |c: f64| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Rgb {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(hsl: Hsl) -> Self {
+        hsl.to_rgb()
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a fully random RGB color
+    pub fn get_rgb(&mut self) -> Rgb {
+        // One step is plenty, skipping the low bits
+        let bits = self.get_u32();
+        Rgb {
+            r: (bits >> 24) as u8,
+            g: (bits >> 16) as u8,
+            b: (bits >> 8) as u8,
+        }
+    }
+
+    /// Provide a color of any hue, with the saturation
+    /// and lightness drawn uniformly from the given bands
+    pub fn get_hsl(
+        &mut self,
+        saturation: RangeInclusive<f64>,
+        lightness: RangeInclusive<f64>,
+    ) -> Hsl {
+        let h = 360.0 * self.get_f64();
+        let s = self.band(&saturation);
+        let l = self.band(&lightness);
+        Hsl { h, s, l }
+    }
+
+    /// Provide `n` colors with well spread hues, from a
+    /// random starting hue, with fixed saturation and lightness
+    pub fn get_palette(&mut self, n: usize, saturation: f64, lightness: f64) -> Vec<Hsl> {
+        let mut hue = self.get_f64();
+        (0..n)
+            .map(|_| {
+                let color = Hsl {
+                    h: 360.0 * hue,
+                    s: saturation,
+                    l: lightness,
+                };
+                hue = (hue + GOLDEN_RATIO_CONJUGATE) % 1.0;
+                color
+            })
+            .collect()
+    }
+
+    fn band(&mut self, range: &RangeInclusive<f64>) -> f64 {
+        range.start() + (range.end() - range.start()) * self.get_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_hsl_colors() {
+        let red = Hsl {
+            h: 0.0,
+            s: 1.0,
+            l: 0.5,
+        };
+        assert_eq!(red.to_rgb(), Rgb { r: 255, g: 0, b: 0 });
+        let teal = Hsl {
+            h: 180.0,
+            s: 1.0,
+            l: 0.25,
+        };
+        assert_eq!(
+            Rgb::from(teal),
+            Rgb {
+                r: 0,
+                g: 128,
+                b: 128
+            }
+        );
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..1000 {
+            let c = rng.get_hsl(0.4..=0.6, 0.7..=0.8);
+            assert!((0.0..360.0).contains(&c.h));
+            assert!((0.4..=0.6).contains(&c.s) && (0.7..=0.8).contains(&c.l));
+            // Light colors never have a dark channel
+            let rgb = c.to_rgb();
+            assert!(rgb.r.min(rgb.g).min(rgb.b) > 100);
+        }
+        let palette = rng.get_palette(5, 0.5, 0.5);
+        assert_eq!(palette.len(), 5);
+    }
+}
@@ -0,0 +1,59 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Older entry points, kept for content which depends
+//! on their exact outputs.
+//!
+//! Everything here gives the same values it always has for
+//! each algorithm version, but new code should prefer the fair
+//! methods, such as `roll`. Calling through the traits here marks
+//! the code which still relies on the old behaviour.
+
+use crate::{trace, Algorithm, SplittingRng};
+use rand::{RngCore, SeedableRng};
+
+/// The original fast roll, which is slightly biased
+///
+/// `SplittingRng` still has a deprecated `biased_roll` of its own,
+/// which wins over the trait in method calls, so call through the
+/// trait to keep the old values without the warning.
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::compat::BiasedRoll;
+/// use splittable_random::SplittingRng;
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// assert!(BiasedRoll::biased_roll(&mut rng, 6) < 6);
+/// ```
+pub trait BiasedRoll {
+    /// Roll a die with up to 2^32 sides
+    ///
+    /// The distribution is not even, as the values of a step
+    /// rarely divide evenly by the number of sides. Under `Algorithm::V1`
+    /// and `V2` a step is taken modulo the sides, so the lowest
+    /// `2^61 % sides` faces are each more likely by about `sides / 2^61`,
+    /// up to one part in 2^29 for the largest dice. Under `Algorithm::V3`
+    /// the step is scaled instead, and each face is off by at most
+    /// one part in 2^32. For an even roll use `SplittingRng::roll`.
+    fn biased_roll(&mut self, sides: u32) -> u32;
+}
+
+impl<T: RngCore + SeedableRng> BiasedRoll for SplittingRng<T> {
+    fn biased_roll(&mut self, sides: u32) -> u32 {
+        if sides == 0 {
+            return 0;
+        }
+        let result = if self.algorithm == Algorithm::V3 {
+            // The high half of the product is in 0..sides,
+            // and depends mostly on the high bits
+            ((self.step() as u128 * sides as u128) >> 64) as u32
+        } else {
+            // lowest 3 bits are low entropy, shift away
+            ((self.step() >> 3) % (sides as u64)) as u32
+        };
+        self.record(trace::Method::BiasedRoll, result as u64);
+        result
+    }
+}
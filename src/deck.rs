@@ -0,0 +1,170 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Decks of cards with a draw pile and a discard pile.
+//!
+//! A deck owns a child rng, split off when it is created, so
+//! its shuffles don't depend on whatever else the game draws.
+//! Saving a deck saves its rng, so a restored game reshuffles
+//! the same way.
+
+use crate::{RngState, SplittingRng};
+use rand::{RngCore, SeedableRng};
+
+/// The state of a `Deck`, from `to_state`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeckState<V> {
+    /// The state of the deck's own rng
+    pub rng: RngState,
+    /// The draw pile, with the top card last
+    pub draw_pile: Vec<V>,
+    /// The discard pile, in the order discarded
+    pub discards: Vec<V>,
+}
+
+/// A shuffled deck which cards are drawn from and discarded to
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::deck::Deck;
+/// use splittable_random::SplittingRng;
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let mut deck = Deck::new((1..=52).collect::<Vec<u8>>(), &mut rng);
+/// let card = deck.draw().unwrap();
+/// deck.discard(card);
+/// assert_eq!(deck.draw_pile_len(), 51);
+/// deck.reshuffle_discards();
+/// assert_eq!(deck.draw_pile_len(), 52);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Deck<V, T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    draw_pile: Vec<V>,
+    discards: Vec<V>,
+}
+
+impl<V, T: RngCore + SeedableRng> Deck<V, T> {
+    /// Create a deck from the cards, splitting its rng from `rng`
+    /// and shuffling the draw pile with it
+    pub fn new(cards: Vec<V>, rng: &mut SplittingRng<T>) -> Self {
+        let mut rng = rng.split();
+        let draw_pile = rng.shuffle_vec(cards);
+        Deck {
+            rng,
+            draw_pile,
+            discards: Vec::new(),
+        }
+    }
+
+    /// Take the top card of the draw pile, or `None` if it is empty
+    pub fn draw(&mut self) -> Option<V> {
+        self.draw_pile.pop()
+    }
+
+    /// Take up to `n` cards from the top of the draw pile
+    pub fn draw_many(&mut self, n: usize) -> Vec<V> {
+        let rest = self.draw_pile.len().saturating_sub(n);
+        let mut hand = self.draw_pile.split_off(rest);
+        hand.reverse();
+        hand
+    }
+
+    /// The top card of the draw pile, without taking it
+    pub fn peek(&self) -> Option<&V> {
+        self.draw_pile.last()
+    }
+
+    /// Put a card on the discard pile
+    pub fn discard(&mut self, card: V) {
+        self.discards.push(card);
+    }
+
+    /// Shuffle the discard pile and put it under the draw pile
+    ///
+    /// The cards left in the draw pile stay on top, in their order.
+    pub fn reshuffle_discards(&mut self) {
+        let mut pile = self.rng.shuffle_vec(std::mem::take(&mut self.discards));
+        pile.append(&mut self.draw_pile);
+        self.draw_pile = pile;
+    }
+
+    /// Shuffle the draw and discard piles together
+    pub fn reshuffle_all(&mut self) {
+        self.draw_pile.append(&mut self.discards);
+        self.draw_pile = self.rng.shuffle_vec(std::mem::take(&mut self.draw_pile));
+    }
+
+    /// The number of cards left to draw
+    pub fn draw_pile_len(&self) -> usize {
+        self.draw_pile.len()
+    }
+
+    /// The number of cards discarded since the last reshuffle
+    pub fn discards_len(&self) -> usize {
+        self.discards.len()
+    }
+
+    /// The discard pile, in the order discarded
+    pub fn discards(&self) -> &[V] {
+        &self.discards
+    }
+
+    /// Restore a deck from its state
+    pub fn from_state(state: DeckState<V>) -> Self {
+        Deck {
+            rng: SplittingRng::from_state(state.rng),
+            draw_pile: state.draw_pile,
+            discards: state.discards,
+        }
+    }
+}
+
+impl<V: Clone, T: RngCore + SeedableRng> Deck<V, T> {
+    /// The full state of the deck, to save and restore with `from_state`
+    pub fn to_state(&self) -> DeckState<V> {
+        DeckState {
+            rng: self.rng.to_state(),
+            draw_pile: self.draw_pile.clone(),
+            discards: self.discards.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_deck_piles_and_state() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut deck = Deck::new((0..20).collect::<Vec<u32>>(), &mut rng);
+        let top = *deck.peek().unwrap();
+        let hand = deck.draw_many(5);
+        assert_eq!(hand[0], top);
+        for card in hand {
+            deck.discard(card);
+        }
+        assert_eq!((deck.draw_pile_len(), deck.discards_len()), (15, 5));
+        let next = *deck.peek().unwrap();
+        deck.reshuffle_discards();
+        assert_eq!(deck.peek(), Some(&next));
+        assert_eq!(deck.discards_len(), 0);
+
+        let mut restored = Deck::<u32, Xoshiro256StarStar>::from_state(deck.to_state());
+        let card = deck.draw().unwrap();
+        deck.discard(card);
+        let card = restored.draw().unwrap();
+        restored.discard(card);
+        deck.reshuffle_all();
+        restored.reshuffle_all();
+        let cards = deck.draw_many(20);
+        assert_eq!(cards, restored.draw_many(20));
+        let mut sorted = cards.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<u32>>());
+        assert_eq!(deck.draw(), None);
+    }
+}
@@ -0,0 +1,587 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Tabletop dice, built on `fair_roll`.
+//!
+//! Dice expressions use the usual notation,
+//! such as `3d6+2`, `d20` or `2d8 - 1d4 + 3`.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// The largest number of dice a single term may roll
+/// Guards against expressions like `1000000000d6`
+pub const MAX_DICE_PER_TERM: u32 = 10_000;
+
+/// The largest number of dice a whole expression may roll
+/// Guards against many large terms, like `10000d6+10000d6+...`
+pub const MAX_DICE: u32 = 100_000;
+
+/// The reasons a dice expression may fail to parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiceError {
+    /// The expression had no terms
+    Empty,
+    /// An unexpected character at the given byte offset
+    UnexpectedChar(usize),
+    /// A number was too large to represent
+    Overflow,
+    /// A die had zero sides
+    ZeroSides,
+    /// A term rolled more than `MAX_DICE_PER_TERM` dice,
+    /// or the whole expression more than `MAX_DICE`
+    TooManyDice,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiceError::Empty => write!(f, "dice expression was empty"),
+            DiceError::UnexpectedChar(at) => write!(f, "unexpected character at {}", at),
+            DiceError::Overflow => write!(f, "number too large"),
+            DiceError::ZeroSides => write!(f, "dice must have at least one side"),
+            DiceError::TooManyDice => write!(f, "too many dice to roll"),
+        }
+    }
+}
+
+impl std::error::Error for DiceError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Term {
+    Dice { count: u32, sides: u32 },
+    Constant(i64),
+}
+
+/// A parsed dice expression which can be rolled many times
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceExpr {
+    // Each term with whether it is subtracted
+    terms: Vec<(bool, Term)>,
+}
+
+/// A single die within a rolled expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DieResult {
+    /// The number of sides on the die
+    pub sides: u32,
+    /// The face rolled, from 1 to `sides`
+    pub value: u32,
+    /// Whether the die counts against the total
+    pub subtracted: bool,
+}
+
+/// The outcome of rolling a dice expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceRoll {
+    /// The final result
+    pub total: i64,
+    /// Each die rolled, in the order written
+    pub dice: Vec<DieResult>,
+    /// The sum of the constant terms
+    pub modifier: i64,
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_space(&mut self) {
+        while self.at < self.bytes.len() && self.bytes[self.at].is_ascii_whitespace() {
+            self.at += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, DiceError> {
+        let start = self.at;
+        let mut value: u32 = 0;
+        while let Some(b @ b'0'..=b'9') = self.peek() {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((b - b'0') as u32))
+                .ok_or(DiceError::Overflow)?;
+            self.at += 1;
+        }
+        Ok(if self.at == start { None } else { Some(value) })
+    }
+
+    fn term(&mut self) -> Result<Term, DiceError> {
+        self.skip_space();
+        let count = self.number()?;
+        if let Some(b'd' | b'D') = self.peek() {
+            self.at += 1;
+            let sides = if self.peek() == Some(b'%') {
+                self.at += 1;
+                100
+            } else {
+                self.number()?.ok_or(DiceError::UnexpectedChar(self.at))?
+            };
+            let count = count.unwrap_or(1);
+            if sides == 0 {
+                return Err(DiceError::ZeroSides);
+            }
+            if count > MAX_DICE_PER_TERM {
+                return Err(DiceError::TooManyDice);
+            }
+            return Ok(Term::Dice { count, sides });
+        }
+        match count {
+            Some(c) => Ok(Term::Constant(c as i64)),
+            None => Err(DiceError::UnexpectedChar(self.at)),
+        }
+    }
+}
+
+impl DiceExpr {
+    /// Parse an expression such as `3d6+2`
+    ///
+    /// Terms are either `NdM` dice, where `N` defaults to 1
+    /// and `d%` is a percentile die, or plain constants.
+    /// Terms are joined with `+` or `-`. No term may roll more than
+    /// `MAX_DICE_PER_TERM` dice, nor the expression more than `MAX_DICE`.
+    pub fn parse(expr: &str) -> Result<Self, DiceError> {
+        let mut parser = Parser {
+            bytes: expr.as_bytes(),
+            at: 0,
+        };
+        parser.skip_space();
+        if parser.peek().is_none() {
+            return Err(DiceError::Empty);
+        }
+        let mut terms = Vec::new();
+        let mut dice = 0;
+        let mut subtracted = false;
+        if let Some(sign @ (b'+' | b'-')) = parser.peek() {
+            subtracted = sign == b'-';
+            parser.at += 1;
+        }
+        loop {
+            let term = parser.term()?;
+            if let Term::Dice { count, .. } = term {
+                dice += count;
+                if dice > MAX_DICE {
+                    return Err(DiceError::TooManyDice);
+                }
+            }
+            terms.push((subtracted, term));
+            parser.skip_space();
+            match parser.peek() {
+                None => break,
+                Some(b'+') => subtracted = false,
+                Some(b'-') => subtracted = true,
+                Some(_) => return Err(DiceError::UnexpectedChar(parser.at)),
+            }
+            parser.at += 1;
+        }
+        Ok(DiceExpr { terms })
+    }
+
+    /// Roll every die in the expression
+    pub fn roll<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> DiceRoll {
+        let mut dice = Vec::new();
+        let mut total = 0i64;
+        let mut modifier = 0i64;
+        for (subtracted, term) in self.terms.iter() {
+            let sign = if *subtracted { -1 } else { 1 };
+            match *term {
+                Term::Dice { count, sides } => {
+                    for _ in 0..count {
+                        let value = rng.fair_roll(sides) + 1;
+                        total += sign * value as i64;
+                        dice.push(DieResult {
+                            sides,
+                            value,
+                            subtracted: *subtracted,
+                        });
+                    }
+                }
+                Term::Constant(c) => {
+                    total += sign * c;
+                    modifier += sign * c;
+                }
+            }
+        }
+        DiceRoll {
+            total,
+            dice,
+            modifier,
+        }
+    }
+}
+
+/// The outcome of rolling a pool of dice against a target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolRoll {
+    /// Each face rolled, from 1 to the number of sides
+    pub dice: Vec<u32>,
+    /// How many dice met or beat the target
+    pub successes: u32,
+    /// How many dice rolled a 1
+    pub ones: u32,
+}
+
+impl PoolRoll {
+    /// A botch is a roll with no successes
+    /// and at least one die showing a 1
+    pub fn is_botch(&self) -> bool {
+        self.successes == 0 && self.ones > 0
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Parse and roll a dice expression such as `3d6+2`
+    /// See `DiceExpr::parse` for the accepted notation
+    pub fn roll_expr(&mut self, expr: &str) -> Result<DiceRoll, DiceError> {
+        Ok(DiceExpr::parse(expr)?.roll(self))
+    }
+
+    /// Roll an exploding die, from 1 upward
+    ///
+    /// Each time the die shows its highest face
+    /// it is rolled again and the result added on.
+    /// A die with fewer than 2 sides can't explode,
+    /// and is returned as is.
+    pub fn roll_exploding(&mut self, sides: u32) -> u64 {
+        if sides < 2 {
+            return sides as u64;
+        }
+        let mut total = 0;
+        loop {
+            let face = self.fair_roll(sides) + 1;
+            total += face as u64;
+            if face != sides {
+                return total;
+            }
+        }
+    }
+
+    /// Roll `n` dice, giving each face and their sum
+    /// Faces run from 1 to `sides`
+    ///
+    /// 8d6 fireball damage is `roll_many(8, 6)`, with
+    /// the faces kept to show each die
+    pub fn roll_many(&mut self, n: u32, sides: u32) -> (Vec<u32>, u64) {
+        let dice = self.roll_faces(n, sides);
+        let total = dice.iter().map(|d| *d as u64).sum();
+        (dice, total)
+    }
+
+    /// Roll `count` dice and count those showing
+    /// at least `target`, along with any 1s
+    ///
+    /// For example, 5 d10 counting 7 and up is
+    /// `roll_pool(5, 10, 7)`
+    pub fn roll_pool(&mut self, count: u32, sides: u32, target: u32) -> PoolRoll {
+        let dice = self.roll_faces(count, sides);
+        let successes = dice.iter().filter(|d| **d >= target).count() as u32;
+        let ones = dice.iter().filter(|d| **d == 1).count() as u32;
+        PoolRoll {
+            dice,
+            successes,
+            ones,
+        }
+    }
+
+    /// Roll `n` dice and sum the highest `k` of them
+    /// Faces run from 1 to `sides`
+    ///
+    /// 4d6 drop lowest is `roll_keep_highest(4, 6, 3)`
+    pub fn roll_keep_highest(&mut self, n: u32, sides: u32, k: u32) -> u64 {
+        let mut dice = self.roll_faces(n, sides);
+        dice.sort_unstable_by(|a, b| b.cmp(a));
+        dice.iter().take(k as usize).map(|d| *d as u64).sum()
+    }
+
+    /// Roll `n` dice and sum the lowest `k` of them
+    /// Faces run from 1 to `sides`
+    pub fn roll_keep_lowest(&mut self, n: u32, sides: u32, k: u32) -> u64 {
+        let mut dice = self.roll_faces(n, sides);
+        dice.sort_unstable();
+        dice.iter().take(k as usize).map(|d| *d as u64).sum()
+    }
+
+    /// Roll two dice and keep the higher
+    /// Faces run from 1 to `sides`
+    pub fn roll_advantage(&mut self, sides: u32) -> u32 {
+        let a = self.fair_roll(sides) + 1;
+        let b = self.fair_roll(sides) + 1;
+        a.max(b)
+    }
+
+    /// Roll two dice and keep the lower
+    /// Faces run from 1 to `sides`
+    pub fn roll_disadvantage(&mut self, sides: u32) -> u32 {
+        let a = self.fair_roll(sides) + 1;
+        let b = self.fair_roll(sides) + 1;
+        a.min(b)
+    }
+
+    /// Roll a die from 0 to `sides - 1`, never landing
+    /// on any of the excluded faces
+    ///
+    /// Each remaining face is equally likely, and only one
+    /// fair roll is made. Returns `None` if every face
+    /// is excluded.
+    pub fn roll_excluding(&mut self, sides: u32, exclusions: &[u32]) -> Option<u32> {
+        let mut excluded: Vec<u32> = exclusions.iter().copied().filter(|e| *e < sides).collect();
+        excluded.sort_unstable();
+        excluded.dedup();
+        let remaining = sides - excluded.len() as u32;
+        if remaining == 0 {
+            return None;
+        }
+        // Walk the roll up past each excluded face below it
+        let mut face = self.fair_roll(remaining);
+        for e in excluded {
+            if e > face {
+                break;
+            }
+            face += 1;
+        }
+        Some(face)
+    }
+
+    /// Roll a die from 0 to `sides - 1`, never landing on any
+    /// of the excluded faces, which must be strictly increasing
+    ///
+    /// Gives the same result as `roll_excluding` from one fair
+    /// roll, but finds the face by a binary search instead of
+    /// sorting, in O(log k) time without allocating, so picking
+    /// a free slot of 10,000 with 9,000 taken stays fast. Faces
+    /// past the last side are ignored. Returns `None` if every
+    /// face is excluded.
+    ///
+    /// If the exclusions are not strictly increasing, the result
+    /// may land on one of them; debug builds panic instead.
+    pub fn roll_excluding_sorted(&mut self, sides: u32, exclusions: &[u32]) -> Option<u32> {
+        debug_assert!(
+            exclusions.windows(2).all(|w| w[0] < w[1]),
+            "Exclusions must be strictly increasing"
+        );
+        let excluded = &exclusions[..exclusions.partition_point(|e| *e < sides)];
+        let remaining = sides - excluded.len() as u32;
+        if remaining == 0 {
+            return None;
+        }
+        // The face is the roll plus the number of excluded faces
+        // below it, and `e - i` only grows along the exclusions
+        let roll = self.fair_roll(remaining);
+        let (mut low, mut high) = (0, excluded.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if excluded[mid] - mid as u32 <= roll {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Some(roll + low as u32)
+    }
+
+    fn roll_faces(&mut self, n: u32, sides: u32) -> Vec<u32> {
+        (0..n).map(|_| self.fair_roll(sides) + 1).collect()
+    }
+}
+
+/// A die which won't repeat any of its recent results
+///
+/// Useful for random encounters that should never
+/// come up twice in a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqueRoller {
+    sides: u32,
+    memory: usize,
+    recent: VecDeque<u32>,
+}
+
+impl UniqueRoller {
+    /// Create a roller over faces 0 to `sides - 1` which
+    /// avoids the last `memory` results
+    ///
+    /// The memory is capped at `sides - 1`, so there is
+    /// always a face left to roll.
+    pub fn new(sides: u32, memory: usize) -> Self {
+        let memory = memory.min(sides.saturating_sub(1) as usize);
+        UniqueRoller {
+            sides,
+            memory,
+            recent: VecDeque::with_capacity(memory),
+        }
+    }
+
+    /// Roll a face not among the recent results
+    /// A roller with zero sides always returns 0
+    pub fn roll<T: RngCore + SeedableRng>(&mut self, rng: &mut SplittingRng<T>) -> u32 {
+        let face = match rng.roll_excluding(self.sides, self.recent.make_contiguous()) {
+            Some(face) => face,
+            None => return 0,
+        };
+        if self.memory > 0 {
+            if self.recent.len() == self.memory {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(face);
+        }
+        face
+    }
+
+    /// Forget the recent results
+    pub fn reset(&mut self) {
+        self.recent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_roll_expr() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..1000 {
+            let roll = rng.roll_expr("3d6 + 2 - d4").unwrap();
+            assert_eq!(roll.dice.len(), 4);
+            assert_eq!(roll.modifier, 2);
+            let sum: i64 = roll
+                .dice
+                .iter()
+                .map(|d| {
+                    assert!(d.value >= 1 && d.value <= d.sides);
+                    if d.subtracted {
+                        -(d.value as i64)
+                    } else {
+                        d.value as i64
+                    }
+                })
+                .sum();
+            assert_eq!(roll.total, sum + 2);
+            assert!((0..=19).contains(&roll.total));
+        }
+        assert_eq!(DiceExpr::parse(" "), Err(DiceError::Empty));
+        assert_eq!(DiceExpr::parse("2d0"), Err(DiceError::ZeroSides));
+        assert_eq!(DiceExpr::parse("2d6+"), Err(DiceError::UnexpectedChar(4)));
+        assert_eq!(DiceExpr::parse("2x6"), Err(DiceError::UnexpectedChar(1)));
+        assert_eq!(DiceExpr::parse("10001d6"), Err(DiceError::TooManyDice));
+        let many = ["10000d6"; 10].join("+");
+        assert!(DiceExpr::parse(&many).is_ok());
+        assert_eq!(
+            DiceExpr::parse(&(many + "-d4")),
+            Err(DiceError::TooManyDice)
+        );
+    }
+
+    #[test]
+    fn test_exploding_and_pools() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        // An exploding d6 averages 3.5 * 6 / 5 = 4.2
+        let acc: u64 = (0..iter).map(|_| rng.roll_exploding(6)).sum();
+        let avg = acc as f64 / iter as f64;
+        assert!((avg - 4.2).abs() < 0.05, "{}", avg);
+        assert!((0..iter).all(|_| !rng.roll_exploding(6).is_multiple_of(6)));
+        assert_eq!(rng.roll_exploding(1), 1);
+
+        let mut botches = 0;
+        for _ in 0..iter {
+            let pool = rng.roll_pool(3, 10, 7);
+            assert_eq!(pool.dice.len(), 3);
+            if pool.is_botch() {
+                botches += 1;
+            }
+        }
+        // No dice at 7+ and not all of them 2-6:
+        // 0.6^3 - 0.5^3 = 0.091
+        let frac = botches as f64 / iter as f64;
+        assert!((frac - 0.091).abs() < 0.01, "{}", frac);
+    }
+
+    #[test]
+    fn test_keep_and_advantage() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        // Well known averages: 4d6 drop lowest is ~12.24,
+        // and a d20 with advantage is 13.825
+        let acc: u64 = (0..iter).map(|_| rng.roll_keep_highest(4, 6, 3)).sum();
+        let avg = acc as f64 / iter as f64;
+        assert!((avg - 12.24).abs() < 0.1, "{}", avg);
+        let acc: u32 = (0..iter).map(|_| rng.roll_advantage(20)).sum();
+        let avg = acc as f64 / iter as f64;
+        assert!((avg - 13.825).abs() < 0.15, "{}", avg);
+        let acc: u32 = (0..iter).map(|_| rng.roll_disadvantage(20)).sum();
+        let avg = acc as f64 / iter as f64;
+        assert!((avg - 7.175).abs() < 0.15, "{}", avg);
+        assert_eq!(rng.roll_keep_lowest(5, 1, 2), 2);
+    }
+
+    #[test]
+    fn test_roll_many() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let (dice, total) = rng.roll_many(8, 6);
+        assert_eq!(dice.len(), 8);
+        assert!(dice.iter().all(|d| (1..=6).contains(d)));
+        assert_eq!(total, dice.iter().map(|d| *d as u64).sum::<u64>());
+        assert_eq!(rng.roll_many(0, 6), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_excluding() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut counts = [0; 6];
+        let iter = 20000;
+        for _ in 0..iter {
+            counts[rng.roll_excluding(6, &[4, 1, 1, 9]).unwrap() as usize] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert_eq!(counts[4], 0);
+        for c in [counts[0], counts[2], counts[3], counts[5]] {
+            let frac = c as f64 / iter as f64;
+            assert!((frac - 0.25).abs() < 0.015, "{}", frac);
+        }
+        assert_eq!(rng.roll_excluding(2, &[0, 1]), None);
+
+        let mut roller = UniqueRoller::new(4, 2);
+        let mut last = [roller.roll(&mut rng), roller.roll(&mut rng)];
+        for _ in 0..1000 {
+            let face = roller.roll(&mut rng);
+            assert!(face < 4 && !last.contains(&face));
+            last = [last[1], face];
+        }
+    }
+
+    #[test]
+    fn test_roll_excluding_sorted() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let taken: Vec<u32> = (0..10000).filter(|i| i % 10 != 3).collect();
+        for _ in 0..100 {
+            let mut copy = rng.clone();
+            let free = rng.roll_excluding_sorted(10000, &taken).unwrap();
+            assert_eq!(free % 10, 3);
+            assert_eq!(copy.roll_excluding(10000, &taken), Some(free));
+        }
+        assert_eq!(rng.roll_excluding_sorted(2, &[0, 1, 5]), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_roll_excluding_sorted_checks_order() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for bad in [&[1, 1][..], &[3, 1]] {
+            let mut copy = rng.clone();
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                copy.roll_excluding_sorted(4, bad)
+            }));
+            assert!(result.is_err(), "{:?}", bad);
+        }
+        assert_eq!(rng.roll_excluding_sorted(2, &[1]), Some(0));
+    }
+}
@@ -0,0 +1,956 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Continuous and discrete distributions
+//! sampled from a `SplittingRng`.
+//!
+//! Each distribution validates its parameters
+//! once on construction, and can then be
+//! sampled as many times as needed.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// Something which can produce values of type `V`
+/// using a `SplittingRng` as the source of randomness
+pub trait Distribution<V> {
+    /// Draw a single value
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> V;
+}
+
+/// The reasons a distribution may refuse its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistributionError {
+    /// A parameter was NaN or infinite
+    NonFinite,
+    /// The minimum was not below the maximum,
+    /// or the mode was outside of them
+    BadBounds,
+    /// A shape, scale or spread parameter was not above zero
+    NonPositive,
+    /// No weights were provided
+    NoWeights,
+    /// A weight was below zero
+    NegativeWeight,
+    /// All of the weights were zero
+    ZeroTotal,
+    /// A probability was outside of [0, 1]
+    BadProbability,
+}
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributionError::NonFinite => write!(f, "parameter was NaN or infinite"),
+            DistributionError::BadBounds => write!(f, "bounds were out of order"),
+            DistributionError::NonPositive => write!(f, "parameter must be above zero"),
+            DistributionError::NoWeights => write!(f, "no weights were provided"),
+            DistributionError::NegativeWeight => write!(f, "a weight was below zero"),
+            DistributionError::ZeroTotal => write!(f, "the weights sum to zero"),
+            DistributionError::BadProbability => write!(f, "probability was outside [0, 1]"),
+        }
+    }
+}
+
+impl std::error::Error for DistributionError {}
+
+fn check_three_point(min: f64, mode: f64, max: f64) -> Result<(), DistributionError> {
+    if !(min.is_finite() && mode.is_finite() && max.is_finite()) {
+        return Err(DistributionError::NonFinite);
+    }
+    if !(min < max && min <= mode && mode <= max) {
+        return Err(DistributionError::BadBounds);
+    }
+    Ok(())
+}
+
+/// The triangular distribution over [min, max]
+/// peaking at `mode`
+///
+/// Useful when all that is known about a
+/// quantity is a rough three-point estimate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangular {
+    min: f64,
+    mode: f64,
+    max: f64,
+    // The CDF at the mode, where the two halves meet
+    split: f64,
+}
+
+impl Triangular {
+    /// Create a triangular distribution
+    /// Requires `min <= mode <= max` and `min < max`
+    pub fn new(min: f64, mode: f64, max: f64) -> Result<Self, DistributionError> {
+        check_three_point(min, mode, max)?;
+        Ok(Triangular {
+            min,
+            mode,
+            max,
+            split: (mode - min) / (max - min),
+        })
+    }
+}
+
+impl Distribution<f64> for Triangular {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        // Invert the CDF of whichever half we land in
+        let u = rng.get_f64();
+        let width = self.max - self.min;
+        if u < self.split {
+            self.min + (u * width * (self.mode - self.min)).sqrt()
+        } else {
+            self.max - ((1.0 - u) * width * (self.max - self.mode)).sqrt()
+        }
+    }
+}
+
+/// The PERT distribution over [min, max]
+/// with the most likely value at `mode`
+///
+/// A smoother alternative to `Triangular`,
+/// with less weight in the tails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pert {
+    min: f64,
+    max: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl Pert {
+    /// Create a PERT distribution
+    /// Requires `min <= mode <= max` and `min < max`
+    pub fn new(min: f64, mode: f64, max: f64) -> Result<Self, DistributionError> {
+        check_three_point(min, mode, max)?;
+        let width = max - min;
+        Ok(Pert {
+            min,
+            max,
+            alpha: 1.0 + 4.0 * (mode - min) / width,
+            beta: 1.0 + 4.0 * (max - mode) / width,
+        })
+    }
+}
+
+impl Distribution<f64> for Pert {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        self.min + (self.max - self.min) * beta(rng, self.alpha, self.beta)
+    }
+}
+
+fn check_positive(values: &[f64]) -> Result<(), DistributionError> {
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(DistributionError::NonFinite);
+    }
+    if values.iter().any(|v| *v <= 0.0) {
+        return Err(DistributionError::NonPositive);
+    }
+    Ok(())
+}
+
+/// The normal distribution with a given mean
+/// and standard deviation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normal {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normal {
+    /// Create a normal distribution
+    /// Requires a standard deviation above zero
+    pub fn new(mean: f64, std_dev: f64) -> Result<Self, DistributionError> {
+        if !mean.is_finite() {
+            return Err(DistributionError::NonFinite);
+        }
+        check_positive(&[std_dev])?;
+        Ok(Normal { mean, std_dev })
+    }
+}
+
+impl Normal {
+    /// Draw two independent values, which takes the same
+    /// two steps as a single `sample`
+    pub fn sample_pair<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> (f64, f64) {
+        let (a, b) = standard_normal_pair(rng);
+        (self.mean + self.std_dev * a, self.mean + self.std_dev * b)
+    }
+}
+
+impl Distribution<f64> for Normal {
+    /// Always takes two steps, as the second value of the
+    /// pair is discarded rather than kept for the next call
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        self.mean + self.std_dev * standard_normal(rng)
+    }
+}
+
+/// A normal distribution which keeps the second value
+/// of each pair for the next call
+///
+/// Takes two steps for every other value, rather than two for
+/// each, so a value's step count depends on what came before.
+/// Lockstep code should `clear` it at fixed points, or use
+/// `Normal` or `get_normal_pair`, which never hold a value back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedNormal {
+    normal: Normal,
+    spare: Option<f64>,
+}
+
+impl CachedNormal {
+    /// Start with nothing held back
+    pub fn new(normal: Normal) -> Self {
+        CachedNormal {
+            normal,
+            spare: None,
+        }
+    }
+
+    /// Provide the held back value, or draw a new pair
+    pub fn next<T: RngCore + SeedableRng>(&mut self, rng: &mut SplittingRng<T>) -> f64 {
+        match self.spare.take() {
+            Some(value) => value,
+            None => {
+                let (a, b) = self.normal.sample_pair(rng);
+                self.spare = Some(b);
+                a
+            }
+        }
+    }
+
+    /// Whether a value is held back, so the next call won't step
+    pub fn has_spare(&self) -> bool {
+        self.spare.is_some()
+    }
+
+    /// Drop any held back value
+    pub fn clear(&mut self) {
+        self.spare = None;
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide both standard normal values of a Box-Muller pair,
+    /// from exactly two steps
+    pub fn get_normal_pair(&mut self) -> (f64, f64) {
+        standard_normal_pair(self)
+    }
+}
+
+/// The log-normal distribution, where the log
+/// of each value is normal with the given
+/// mean and standard deviation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogNormal {
+    normal: Normal,
+}
+
+impl LogNormal {
+    /// Create a log-normal distribution from the parameters
+    /// of the underlying normal distribution
+    pub fn new(mu: f64, sigma: f64) -> Result<Self, DistributionError> {
+        Ok(LogNormal {
+            normal: Normal::new(mu, sigma)?,
+        })
+    }
+}
+
+impl Distribution<f64> for LogNormal {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        self.normal.sample(rng).exp()
+    }
+}
+
+/// The gamma distribution with a given shape (k)
+/// and scale (theta)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gamma {
+    shape: f64,
+    scale: f64,
+}
+
+impl Gamma {
+    /// Create a gamma distribution
+    /// Requires both parameters to be above zero
+    pub fn new(shape: f64, scale: f64) -> Result<Self, DistributionError> {
+        check_positive(&[shape, scale])?;
+        Ok(Gamma { shape, scale })
+    }
+}
+
+impl Distribution<f64> for Gamma {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        self.scale * standard_gamma(rng, self.shape)
+    }
+}
+
+/// The exponential distribution with a given rate,
+/// the waiting time between events of a Poisson process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exponential {
+    rate: f64,
+}
+
+impl Exponential {
+    /// Create an exponential distribution, with a mean of `1 / rate`
+    /// Requires a rate above zero
+    pub fn new(rate: f64) -> Result<Self, DistributionError> {
+        check_positive(&[rate])?;
+        Ok(Exponential { rate })
+    }
+}
+
+impl Distribution<f64> for Exponential {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        -open_unit(rng).ln() / self.rate
+    }
+}
+
+/// The beta distribution over [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beta {
+    alpha: f64,
+    beta: f64,
+}
+
+impl Beta {
+    /// Create a beta distribution
+    /// Requires both parameters to be above zero
+    pub fn new(alpha: f64, beta: f64) -> Result<Self, DistributionError> {
+        check_positive(&[alpha, beta])?;
+        Ok(Beta { alpha, beta })
+    }
+}
+
+impl Distribution<f64> for Beta {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> f64 {
+        beta(rng, self.alpha, self.beta)
+    }
+}
+
+/// The Dirichlet distribution, over weights which are
+/// non-negative and sum to 1
+///
+/// Each concentration above 1 pulls the weights towards even
+/// shares, and each below 1 pushes them towards a few large ones.
+/// A concentration of 1 for every weight is uniform over all
+/// ways to split 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dirichlet {
+    concentrations: Vec<f64>,
+}
+
+impl Dirichlet {
+    /// Create a Dirichlet distribution with one concentration per weight
+    /// Requires at least one concentration, and all to be above zero
+    pub fn new(concentrations: &[f64]) -> Result<Self, DistributionError> {
+        if concentrations.is_empty() {
+            return Err(DistributionError::NoWeights);
+        }
+        check_positive(concentrations)?;
+        Ok(Dirichlet {
+            concentrations: concentrations.to_vec(),
+        })
+    }
+
+    /// Create a Dirichlet distribution over `k` weights,
+    /// all with the same concentration
+    pub fn symmetric(k: usize, concentration: f64) -> Result<Self, DistributionError> {
+        Self::new(&vec![concentration; k])
+    }
+}
+
+impl Distribution<Vec<f64>> for Dirichlet {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> Vec<f64> {
+        let mut weights: Vec<f64> = self
+            .concentrations
+            .iter()
+            .map(|a| standard_gamma(rng, *a))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.iter_mut().for_each(|w| *w /= total);
+        } else {
+            // Tiny concentrations can underflow every gamma,
+            // in the limit all the weight goes to one
+            let index = rng.below_u128(weights.len() as u128) as usize;
+            weights[index] = 1.0;
+        }
+        weights
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide `k` weights which are non-negative and sum to 1,
+    /// uniformly over all ways to split 1
+    ///
+    /// Use `Dirichlet` to make the weights more or less even.
+    pub fn get_weights(&mut self, k: usize) -> Vec<f64> {
+        match Dirichlet::symmetric(k, 1.0) {
+            Ok(dirichlet) => dirichlet.sample(self),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Choose an index with probability in proportion to
+    /// `exp(score / temperature)`, a softmax over the scores
+    ///
+    /// The highest score is subtracted first, so large scores
+    /// don't overflow. Higher temperatures even out the choice,
+    /// and a temperature of zero always picks the first highest
+    /// score. A score of negative infinity is never picked.
+    /// Always takes one step, so annealing the temperature down
+    /// to zero doesn't shift later draws.
+    pub fn softmax_sample(
+        &mut self,
+        scores: &[f64],
+        temperature: f64,
+    ) -> Result<usize, DistributionError> {
+        if scores.is_empty() {
+            return Err(DistributionError::NoWeights);
+        }
+        if !temperature.is_finite() || scores.iter().any(|s| s.is_nan() || *s == f64::INFINITY) {
+            return Err(DistributionError::NonFinite);
+        }
+        if temperature < 0.0 {
+            return Err(DistributionError::NonPositive);
+        }
+        let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if max == f64::NEG_INFINITY {
+            return Err(DistributionError::ZeroTotal);
+        }
+        if temperature == 0.0 {
+            self.get_f64();
+            return Ok(scores.iter().position(|s| *s == max).unwrap_or(0));
+        }
+        let weights: Vec<f64> = scores
+            .iter()
+            .map(|s| ((s - max) / temperature).exp())
+            .collect();
+        Ok(DiscreteDistribution::new(&weights)?.sample(self))
+    }
+}
+
+/// A distribution over the indices of a weight table
+///
+/// The weights are normalized into a cumulative table
+/// once, so each sample is a single float and a binary search.
+/// Entries with zero weight are never chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteDistribution {
+    cdf: Vec<f64>,
+}
+
+impl DiscreteDistribution {
+    /// Create a distribution from a list of weights
+    /// The weights need not sum to 1
+    pub fn new(weights: &[f64]) -> Result<Self, DistributionError> {
+        if weights.is_empty() {
+            return Err(DistributionError::NoWeights);
+        }
+        if weights.iter().any(|w| !w.is_finite()) {
+            return Err(DistributionError::NonFinite);
+        }
+        if weights.iter().any(|w| *w < 0.0) {
+            return Err(DistributionError::NegativeWeight);
+        }
+        let mut running = 0.0;
+        let mut cdf: Vec<f64> = weights
+            .iter()
+            .map(|w| {
+                running += w;
+                running
+            })
+            .collect();
+        if running <= 0.0 {
+            return Err(DistributionError::ZeroTotal);
+        }
+        if !running.is_finite() {
+            return Err(DistributionError::NonFinite);
+        }
+        // The last nonzero entry divides exactly to 1
+        for c in cdf.iter_mut() {
+            *c /= running;
+        }
+        Ok(DiscreteDistribution { cdf })
+    }
+
+    /// The normalized probability of choosing an index
+    /// Indices outside the table have no chance
+    pub fn probability(&self, index: usize) -> f64 {
+        match index {
+            0 => self.cdf.first().copied().unwrap_or(0.0),
+            i if i < self.cdf.len() => self.cdf[i] - self.cdf[i - 1],
+            _ => 0.0,
+        }
+    }
+}
+
+impl Distribution<usize> for DiscreteDistribution {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> usize {
+        let u = rng.get_f64();
+        // Zero-weight entries share a bound with their
+        // predecessor, so they are always stepped over
+        self.cdf
+            .partition_point(|c| *c <= u)
+            .min(self.cdf.len() - 1)
+    }
+}
+
+/// A weighted choice of index in constant time, using
+/// Vose's alias method
+///
+/// Building the table takes time in proportion to the number
+/// of weights, but every sample is a fair roll and one float,
+/// however many weights there are. Prefer this to a
+/// `DiscreteDistribution` for large tables sampled often.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasTable {
+    // The chance of keeping each index rather than its alias
+    keep: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl AliasTable {
+    /// Create a table from a list of weights
+    /// The weights need not sum to 1
+    pub fn new(weights: &[f64]) -> Result<Self, DistributionError> {
+        // Shares the validation of the cumulative table
+        DiscreteDistribution::new(weights)?;
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let mut keep = vec![1.0; n];
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|i| scaled[*i] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            keep[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Whatever is left over is 1 up to rounding, unless rounding
+        // emptied the large list early, when zero weights must still
+        // never be kept
+        if let Some(heaviest) = (0..n).max_by(|a, b| weights[*a].total_cmp(&weights[*b])) {
+            for s in small.into_iter().filter(|s| weights[*s] == 0.0) {
+                keep[s] = 0.0;
+                alias[s] = heaviest;
+            }
+        }
+        Ok(AliasTable { keep, alias })
+    }
+
+    /// The number of indices
+    pub fn len(&self) -> usize {
+        self.keep.len()
+    }
+
+    /// Whether there are no indices, which is never true
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_empty()
+    }
+}
+
+impl Distribution<usize> for AliasTable {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> usize {
+        let column = rng.below_u128(self.keep.len() as u128) as usize;
+        if rng.get_f64() < self.keep[column] {
+            column
+        } else {
+            self.alias[column]
+        }
+    }
+}
+
+/// A biased coin which is true with probability `p`
+///
+/// The probability is converted to an integer threshold
+/// once, so each sample is one step and one comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bernoulli {
+    // None means the coin always lands true
+    threshold: Option<u64>,
+}
+
+impl Bernoulli {
+    /// Create a coin which is true with probability `p`
+    /// Requires `p` to be within [0, 1]
+    pub fn new(p: f64) -> Result<Self, DistributionError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(DistributionError::BadProbability);
+        }
+        if p == 1.0 {
+            return Ok(Bernoulli { threshold: None });
+        }
+        // p < 1, so this cannot overflow
+        let threshold = (p * 2f64.powi(64)) as u64;
+        Ok(Bernoulli {
+            threshold: Some(threshold),
+        })
+    }
+}
+
+impl Distribution<bool> for Bernoulli {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> bool {
+        // Always step, so the stream position doesn't
+        // depend on the probability
+        let step = rng.get_u64();
+        match self.threshold {
+            Some(threshold) => step < threshold,
+            None => true,
+        }
+    }
+}
+
+/// A coin weighted by whole-number odds, such as 3 to 17
+///
+/// The odds become a 32-bit threshold once, in integer arithmetic,
+/// so each flip is a compare of 32 bits with no float and no branch
+/// on the odds. `flip` always takes one step, and `flip_two` gets
+/// two flips from one step, at 30 bits each so that the low bits
+/// of the step are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BiasedCoin {
+    // Flips below this, out of 2^32, land true
+    threshold: u64,
+}
+
+impl BiasedCoin {
+    /// Create a coin which lands true `wins` times for every
+    /// `losses` times it lands false, so `from_odds(3, 17)`
+    /// is true 3 times in 20
+    /// Requires the odds not to both be zero
+    pub fn from_odds(wins: u32, losses: u32) -> Result<Self, DistributionError> {
+        let total = wins as u64 + losses as u64;
+        if total == 0 {
+            return Err(DistributionError::ZeroTotal);
+        }
+        Ok(BiasedCoin {
+            threshold: ((wins as u64) << 32) / total,
+        })
+    }
+
+    /// The chance of landing true, to 32 bits
+    pub fn probability(&self) -> f64 {
+        self.threshold as f64 / (1u64 << 32) as f64
+    }
+
+    fn lands(&self, lane: u32) -> bool {
+        (lane as u64) < self.threshold
+    }
+
+    /// Flip once, from the high half of one step
+    pub fn flip<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> bool {
+        self.lands((rng.get_u64() >> 32) as u32)
+    }
+
+    /// Flip twice, from two 30-bit lanes in the high 60 bits of one step
+    ///
+    /// Each lane stands for the top 30 of 32 bits, so the chance
+    /// of each flip is within 2^-30 of `probability`.
+    pub fn flip_two<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> [bool; 2] {
+        // Take the high bits, as the low bits are low entropy
+        let step = rng.get_u64() >> 4;
+        let lane = |bits: u64| ((bits & ((1 << 30) - 1)) << 2) as u32;
+        [self.lands(lane(step >> 30)), self.lands(lane(step))]
+    }
+}
+
+impl Distribution<bool> for BiasedCoin {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> bool {
+        self.flip(rng)
+    }
+}
+
+/// A float in (0, 1], safe to take the log of
+fn open_unit<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> f64 {
+    1.0 - rng.get_f64()
+}
+
+/// A standard normal value, using Box-Muller
+/// The second value of the pair is discarded
+pub(crate) fn standard_normal<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> f64 {
+    standard_normal_pair(rng).0
+}
+
+/// Both standard normal values of a Box-Muller pair
+fn standard_normal_pair<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> (f64, f64) {
+    let radius = (-2.0 * open_unit(rng).ln()).sqrt();
+    let theta = std::f64::consts::TAU * rng.get_f64();
+    (radius * theta.cos(), radius * theta.sin())
+}
+
+/// A gamma value with unit scale
+/// Uses Marsaglia and Tsang's method
+fn standard_gamma<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, shape: f64) -> f64 {
+    if shape < 1.0 {
+        // Boost the shape above 1, then correct for it
+        let boosted = standard_gamma(rng, shape + 1.0);
+        return boosted * open_unit(rng).powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = 1.0 + c * x;
+        if v <= 0.0 {
+            continue;
+        }
+        let v = v * v * v;
+        let u = open_unit(rng);
+        let x2 = x * x;
+        // The cheap squeeze accepts most candidates
+        if u < 1.0 - 0.0331 * x2 * x2 || u.ln() < 0.5 * x2 + d * (1.0 - v + v.ln()) {
+            return d * v;
+        }
+    }
+}
+
+/// A beta value, as the ratio of two gammas
+fn beta<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, alpha: f64, beta: f64) -> f64 {
+    let x = standard_gamma(rng, alpha);
+    let y = standard_gamma(rng, beta);
+    x / (x + y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_three_point_means() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let tri = Triangular::new(0.0, 3.0, 9.0).unwrap();
+        let pert = Pert::new(0.0, 3.0, 9.0).unwrap();
+        let iter = 20000;
+        let mut tri_acc = 0.0;
+        let mut pert_acc = 0.0;
+        for _ in 0..iter {
+            let t = rng.sample(&tri);
+            let p = rng.sample(&pert);
+            assert!((0.0..=9.0).contains(&t));
+            assert!((0.0..=9.0).contains(&p));
+            tri_acc += t;
+            pert_acc += p;
+        }
+        // Triangular mean is (min + mode + max) / 3 = 4
+        // PERT mean is (min + 4 * mode + max) / 6 = 3.5
+        let tri_avg = tri_acc / iter as f64;
+        let pert_avg = pert_acc / iter as f64;
+        assert!((tri_avg - 4.0).abs() < 0.05, "{}", tri_avg);
+        assert!((pert_avg - 3.5).abs() < 0.05, "{}", pert_avg);
+        assert_eq!(
+            Triangular::new(1.0, 0.0, 2.0),
+            Err(DistributionError::BadBounds)
+        );
+    }
+
+    #[test]
+    fn test_gamma_family_means() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        // Mean of gamma is shape * scale, including shapes below 1
+        for (shape, scale) in [(0.5, 2.0), (3.0, 1.5)] {
+            let gamma = Gamma::new(shape, scale).unwrap();
+            let avg = (0..iter).map(|_| rng.sample(&gamma)).sum::<f64>() / iter as f64;
+            assert!((avg - shape * scale).abs() < 0.1, "{}", avg);
+        }
+        // Mean of beta is alpha / (alpha + beta)
+        let beta = Beta::new(2.0, 6.0).unwrap();
+        let avg = (0..iter).map(|_| rng.sample(&beta)).sum::<f64>() / iter as f64;
+        assert!((avg - 0.25).abs() < 0.01, "{}", avg);
+        // Median of log-normal is e^mu
+        let log_normal = LogNormal::new(1.0, 0.5).unwrap();
+        let below = (0..iter)
+            .filter(|_| rng.sample(&log_normal) < 1f64.exp())
+            .count();
+        let frac = below as f64 / iter as f64;
+        assert!((frac - 0.5).abs() < 0.02, "{}", frac);
+        assert_eq!(Gamma::new(0.0, 1.0), Err(DistributionError::NonPositive));
+    }
+
+    #[test]
+    fn test_exponential() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        // Mean of exponential is 1 / rate
+        let exp = Exponential::new(4.0).unwrap();
+        let avg = (0..iter).map(|_| rng.sample(&exp)).sum::<f64>() / iter as f64;
+        assert!((avg - 0.25).abs() < 0.01, "{}", avg);
+        assert_eq!(Exponential::new(0.0), Err(DistributionError::NonPositive));
+    }
+
+    #[test]
+    fn test_discrete_distribution() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let dist = DiscreteDistribution::new(&[1.0, 0.0, 3.0, 0.0]).unwrap();
+        assert_eq!(dist.probability(2), 0.75);
+        let iter = 20000;
+        let mut counts = [0; 4];
+        for _ in 0..iter {
+            counts[rng.sample(&dist)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert_eq!(counts[3], 0);
+        let frac = counts[0] as f64 / iter as f64;
+        assert!((frac - 0.25).abs() < 0.01, "{}", frac);
+        assert_eq!(
+            DiscreteDistribution::new(&[0.0, 0.0]),
+            Err(DistributionError::ZeroTotal)
+        );
+        assert_eq!(
+            DiscreteDistribution::new(&[1.0, -1.0]),
+            Err(DistributionError::NegativeWeight)
+        );
+    }
+
+    #[test]
+    fn test_softmax_sample() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        // Scores this large overflow if exponentiated directly
+        let scores = [1000.0, 1000.0 + 2f64.ln(), f64::NEG_INFINITY];
+        let iter = 20000;
+        let mut counts = [0; 3];
+        for _ in 0..iter {
+            counts[rng.softmax_sample(&scores, 1.0).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        let frac = counts[1] as f64 / iter as f64;
+        assert!((frac - 2.0 / 3.0).abs() < 0.01, "{}", frac);
+        let before = rng.steps();
+        assert_eq!(rng.softmax_sample(&[1.0, 3.0, 3.0], 0.0), Ok(1));
+        assert_eq!(rng.steps() - before, 1);
+        assert_eq!(
+            rng.softmax_sample(&[f64::NEG_INFINITY], 1.0),
+            Err(DistributionError::ZeroTotal)
+        );
+        assert_eq!(
+            rng.softmax_sample(&[1.0], -1.0),
+            Err(DistributionError::NonPositive)
+        );
+        assert_eq!(
+            rng.softmax_sample(&[f64::NAN], 1.0),
+            Err(DistributionError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn test_alias_table() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let table = AliasTable::new(&[1.0, 0.0, 3.0, 4.0]).unwrap();
+        let mut counts = [0; 4];
+        let iter = 40000;
+        for _ in 0..iter {
+            counts[rng.sample(&table)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        for (count, p) in counts.iter().zip([0.125, 0.0, 0.375, 0.5]) {
+            let frac = *count as f64 / iter as f64;
+            assert!((frac - p).abs() < 0.01, "{:?}", counts);
+        }
+        assert_eq!(AliasTable::new(&[]), Err(DistributionError::NoWeights));
+    }
+
+    #[test]
+    fn test_bernoulli() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let coin = Bernoulli::new(0.1).unwrap();
+        let iter = 20000;
+        let hits = (0..iter).filter(|_| rng.sample(&coin)).count();
+        let frac = hits as f64 / iter as f64;
+        assert!((frac - 0.1).abs() < 0.01, "{}", frac);
+        let never = Bernoulli::new(0.0).unwrap();
+        let always = Bernoulli::new(1.0).unwrap();
+        assert!((0..100).all(|_| !rng.sample(&never) && rng.sample(&always)));
+        assert_eq!(Bernoulli::new(1.5), Err(DistributionError::BadProbability));
+    }
+
+    #[test]
+    fn test_dirichlet_weights() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let even = Dirichlet::symmetric(4, 50.0).unwrap();
+        let lumpy = Dirichlet::symmetric(4, 0.1).unwrap();
+        let iter = 5000;
+        let (mut even_max, mut lumpy_max, mut first) = (0.0, 0.0, 0.0);
+        for _ in 0..iter {
+            let w = rng.get_weights(3);
+            assert!((w.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(w.iter().all(|x| *x >= 0.0));
+            first += w[0];
+            let e = rng.sample(&even);
+            let l = rng.sample(&lumpy);
+            even_max += e.iter().cloned().fold(0.0, f64::max);
+            lumpy_max += l.iter().cloned().fold(0.0, f64::max);
+        }
+        let first = first / iter as f64;
+        assert!((first - 1.0 / 3.0).abs() < 0.01, "{}", first);
+        assert!(even_max / (iter as f64) < 0.35);
+        assert!(lumpy_max / (iter as f64) > 0.8);
+        assert!(rng.get_weights(0).is_empty());
+        assert_eq!(
+            Dirichlet::new(&[1.0, 0.0]),
+            Err(DistributionError::NonPositive)
+        );
+    }
+
+    #[test]
+    fn test_biased_coin() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let coin = BiasedCoin::from_odds(3, 17).unwrap();
+        assert!((coin.probability() - 0.15).abs() < 1e-9);
+        let iter = 20000;
+        let mut hits = 0;
+        for _ in 0..iter / 2 {
+            hits += coin.flip_two(&mut rng).iter().filter(|b| **b).count();
+        }
+        assert_eq!(rng.steps(), iter as u64 / 2);
+        let frac = hits as f64 / iter as f64;
+        assert!((frac - 0.15).abs() < 0.01, "{}", frac);
+        let always = BiasedCoin::from_odds(1, 0).unwrap();
+        let never = BiasedCoin::from_odds(0, 5).unwrap();
+        assert!((0..100).all(|_| always.flip(&mut rng) && !rng.sample(&never)));
+        assert!((0..100).all(|_| always.flip_two(&mut rng) == [true; 2]));
+        assert!((0..100).all(|_| never.flip_two(&mut rng) == [false; 2]));
+        assert_eq!(
+            BiasedCoin::from_odds(0, 0),
+            Err(DistributionError::ZeroTotal)
+        );
+    }
+
+    #[test]
+    fn test_normal_pairs() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let (a, _) = rng.get_normal_pair();
+        assert_eq!(a, copy.sample(&normal));
+        assert_eq!(rng.steps(), copy.steps());
+        let iter = 10000;
+        let mut product = 0.0;
+        for _ in 0..iter {
+            let (x, y) = rng.get_normal_pair();
+            product += x * y;
+        }
+        // The two values of a pair are uncorrelated
+        assert!((product / iter as f64).abs() < 0.03, "{}", product);
+        let mut cached = CachedNormal::new(Normal::new(10.0, 2.0).unwrap());
+        let before = rng.steps();
+        cached.next(&mut rng);
+        assert!(cached.has_spare());
+        cached.next(&mut rng);
+        assert_eq!(rng.steps() - before, 2);
+        cached.next(&mut rng);
+        cached.clear();
+        assert!(!cached.has_spare());
+    }
+}
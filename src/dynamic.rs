@@ -0,0 +1,241 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! An object-safe trait over the core methods, so an engine
+//! can take a real, scripted or played back rng behind one
+//! `Box<dyn SplittableRandom>`.
+//!
+//! Generic code can take `impl SplittableRandom` instead, and
+//! boxes implement the trait too, so both styles mix.
+
+use crate::compat::BiasedRoll;
+use crate::mock::MockSplittingRng;
+use crate::playback::Playback;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// The core methods of `SplittingRng`, without generics
+pub trait SplittableRandom {
+    /// As `SplittingRng::get_bool`
+    fn get_bool(&mut self) -> bool;
+    /// As `SplittingRng::get_u32`
+    fn get_u32(&mut self) -> u32;
+    /// As `SplittingRng::get_u64`
+    fn get_u64(&mut self) -> u64;
+    /// As `SplittingRng::get_f64`
+    fn get_f64(&mut self) -> f64;
+    /// As `SplittingRng::biased_roll`
+    fn biased_roll(&mut self, sides: u32) -> u32;
+    /// As `SplittingRng::fair_roll`
+    fn fair_roll(&mut self, sides: u32) -> u32;
+    /// As `SplittingRng::roll`
+    fn roll(&mut self, sides: u32) -> u32;
+    /// The order `shuffle` would give a list of `len` items,
+    /// as the index each item is taken from
+    fn shuffle_indices(&mut self, len: usize) -> Vec<usize>;
+    /// As `SplittingRng::split`, boxed
+    fn split_boxed(&mut self) -> Box<dyn SplittableRandom>;
+}
+
+impl dyn SplittableRandom + '_ {
+    /// Shuffle a list, as `SplittingRng::shuffle`
+    pub fn shuffle<L: Copy>(&mut self, list: &[L]) -> Vec<L> {
+        self.shuffle_indices(list.len())
+            .into_iter()
+            .map(|i| list[i])
+            .collect()
+    }
+}
+
+impl<T: RngCore + SeedableRng + 'static> SplittableRandom for SplittingRng<T> {
+    fn get_bool(&mut self) -> bool {
+        SplittingRng::get_bool(self)
+    }
+
+    fn get_u32(&mut self) -> u32 {
+        SplittingRng::get_u32(self)
+    }
+
+    fn get_u64(&mut self) -> u64 {
+        SplittingRng::get_u64(self)
+    }
+
+    fn get_f64(&mut self) -> f64 {
+        SplittingRng::get_f64(self)
+    }
+
+    fn biased_roll(&mut self, sides: u32) -> u32 {
+        BiasedRoll::biased_roll(self, sides)
+    }
+
+    fn fair_roll(&mut self, sides: u32) -> u32 {
+        SplittingRng::fair_roll(self, sides)
+    }
+
+    fn roll(&mut self, sides: u32) -> u32 {
+        SplittingRng::roll(self, sides)
+    }
+
+    fn shuffle_indices(&mut self, len: usize) -> Vec<usize> {
+        let indices: Vec<usize> = (0..len).collect();
+        self.shuffle(&indices)
+    }
+
+    fn split_boxed(&mut self) -> Box<dyn SplittableRandom> {
+        Box::new(self.split())
+    }
+}
+
+impl SplittableRandom for MockSplittingRng {
+    fn get_bool(&mut self) -> bool {
+        MockSplittingRng::get_bool(self)
+    }
+
+    fn get_u32(&mut self) -> u32 {
+        MockSplittingRng::get_u32(self)
+    }
+
+    fn get_u64(&mut self) -> u64 {
+        MockSplittingRng::get_u64(self)
+    }
+
+    fn get_f64(&mut self) -> f64 {
+        MockSplittingRng::get_f64(self)
+    }
+
+    fn biased_roll(&mut self, sides: u32) -> u32 {
+        MockSplittingRng::biased_roll(self, sides)
+    }
+
+    fn fair_roll(&mut self, sides: u32) -> u32 {
+        MockSplittingRng::fair_roll(self, sides)
+    }
+
+    fn roll(&mut self, sides: u32) -> u32 {
+        MockSplittingRng::roll(self, sides)
+    }
+
+    fn shuffle_indices(&mut self, len: usize) -> Vec<usize> {
+        let indices: Vec<usize> = (0..len).collect();
+        self.shuffle(&indices)
+    }
+
+    fn split_boxed(&mut self) -> Box<dyn SplittableRandom> {
+        Box::new(self.split())
+    }
+}
+
+impl<T: RngCore + SeedableRng + 'static> SplittableRandom for Playback<T> {
+    fn get_bool(&mut self) -> bool {
+        Playback::get_bool(self)
+    }
+
+    fn get_u32(&mut self) -> u32 {
+        Playback::get_u32(self)
+    }
+
+    fn get_u64(&mut self) -> u64 {
+        Playback::get_u64(self)
+    }
+
+    fn get_f64(&mut self) -> f64 {
+        Playback::get_f64(self)
+    }
+
+    fn biased_roll(&mut self, sides: u32) -> u32 {
+        Playback::biased_roll(self, sides)
+    }
+
+    fn fair_roll(&mut self, sides: u32) -> u32 {
+        Playback::fair_roll(self, sides)
+    }
+
+    fn roll(&mut self, sides: u32) -> u32 {
+        Playback::roll(self, sides)
+    }
+
+    fn shuffle_indices(&mut self, len: usize) -> Vec<usize> {
+        let indices: Vec<usize> = (0..len).collect();
+        self.shuffle(&indices)
+    }
+
+    fn split_boxed(&mut self) -> Box<dyn SplittableRandom> {
+        Box::new(self.split())
+    }
+}
+
+impl<R: SplittableRandom + ?Sized> SplittableRandom for Box<R> {
+    fn get_bool(&mut self) -> bool {
+        (**self).get_bool()
+    }
+
+    fn get_u32(&mut self) -> u32 {
+        (**self).get_u32()
+    }
+
+    fn get_u64(&mut self) -> u64 {
+        (**self).get_u64()
+    }
+
+    fn get_f64(&mut self) -> f64 {
+        (**self).get_f64()
+    }
+
+    fn biased_roll(&mut self, sides: u32) -> u32 {
+        (**self).biased_roll(sides)
+    }
+
+    fn fair_roll(&mut self, sides: u32) -> u32 {
+        (**self).fair_roll(sides)
+    }
+
+    fn roll(&mut self, sides: u32) -> u32 {
+        (**self).roll(sides)
+    }
+
+    fn shuffle_indices(&mut self, len: usize) -> Vec<usize> {
+        (**self).shuffle_indices(len)
+    }
+
+    fn split_boxed(&mut self) -> Box<dyn SplittableRandom> {
+        (**self).split_boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::Scripted;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    fn attack(rng: &mut dyn SplittableRandom) -> u32 {
+        1 + rng.fair_roll(20)
+    }
+
+    #[test]
+    fn test_boxed_rngs() {
+        let mut real = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = real.clone();
+        let mut rngs: Vec<Box<dyn SplittableRandom>> = vec![
+            Box::new(real.clone()),
+            Box::new(MockSplittingRng::new(vec![
+                Scripted::Roll(19),
+                Scripted::Roll(3),
+            ])),
+        ];
+        assert_eq!(attack(rngs[0].as_mut()), 1 + copy.fair_roll(20));
+        assert_eq!(attack(rngs[1].as_mut()), 20);
+        assert_eq!(rngs[0].roll(6), copy.roll(6));
+        assert_eq!(rngs[1].roll(6), 3);
+        let shuffled = rngs[0].shuffle(&['a', 'b', 'c', 'd']);
+        assert_eq!(shuffled, copy.shuffle(&['a', 'b', 'c', 'd']));
+        let mut child = rngs[0].split_boxed();
+        assert_eq!(child.get_u64(), copy.split().get_u64());
+        // Boxes can be passed wherever the trait is expected
+        fn takes_impl(mut rng: impl SplittableRandom) -> u64 {
+            rng.get_u64()
+        }
+        assert_eq!(takes_impl(Box::new(real.clone())), real.get_u64());
+    }
+}
@@ -0,0 +1,138 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Shuffling datasets too large to hold in memory.
+//!
+//! The first pass reads the input a chunk at a time and
+//! deals every item into a random bucket, spilling each
+//! bucket to storage. The second pass loads the buckets one
+//! by one, shuffles each in memory and writes it out. Only one
+//! input chunk or one bucket is held at a time, so the buckets
+//! should be small enough to fit in memory.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// Where an external shuffle reads, spills and writes its items,
+/// such as a set of files
+pub trait ChunkStore<L> {
+    /// The error the storage may fail with
+    type Error;
+
+    /// Read the next chunk of input, or `None` at the end
+    fn read_chunk(&mut self) -> Result<Option<Vec<L>>, Self::Error>;
+
+    /// Append items to a bucket
+    fn spill(&mut self, bucket: usize, items: Vec<L>) -> Result<(), Self::Error>;
+
+    /// Load every item spilled to a bucket
+    fn load(&mut self, bucket: usize) -> Result<Vec<L>, Self::Error>;
+
+    /// Write the next chunk of shuffled output
+    fn write_chunk(&mut self, items: Vec<L>) -> Result<(), Self::Error>;
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Shuffle everything read from a store into its output,
+    /// using `buckets` buckets
+    ///
+    /// The order depends only on the items in order and the number
+    /// of buckets, not on how the input is split into chunks. Each
+    /// bucket is written out as one chunk. Stops at the first error.
+    ///
+    /// Panics if there are no buckets
+    pub fn shuffle_external<L, S: ChunkStore<L>>(
+        &mut self,
+        store: &mut S,
+        buckets: usize,
+    ) -> Result<(), S::Error> {
+        assert!(buckets > 0, "An external shuffle needs at least one bucket");
+        while let Some(chunk) = store.read_chunk()? {
+            let mut dealt: Vec<Vec<L>> = (0..buckets).map(|_| Vec::new()).collect();
+            for item in chunk {
+                dealt[self.fair_roll_usize(buckets)].push(item);
+            }
+            for (bucket, items) in dealt.into_iter().enumerate() {
+                if !items.is_empty() {
+                    store.spill(bucket, items)?;
+                }
+            }
+        }
+        for bucket in 0..buckets {
+            let items = store.load(bucket)?;
+            store.write_chunk(self.shuffle_vec(items))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::convert::Infallible;
+
+    struct MemoryStore {
+        input: Vec<u32>,
+        chunk: usize,
+        buckets: Vec<Vec<u32>>,
+        output: Vec<u32>,
+    }
+
+    impl ChunkStore<u32> for MemoryStore {
+        type Error = Infallible;
+
+        fn read_chunk(&mut self) -> Result<Option<Vec<u32>>, Infallible> {
+            if self.input.is_empty() {
+                return Ok(None);
+            }
+            let rest = self.input.split_off(self.chunk.min(self.input.len()));
+            Ok(Some(std::mem::replace(&mut self.input, rest)))
+        }
+
+        fn spill(&mut self, bucket: usize, items: Vec<u32>) -> Result<(), Infallible> {
+            self.buckets
+                .resize(self.buckets.len().max(bucket + 1), Vec::new());
+            self.buckets[bucket].extend(items);
+            Ok(())
+        }
+
+        fn load(&mut self, bucket: usize) -> Result<Vec<u32>, Infallible> {
+            Ok(self
+                .buckets
+                .get_mut(bucket)
+                .map(std::mem::take)
+                .unwrap_or_default())
+        }
+
+        fn write_chunk(&mut self, items: Vec<u32>) -> Result<(), Infallible> {
+            self.output.extend(items);
+            Ok(())
+        }
+    }
+
+    fn shuffled(chunk: usize) -> Vec<u32> {
+        let mut store = MemoryStore {
+            input: (0..1000).collect(),
+            chunk,
+            buckets: Vec::new(),
+            output: Vec::new(),
+        };
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.shuffle_external(&mut store, 8).unwrap();
+        store.output
+    }
+
+    #[test]
+    fn test_external_shuffle() {
+        let out = shuffled(100);
+        assert_eq!(out, shuffled(7));
+        let mut sorted = out.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..1000).collect::<Vec<_>>());
+        // Early items are spread through the whole output
+        let early: usize = out.iter().take(500).filter(|i| **i < 500).count();
+        assert!((early as i32 - 250).abs() < 40, "{}", early);
+    }
+}
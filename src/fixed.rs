@@ -0,0 +1,111 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random fixed-point decimals, such as money
+//! amounts, drawn exactly rather than through `f64`.
+//!
+//! A value is a whole number of units of `10^-scale`,
+//! so 12.34 is 1234 units at a scale of 2.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+use std::ops::RangeBounds;
+
+/// The most decimal places a value may have,
+/// as `10^38` is the largest power of ten in a `u128`
+pub const MAX_SCALE: u32 = 38;
+
+/// A decimal with a fixed number of places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fixed {
+    units: i128,
+    scale: u32,
+}
+
+impl Fixed {
+    /// The value of `units * 10^-scale`
+    ///
+    /// Panics if the scale is over `MAX_SCALE`
+    pub fn new(units: i128, scale: u32) -> Self {
+        assert!(scale <= MAX_SCALE, "Too many decimal places");
+        Fixed { units, scale }
+    }
+
+    /// The number of units of `10^-scale`
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    /// The number of decimal places
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The nearest `f64`, for display or plotting
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 / 10f64.powi(self.scale as i32)
+    }
+}
+
+/// Every decimal place is written, so 5 units
+/// at a scale of 2 is `0.05`
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.units < 0 { "-" } else { "" };
+        let magnitude = self.units.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{}{}", sign, magnitude);
+        }
+        let one = 10u128.pow(self.scale);
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            magnitude / one,
+            magnitude % one,
+            width = self.scale as usize
+        )
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a decimal with `scale` places, uniformly within
+    /// a range of units, or `None` if the range is empty
+    ///
+    /// The range is in units of `10^-scale`, so
+    /// `get_fixed(2, 550..=2000)` gives an amount from 5.50 to
+    /// 20.00, each cent equally likely.
+    ///
+    /// Panics if the scale is over `MAX_SCALE`
+    pub fn get_fixed<R: RangeBounds<i128>>(&mut self, scale: u32, range: R) -> Option<Fixed> {
+        assert!(scale <= MAX_SCALE, "Too many decimal places");
+        let units = self.fair_roll_range(range)?;
+        Some(Fixed::new(units, scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(Fixed::new(1234, 2).to_string(), "12.34");
+        assert_eq!(Fixed::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Fixed::new(7, 0).to_string(), "7");
+        assert_eq!(Fixed::new(i128::MIN, MAX_SCALE).to_string().len(), 41);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut cents = [0u32; 100];
+        for _ in 0..10000 {
+            let amount = rng.get_fixed(2, 550..=2000).unwrap();
+            assert!((550..=2000).contains(&amount.units()));
+            assert_eq!(amount.scale(), 2);
+            cents[(amount.units() % 100) as usize] += 1;
+        }
+        assert!(cents.iter().all(|c| (50..150).contains(c)), "{:?}", cents);
+        assert_eq!(rng.get_fixed(2, 10..10), None);
+    }
+}
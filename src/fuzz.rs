@@ -0,0 +1,149 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Budgeted generation for structure-aware fuzzing.
+//!
+//! A `FuzzBudget` lends out an rng for a limited number
+//! of steps. Once the steps run out every draw fails with
+//! `Exhausted`, so generators can bail out with `?` and
+//! the size of each input is bounded by its budget alone.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// The budget ran out before the draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Exhausted;
+
+impl fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "random budget exhausted")
+    }
+}
+
+impl std::error::Error for Exhausted {}
+
+/// An rng which may only be stepped a limited number of times
+pub struct FuzzBudget<'a, T: RngCore + SeedableRng> {
+    rng: &'a mut SplittingRng<T>,
+    limit: u64,
+    used: u64,
+}
+
+impl<'a, T: RngCore + SeedableRng> FuzzBudget<'a, T> {
+    /// Lend out an rng for at most `max_steps` steps
+    pub fn new(rng: &'a mut SplittingRng<T>, max_steps: u64) -> Self {
+        FuzzBudget {
+            rng,
+            limit: max_steps,
+            used: 0,
+        }
+    }
+
+    /// Lend out an rng for at most `max_bytes` of output,
+    /// rounded up to whole 8-byte steps
+    pub fn from_bytes(rng: &'a mut SplittingRng<T>, max_bytes: u64) -> Self {
+        Self::new(rng, max_bytes.div_ceil(8))
+    }
+
+    /// How many steps may still be used
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used)
+    }
+
+    /// Whether every draw will now fail
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Make any draw from the rng, charging the steps it used
+    ///
+    /// The draw is refused if the budget is already spent. A draw
+    /// which starts within budget is allowed to finish, such as
+    /// a `fair_roll` which rerolls, and the overspend is not refunded.
+    pub fn draw<V>(&mut self, f: impl FnOnce(&mut SplittingRng<T>) -> V) -> Result<V, Exhausted> {
+        if self.is_exhausted() {
+            return Err(Exhausted);
+        }
+        let before = self.rng.steps;
+        let value = f(self.rng);
+        self.used += self.rng.steps - before;
+        Ok(value)
+    }
+
+    /// Provide an unsigned 64-bit integer
+    pub fn get_u64(&mut self) -> Result<u64, Exhausted> {
+        self.draw(|rng| rng.get_u64())
+    }
+
+    /// Provide a random boolean
+    ///
+    /// Most bools come from the bool pool and cost
+    /// nothing, but still fail once the budget is spent
+    pub fn get_bool(&mut self) -> Result<bool, Exhausted> {
+        self.draw(|rng| rng.get_bool())
+    }
+
+    /// Roll a fair die, from 0 to `sides - 1`
+    pub fn fair_roll(&mut self, sides: u32) -> Result<u32, Exhausted> {
+        self.draw(|rng| rng.fair_roll(sides))
+    }
+
+    /// Pick a collection length from 0 to `max`, shrunk so it is no
+    /// more than the steps remaining, so that each element can
+    /// still afford at least one draw
+    pub fn choose_len(&mut self, max: usize) -> Result<usize, Exhausted> {
+        let limit = self.remaining().saturating_sub(1).min(max as u64) as u32;
+        self.fair_roll(limit.saturating_add(1)).map(|l| l as usize)
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Lend out this rng for at most `max_steps` steps
+    pub fn budget(&mut self, max_steps: u64) -> FuzzBudget<'_, T> {
+        FuzzBudget::new(self, max_steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[derive(Debug, PartialEq)]
+    enum Tree {
+        Leaf(u32),
+        Node(Vec<Tree>),
+    }
+
+    fn tree<T: RngCore + SeedableRng>(budget: &mut FuzzBudget<'_, T>) -> Result<Tree, Exhausted> {
+        if budget.fair_roll(3)? == 0 {
+            let len = budget.choose_len(4)?;
+            let mut children = Vec::with_capacity(len);
+            for _ in 0..len {
+                children.push(tree(budget)?);
+            }
+            Ok(Tree::Node(children))
+        } else {
+            Ok(Tree::Leaf(budget.fair_roll(100)?))
+        }
+    }
+
+    #[test]
+    fn test_budget_bounds_generation() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..100 {
+            let mut budget = rng.budget(20);
+            let _ = tree(&mut budget);
+            // A draw may finish a little past the end of its budget
+            assert!(budget.used < 30);
+        }
+        let mut budget = FuzzBudget::from_bytes(&mut rng, 9);
+        assert_eq!(budget.remaining(), 2);
+        assert!(budget.get_u64().is_ok());
+        assert!(budget.get_u64().is_ok());
+        assert_eq!(budget.get_bool(), Err(Exhausted));
+    }
+}
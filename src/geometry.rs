@@ -0,0 +1,181 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random directions and points for game code.
+//!
+//! Vectors are plain arrays of `f64`, so they
+//! can be converted into any math library's types.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::f64::consts::TAU;
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide an angle in radians, in [0, 2π)
+    pub fn get_angle(&mut self) -> f64 {
+        TAU * self.get_f64()
+    }
+
+    /// Provide a unit quaternion, as `[x, y, z, w]`, for an
+    /// orientation uniformly distributed over all rotations
+    ///
+    /// Uses Shoemake's method. Picking three Euler angles
+    /// uniformly instead would favour some orientations.
+    pub fn get_quaternion(&mut self) -> [f64; 4] {
+        let u = self.get_f64();
+        let a = self.get_angle();
+        let b = self.get_angle();
+        let low = (1.0 - u).sqrt();
+        let high = u.sqrt();
+        [low * a.sin(), low * a.cos(), high * b.sin(), high * b.cos()]
+    }
+
+    /// Provide a 2D unit vector, uniform around the circle
+    pub fn get_unit_vec2(&mut self) -> [f64; 2] {
+        let theta = self.get_angle();
+        [theta.cos(), theta.sin()]
+    }
+
+    /// Provide a 3D unit vector, uniform over the sphere
+    ///
+    /// Picks the height uniformly, which by Archimedes'
+    /// hat-box theorem covers the sphere evenly
+    pub fn get_unit_vec3(&mut self) -> [f64; 3] {
+        let z = 2.0 * self.get_f64() - 1.0;
+        let theta = self.get_angle();
+        let r = (1.0 - z * z).sqrt();
+        [r * theta.cos(), r * theta.sin(), z]
+    }
+
+    /// Provide a point uniformly within the rectangle
+    /// from `min` to `max`
+    pub fn get_point_in_rect(&mut self, min: [f64; 2], max: [f64; 2]) -> [f64; 2] {
+        [
+            min[0] + (max[0] - min[0]) * self.get_f64(),
+            min[1] + (max[1] - min[1]) * self.get_f64(),
+        ]
+    }
+
+    /// Provide a point uniformly within a disc
+    ///
+    /// The distance from the center is the square root of
+    /// a uniform value, as the area grows with the radius
+    /// squared. Without it, points cluster in the middle.
+    pub fn get_point_in_disc(&mut self, center: [f64; 2], radius: f64) -> [f64; 2] {
+        let r = radius * self.get_f64().sqrt();
+        let [x, y] = self.get_unit_vec2();
+        [center[0] + r * x, center[1] + r * y]
+    }
+
+    /// Provide a point uniformly within a ball
+    ///
+    /// As with discs, the distance from the center is the cube
+    /// root of a uniform value, as the volume grows as its cube.
+    pub fn get_point_in_ball(&mut self, center: [f64; 3], radius: f64) -> [f64; 3] {
+        let r = radius * self.get_f64().cbrt();
+        let [x, y, z] = self.get_unit_vec3();
+        [center[0] + r * x, center[1] + r * y, center[2] + r * z]
+    }
+
+    /// Provide a point uniformly within the axis-aligned
+    /// box from `min` to `max`
+    pub fn get_point_in_box(&mut self, min: [f64; 3], max: [f64; 3]) -> [f64; 3] {
+        [
+            min[0] + (max[0] - min[0]) * self.get_f64(),
+            min[1] + (max[1] - min[1]) * self.get_f64(),
+            min[2] + (max[2] - min[2]) * self.get_f64(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_unit_vectors() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        let mut acc = [0.0; 3];
+        let mut upper = 0;
+        for _ in 0..iter {
+            let [x, y] = rng.get_unit_vec2();
+            assert!((x * x + y * y - 1.0).abs() < 1e-9);
+            let v = rng.get_unit_vec3();
+            assert!((v.iter().map(|c| c * c).sum::<f64>() - 1.0).abs() < 1e-9);
+            for i in 0..3 {
+                acc[i] += v[i];
+            }
+            // The cap above z = 0.5 is a quarter of the sphere
+            if v[2] > 0.5 {
+                upper += 1;
+            }
+        }
+        for c in acc {
+            assert!((c / iter as f64).abs() < 0.02, "{}", c);
+        }
+        let frac = upper as f64 / iter as f64;
+        assert!((frac - 0.25).abs() < 0.01, "{}", frac);
+    }
+
+    #[test]
+    fn test_quaternions() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        let mut small = 0;
+        for _ in 0..iter {
+            let q = rng.get_quaternion();
+            assert!((q.iter().map(|c| c * c).sum::<f64>() - 1.0).abs() < 1e-9);
+            // Rotations by under 90 degrees have |w| > cos(45°),
+            // which is (π/2 - 1) / π ≈ 18.2% of uniform rotations
+            if q[3].abs() > std::f64::consts::FRAC_1_SQRT_2 {
+                small += 1;
+            }
+            assert!((0.0..TAU).contains(&rng.get_angle()));
+        }
+        let frac = small as f64 / iter as f64;
+        assert!((frac - 0.1817).abs() < 0.01, "{}", frac);
+    }
+
+    #[test]
+    fn test_points_in_shapes() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        let mut inner = 0;
+        for _ in 0..iter {
+            let [x, y] = rng.get_point_in_disc([1.0, -1.0], 2.0);
+            let d2 = (x - 1.0).powi(2) + (y + 1.0).powi(2);
+            assert!(d2 <= 4.0);
+            // Half the radius holds a quarter of the area
+            if d2 < 1.0 {
+                inner += 1;
+            }
+            let [x, y] = rng.get_point_in_rect([0.0, 5.0], [1.0, 6.0]);
+            assert!((0.0..1.0).contains(&x) && (5.0..6.0).contains(&y));
+            let p = rng.get_point_in_box([0.0; 3], [1.0, 2.0, 3.0]);
+            assert!(p[0] < 1.0 && p[1] < 2.0 && p[2] < 3.0);
+        }
+        let frac = inner as f64 / iter as f64;
+        assert!((frac - 0.25).abs() < 0.01, "{}", frac);
+    }
+
+    #[test]
+    fn test_points_in_ball() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        let mut inner = 0;
+        for _ in 0..iter {
+            let b = rng.get_point_in_ball([1.0, 0.0, -1.0], 2.0);
+            let d2 = (b[0] - 1.0).powi(2) + b[1].powi(2) + (b[2] + 1.0).powi(2);
+            assert!(d2 <= 4.0);
+            // Half the radius holds an eighth of the volume
+            if d2 < 1.0 {
+                inner += 1;
+            }
+        }
+        let frac = inner as f64 / iter as f64;
+        assert!((frac - 0.125).abs() < 0.01, "{}", frac);
+    }
+}
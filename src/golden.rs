@@ -0,0 +1,259 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Pinned outputs for each algorithm version.
+//!
+//! These are part of the stability guarantee: a release
+//! which changes any of them for an existing version is a bug.
+//! Downstream crates which save seeds may check them too,
+//! or pin many more outputs with `golden_vectors`.
+
+use crate::compat::BiasedRoll;
+use crate::{Algorithm, ShuffleAlgorithm, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::fmt::{Display, Write};
+
+/// The outputs one version gives for a fixed origin
+///
+/// Every sequence is drawn in order from a single
+/// `SplittingRng<Xoshiro256StarStar>`: first `u64s` from
+/// `get_u64`, then `d6` from `fair_roll(6)`, then `bools`
+/// from `get_bool`, and last `shuffle` of `0..10u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoldenVector {
+    pub algorithm: Algorithm,
+    pub origin: u64,
+    pub u64s: &'static [u64],
+    pub d6: &'static [u32],
+    pub bools: &'static [bool],
+    pub shuffle: &'static [u8],
+}
+
+// Short names keep the bool tables readable. The 64 bools
+// run past the end of the first pool, where V1 and V2 differ
+const T: bool = true;
+const F: bool = false;
+
+/// The pinned outputs of `Algorithm::V1`
+pub const V1: GoldenVector = GoldenVector {
+    algorithm: Algorithm::V1,
+    origin: 12345,
+    u64s: &[
+        2398916695208396998,
+        17770384849984869256,
+        891717726879801395,
+        10241316046318454344,
+    ],
+    d6: &[0, 3, 5, 2, 5, 2, 5, 5],
+    bools: &[
+        F, T, F, F, T, T, T, T, T, F, F, T, F, T, T, T, T, T, F, T, F, F, F, T, F, F, T, T, T, F,
+        F, T, F, F, T, T, T, F, T, F, T, F, F, T, T, F, F, F, F, F, T, F, T, T, T, T, T, T, T, T,
+        T, F, F, T,
+    ],
+    shuffle: &[0, 8, 2, 9, 4, 6, 1, 3, 7, 5],
+};
+
+/// The pinned outputs of `Algorithm::V2`
+pub const V2: GoldenVector = GoldenVector {
+    algorithm: Algorithm::V2,
+    origin: 12345,
+    u64s: &[
+        2398916695208396998,
+        17770384849984869256,
+        891717726879801395,
+        10241316046318454344,
+    ],
+    d6: &[0, 3, 5, 2, 5, 2, 5, 5],
+    bools: &[
+        F, F, T, T, F, T, T, F, T, F, T, F, F, T, T, T, T, T, F, F, T, F, T, T, T, T, T, F, T, F,
+        F, F, T, F, F, T, T, T, F, F, T, F, F, T, T, T, F, T, F, T, F, F, T, T, F, F, F, F, F, T,
+        F, T, F, F,
+    ],
+    shuffle: &[2, 7, 3, 8, 6, 4, 9, 0, 1, 5],
+};
+
+/// The pinned outputs of `Algorithm::V3`
+pub const V3: GoldenVector = GoldenVector {
+    algorithm: Algorithm::V3,
+    origin: 12345,
+    u64s: &[
+        2398916695208396998,
+        17770384849984869256,
+        891717726879801395,
+        10241316046318454344,
+    ],
+    d6: &[0, 0, 1, 2, 5, 4, 3, 5],
+    bools: V2.bools,
+    shuffle: V2.shuffle,
+};
+
+/// The pinned outputs of every version, oldest first
+pub const ALL: &[GoldenVector] = &[V1, V2, V3];
+
+/// Write the first `n` outputs of each core method, for every
+/// version, as text to pin in a regression suite
+///
+/// Each line is one method under one version, drawn from a new
+/// rng of the given backend, as in `v1 fair_roll(6) 2 5 4 5`
+/// for `Xoshiro256StarStar` from origin 12345. Floats are written as the hex of
+/// their bits, so the text is exact, and only outputs which are
+/// the same on every platform are included. Keep the text for a
+/// known good release and compare it on upgrade: any difference
+/// means a version changed its outputs, which is a bug.
+pub fn golden_vectors<T: RngCore + SeedableRng>(origin: u64, n: usize) -> String {
+    let mut out = format!("# golden vectors, origin {}, {} of each\n", origin, n);
+    for (version, algorithm) in [
+        ("v1", Algorithm::V1),
+        ("v2", Algorithm::V2),
+        ("v3", Algorithm::V3),
+    ] {
+        let new = || SplittingRng::<T>::with_algorithm(origin, algorithm);
+        let mut line = |method: &str, values: Vec<String>| {
+            writeln!(out, "{} {} {}", version, method, values.join(" "))
+                .expect("Writing to a string");
+        };
+        line("get_u64", draws(n, new(), |r| r.get_u64()));
+        line("get_u32", draws(n, new(), |r| r.get_u32()));
+        line(
+            "get_f64",
+            draws(n, new(), |r| format!("{:016x}", r.get_f64().to_bits())),
+        );
+        line("get_bool", draws(n, new(), |r| r.get_bool() as u8));
+        line(
+            "biased_roll(6)",
+            draws(n, new(), |r| BiasedRoll::biased_roll(r, 6)),
+        );
+        line("fair_roll(6)", draws(n, new(), |r| r.fair_roll(6)));
+        line(
+            "fair_roll(1000000)",
+            draws(n, new(), |r| r.fair_roll(1_000_000)),
+        );
+        line(
+            "fair_roll_u128",
+            draws(n, new(), |r| r.fair_roll_u128(u128::MAX / 3)),
+        );
+        line("split", draws(n, new(), |r| r.split().origin()));
+        line(
+            "value_at",
+            (0..n as u64)
+                .map(|k| new().value_at(k).to_string())
+                .collect(),
+        );
+        let items: Vec<u64> = (0..n as u64).collect();
+        for (name, shuffle) in [
+            ("shuffle", ShuffleAlgorithm::HashSort),
+            ("shuffle(FisherYates)", ShuffleAlgorithm::FisherYates),
+            ("shuffle(BatchedHash)", ShuffleAlgorithm::BatchedHash),
+        ] {
+            let order = new().shuffle_with(&items, shuffle);
+            line(name, order.iter().map(|i| i.to_string()).collect());
+        }
+    }
+    out
+}
+
+fn draws<T, V: Display>(
+    n: usize,
+    mut rng: SplittingRng<T>,
+    mut draw: impl FnMut(&mut SplittingRng<T>) -> V,
+) -> Vec<String>
+where
+    T: RngCore + SeedableRng,
+{
+    (0..n).map(|_| draw(&mut rng).to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_golden_vectors() {
+        for golden in ALL {
+            let mut rng =
+                SplittingRng::<Xoshiro256StarStar>::with_algorithm(golden.origin, golden.algorithm);
+            let u64s: Vec<u64> = golden.u64s.iter().map(|_| rng.get_u64()).collect();
+            assert_eq!(u64s, golden.u64s, "{:?}", golden.algorithm);
+            let d6: Vec<u32> = golden.d6.iter().map(|_| rng.fair_roll(6)).collect();
+            assert_eq!(d6, golden.d6, "{:?}", golden.algorithm);
+            let bools: Vec<bool> = golden.bools.iter().map(|_| rng.get_bool()).collect();
+            assert_eq!(bools, golden.bools, "{:?}", golden.algorithm);
+            let items: Vec<u8> = (0..10).collect();
+            assert_eq!(
+                rng.shuffle(&items),
+                golden.shuffle,
+                "{:?}",
+                golden.algorithm
+            );
+            // Saved states replay under their own version
+            let state = rng.to_state();
+            let mut copy = SplittingRng::<Xoshiro256StarStar>::from_state(state);
+            assert_eq!(copy.algorithm(), golden.algorithm);
+            assert_eq!(copy.get_u64(), rng.get_u64());
+        }
+    }
+
+    #[test]
+    fn test_portable_shuffles() {
+        // Pinned on a 64-bit target, and must hold on 32-bit
+        // and wasm targets too, as lockstep games rely on it
+        let hash_sort = ShuffleAlgorithm::HashSort;
+        let pinned: [(Algorithm, ShuffleAlgorithm, [u32; 8], u64); 4] = [
+            (
+                Algorithm::V1,
+                hash_sort,
+                [4, 223, 149, 15, 232, 133, 84, 36],
+                6753505,
+            ),
+            (
+                Algorithm::V2,
+                hash_sort,
+                [29, 3, 71, 179, 57, 209, 278, 197],
+                6577848,
+            ),
+            (
+                Algorithm::V1,
+                ShuffleAlgorithm::FisherYates,
+                [287, 99, 119, 60, 250, 133, 1, 12],
+                6585223,
+            ),
+            (
+                Algorithm::V1,
+                ShuffleAlgorithm::BatchedHash,
+                [20, 15, 111, 23, 9, 189, 24, 173],
+                6794823,
+            ),
+        ];
+        let items: Vec<u32> = (0..300).collect();
+        for (algorithm, shuffle, head, checksum) in pinned {
+            let mut rng = SplittingRng::<Xoshiro256StarStar>::with_algorithm(7, algorithm)
+                .with_shuffle_algorithm(shuffle);
+            let shuffled = rng.shuffle(&items);
+            assert_eq!(shuffled[..8], head, "{:?} {:?}", algorithm, shuffle);
+            let sum: u64 = shuffled
+                .iter()
+                .enumerate()
+                .map(|(i, v)| i as u64 * *v as u64)
+                .sum();
+            assert_eq!(sum, checksum, "{:?} {:?}", algorithm, shuffle);
+        }
+    }
+
+    #[test]
+    fn test_golden_vector_text() {
+        let text = golden_vectors::<Xoshiro256StarStar>(12345, 4);
+        assert_eq!(text, golden_vectors::<Xoshiro256StarStar>(12345, 4));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "# golden vectors, origin 12345, 4 of each");
+        assert_eq!(lines.len(), 1 + 3 * 13);
+        assert_eq!(
+            lines[1],
+            "v1 get_u64 2398916695208396998 17770384849984869256 891717726879801395 10241316046318454344"
+        );
+        assert!(text.contains("\nv1 fair_roll(6) 2 5 4 5\n"));
+        assert!(text.contains("\nv3 fair_roll(6) 0 5 0 3\n"));
+        assert_ne!(text, golden_vectors::<Xoshiro256StarStar>(12346, 4));
+    }
+}
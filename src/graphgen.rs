@@ -0,0 +1,148 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random trees and graphs, for testing graph algorithms.
+//!
+//! Graphs are edge lists over the vertices `0..n`. Undirected
+//! edges are given as `(low, high)`, in order, with no loops
+//! or repeated edges.
+
+use crate::distributions::{Bernoulli, Distribution, DistributionError};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a tree on `n` vertices, uniform over all labelled trees
+    ///
+    /// Decodes a random Prüfer sequence, so every one of the
+    /// n^(n-2) trees is equally likely.
+    pub fn get_tree(&mut self, n: usize) -> Vec<(usize, usize)> {
+        if n < 2 {
+            return Vec::new();
+        }
+        let code: Vec<usize> = (0..n - 2)
+            .map(|_| self.below_u128(n as u128) as usize)
+            .collect();
+        let mut degree = vec![1; n];
+        for v in code.iter() {
+            degree[*v] += 1;
+        }
+        let mut leaves: BinaryHeap<Reverse<usize>> =
+            (0..n).filter(|v| degree[*v] == 1).map(Reverse).collect();
+        let mut edges = Vec::with_capacity(n - 1);
+        for v in code {
+            let Reverse(leaf) = leaves.pop().expect("A Prüfer code always has a leaf");
+            edges.push((leaf.min(v), leaf.max(v)));
+            degree[v] -= 1;
+            if degree[v] == 1 {
+                leaves.push(Reverse(v));
+            }
+        }
+        let Reverse(a) = leaves.pop().expect("Two vertices remain");
+        let Reverse(b) = leaves.pop().expect("Two vertices remain");
+        edges.push((a.min(b), a.max(b)));
+        edges.sort_unstable();
+        edges
+    }
+
+    /// Provide an Erdős–Rényi graph, where each of the possible
+    /// edges between `n` vertices is present with chance `p`
+    pub fn get_gnp_graph(
+        &mut self,
+        n: usize,
+        p: f64,
+    ) -> Result<Vec<(usize, usize)>, DistributionError> {
+        let coin = Bernoulli::new(p)?;
+        let mut edges = Vec::new();
+        for a in 0..n {
+            for b in a + 1..n {
+                if coin.sample(self) {
+                    edges.push((a, b));
+                }
+            }
+        }
+        Ok(edges)
+    }
+
+    /// Provide a directed acyclic graph, as `(from, to)` edges, where
+    /// each edge consistent with a hidden random order is present
+    /// with chance `p`
+    ///
+    /// The order is shuffled, so vertex numbers say nothing
+    /// about which way the edges point.
+    pub fn get_dag(&mut self, n: usize, p: f64) -> Result<Vec<(usize, usize)>, DistributionError> {
+        let order: Vec<usize> = (0..n).collect();
+        let order = self.shuffle(&order);
+        Ok(self
+            .get_gnp_graph(n, p)?
+            .into_iter()
+            .map(|(a, b)| (order[a], order[b]))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    // Whether the edges join all vertices, using union-find
+    fn connected(n: usize, edges: &[(usize, usize)]) -> bool {
+        let mut parent: Vec<usize> = (0..n).collect();
+        fn find(parent: &mut [usize], v: usize) -> usize {
+            if parent[v] != v {
+                parent[v] = find(parent, parent[v]);
+            }
+            parent[v]
+        }
+        for (a, b) in edges {
+            let (ra, rb) = (find(&mut parent, *a), find(&mut parent, *b));
+            parent[ra] = rb;
+        }
+        let root = find(&mut parent, 0);
+        (0..n).all(|v| find(&mut parent, v) == root)
+    }
+
+    #[test]
+    fn test_graphs() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for n in [2, 3, 50] {
+            let tree = rng.get_tree(n);
+            assert_eq!(tree.len(), n - 1);
+            assert!(connected(n, &tree));
+        }
+        // All 3 trees on 3 vertices are equally likely
+        let mut paths = [0i32; 3];
+        for _ in 0..3000 {
+            let tree = rng.get_tree(3);
+            let middle = (0..3).find(|v| tree.iter().all(|(a, b)| a == v || b == v));
+            paths[middle.unwrap()] += 1;
+        }
+        assert!(paths.iter().all(|c| (*c - 1000).abs() < 100), "{:?}", paths);
+        let graph = rng.get_gnp_graph(100, 0.1).unwrap();
+        assert!((graph.len() as i32 - 495).abs() < 70, "{}", graph.len());
+        let dag = rng.get_dag(30, 0.3).unwrap();
+        // Acyclic graphs can be ordered with Kahn's algorithm
+        let mut indegree = [0; 30];
+        for (_, to) in dag.iter() {
+            indegree[*to] += 1;
+        }
+        let mut ready: Vec<usize> = (0..30).filter(|v| indegree[*v] == 0).collect();
+        let mut seen = 0;
+        while let Some(v) = ready.pop() {
+            seen += 1;
+            for (from, to) in dag.iter() {
+                if *from == v {
+                    indegree[*to] -= 1;
+                    if indegree[*to] == 0 {
+                        ready.push(*to);
+                    }
+                }
+            }
+        }
+        assert_eq!(seen, 30);
+    }
+}
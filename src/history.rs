@@ -0,0 +1,225 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Per-frame snapshots, for rolling back randomness
+//! alongside game state.
+//!
+//! With history kept, `save_frame` at the start of each
+//! frame stores a copy of the backend and the bool pool, along
+//! with the audit counts, the steps charged to any step limit
+//! and the registered children, and `rewind` jumps straight
+//! back to one of them. Unlike `seek`, nothing is replayed, so
+//! rewinding costs the same however far into the stream the rng is.
+
+use crate::bits::BitPool;
+use crate::prefetch::Prefetch;
+use crate::trace::Audit;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+/// The reasons an rng may fail to rewind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RewindError {
+    /// History is not being kept, see `keep_history`
+    NoHistory,
+    /// Fewer frames are kept than were asked for
+    TooFar {
+        /// The frames asked for
+        frames: usize,
+        /// The frames kept
+        kept: usize,
+    },
+}
+
+impl fmt::Display for RewindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewindError::NoHistory => write!(f, "no history is being kept"),
+            RewindError::TooFar { frames, kept } => {
+                write!(f, "cannot rewind {} frames with only {} kept", frames, kept)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RewindError {}
+
+#[derive(Clone)]
+struct Snapshot<T: RngCore + SeedableRng> {
+    prng: T,
+    steps: u64,
+    bool_pool: BitPool,
+    prefetch: Option<Prefetch>,
+    audit: Audit,
+    limit_used: Option<u64>,
+    children: BTreeMap<String, SplittingRng<T>>,
+}
+
+/// The last frames saved, oldest first
+#[derive(Clone)]
+pub(crate) struct History<T: RngCore + SeedableRng> {
+    capacity: usize,
+    frames: VecDeque<Snapshot<T>>,
+}
+
+impl<T: RngCore + SeedableRng> History<T> {
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+impl<T: RngCore + SeedableRng + Clone> SplittingRng<T> {
+    /// Keep the last `frames` frames saved by `save_frame`
+    /// from now on, or stop keeping history with zero
+    ///
+    /// Shrinking drops the oldest frames. Children don't inherit
+    /// history, and it isn't part of the saved state.
+    pub fn keep_history(&mut self, frames: usize) {
+        match (self.history.as_mut(), frames) {
+            (_, 0) => self.history = None,
+            (Some(history), _) => {
+                history.capacity = frames;
+                while history.frames.len() > frames {
+                    history.frames.pop_front();
+                }
+            }
+            (None, _) => {
+                self.history = Some(History {
+                    capacity: frames,
+                    frames: VecDeque::with_capacity(frames),
+                })
+            }
+        }
+    }
+
+    /// How many frames are kept to rewind to
+    pub fn frames_kept(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.len())
+    }
+
+    /// Save the current position as a frame, dropping the oldest
+    /// frame if the history is full
+    ///
+    /// Registered children are copied into the frame too, so
+    /// saving costs more the more children there are. Does
+    /// nothing unless history is being kept.
+    pub fn save_frame(&mut self) {
+        if self.history.is_none() {
+            return;
+        }
+        let snapshot = Snapshot {
+            prng: self.prng.clone(),
+            steps: self.steps,
+            bool_pool: self.bool_pool.clone(),
+            prefetch: self.prefetch.clone(),
+            audit: self.audit,
+            limit_used: self.limit.as_ref().map(|limit| limit.used()),
+            children: self.children.clone(),
+        };
+        if let Some(history) = self.history.as_mut() {
+            if history.frames.len() == history.capacity {
+                history.frames.pop_front();
+            }
+            history.frames.push_back(snapshot);
+        }
+    }
+
+    /// Go back to the frame saved `frames` saves ago, counting
+    /// the latest save as one, and forget it and every later frame
+    ///
+    /// Saving at the start of each frame and rewinding to the
+    /// frame a late input was for, the saves made while simulating
+    /// forward again rebuild the history as it was. Rewinding zero
+    /// frames does nothing.
+    ///
+    /// The steps charged to a step limit go back too, when the
+    /// limit was set before the frame was saved. Registered
+    /// children are restored as the copies saved, which, like any
+    /// clone, are not traced.
+    pub fn rewind(&mut self, frames: usize) -> Result<(), RewindError> {
+        let history = self.history.as_mut().ok_or(RewindError::NoHistory)?;
+        if frames > history.frames.len() {
+            return Err(RewindError::TooFar {
+                frames,
+                kept: history.frames.len(),
+            });
+        }
+        let keep = history.frames.len() - frames;
+        if let Some(snapshot) = history.frames.drain(keep..).next() {
+            self.prng = snapshot.prng;
+            self.steps = snapshot.steps;
+            self.bool_pool = snapshot.bool_pool;
+            self.prefetch = snapshot.prefetch;
+            self.audit = snapshot.audit;
+            if let (Some(limit), Some(used)) = (self.limit.as_mut(), snapshot.limit_used) {
+                limit.set_used(used);
+            }
+            self.children = snapshot.children;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_rewind_frames() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(rng.rewind(1), Err(RewindError::NoHistory));
+        rng.keep_history(3);
+        let mut played = Vec::new();
+        for _ in 0..5 {
+            rng.save_frame();
+            played.push((rng.get_u64(), rng.get_bool(), rng.fair_roll(6)));
+        }
+        assert_eq!(rng.frames_kept(), 3);
+        assert_eq!(
+            rng.rewind(4),
+            Err(RewindError::TooFar { frames: 4, kept: 3 })
+        );
+        // Back to the start of the fourth frame
+        rng.rewind(2).unwrap();
+        assert_eq!(rng.frames_kept(), 1);
+        for frame in played[3..].iter() {
+            rng.save_frame();
+            assert_eq!((rng.get_u64(), rng.get_bool(), rng.fair_roll(6)), *frame);
+        }
+        assert_eq!(rng.frames_kept(), 3);
+
+        let mut prefetched = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        prefetched.prefetch(8);
+        prefetched.keep_history(1);
+        prefetched.get_u64();
+        prefetched.save_frame();
+        let next = prefetched.get_u64();
+        prefetched.rewind(1).unwrap();
+        assert_eq!(prefetched.get_u64(), next);
+        prefetched.keep_history(0);
+        assert_eq!(prefetched.frames_kept(), 0);
+    }
+
+    #[test]
+    fn test_rewind_restores_bookkeeping() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.keep_history(2);
+        rng.limit_steps(10);
+        rng.child("physics").get_u64();
+        rng.fair_roll(6);
+        rng.save_frame();
+        let (audit, remaining) = (*rng.audit(), rng.steps_remaining());
+        let next = rng.child("physics").get_u64();
+        rng.get_u64s(4);
+        rng.child("audio");
+        rng.rewind(1).unwrap();
+        assert_eq!(*rng.audit(), audit);
+        assert_eq!(rng.steps_remaining(), remaining);
+        assert_eq!(rng.child_names().collect::<Vec<_>>(), ["physics"]);
+        assert_eq!(rng.child("physics").get_u64(), next);
+    }
+}
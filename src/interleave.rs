@@ -0,0 +1,118 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random interleavings of logical threads, for
+//! deterministic concurrency tests.
+//!
+//! A harness which runs each logical thread one step at a
+//! time asks which thread goes next. Each thread is picked in
+//! proportion to its steps left, which makes every interleaving
+//! of the steps equally likely, so a single origin explores the
+//! schedules evenly and replays any one of them exactly.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// The thread ids of a random interleaving, one per step
+///
+/// Owns a child split from the rng it was made with,
+/// so drawing the schedule doesn't disturb the rest of a test.
+#[derive(Debug, Clone)]
+pub struct Interleaving<T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    pending: Vec<u64>,
+    total: u64,
+}
+
+impl<T: RngCore + SeedableRng> Interleaving<T> {
+    /// Schedule thread `i` for `steps[i]` steps
+    ///
+    /// Panics if there are more than 2^64 steps in all
+    pub fn new(rng: &mut SplittingRng<T>, steps: &[u64]) -> Self {
+        let total = steps
+            .iter()
+            .try_fold(0u64, |total, s| total.checked_add(*s))
+            .expect("Too many steps to interleave");
+        Interleaving {
+            rng: rng.split(),
+            pending: steps.to_vec(),
+            total,
+        }
+    }
+
+    /// The steps a thread has left
+    pub fn pending(&self, thread: usize) -> u64 {
+        self.pending.get(thread).copied().unwrap_or(0)
+    }
+
+    /// The steps every thread has left together
+    pub fn total_pending(&self) -> u64 {
+        self.total
+    }
+}
+
+impl<T: RngCore + SeedableRng> Iterator for Interleaving<T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.total == 0 {
+            return None;
+        }
+        let mut pick = self.rng.fair_roll_u64(self.total);
+        for (thread, left) in self.pending.iter_mut().enumerate() {
+            if pick < *left {
+                *left -= 1;
+                self.total -= 1;
+                return Some(thread);
+            }
+            pick -= *left;
+        }
+        unreachable!("Picked past the pending steps")
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.total).ok();
+        (len.unwrap_or(usize::MAX), len)
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a uniformly random interleaving of threads with
+    /// the given steps, as the id of the thread for each step
+    ///
+    /// The same as collecting an `Interleaving`
+    pub fn get_interleaving(&mut self, steps: &[u64]) -> Vec<usize> {
+        Interleaving::new(self, steps).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_interleavings() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut seen: BTreeMap<Vec<usize>, u32> = BTreeMap::new();
+        let iter = 10000;
+        for _ in 0..iter {
+            let order = rng.get_interleaving(&[2, 0, 2]);
+            assert_eq!(order.iter().filter(|t| **t == 0).count(), 2);
+            *seen.entry(order).or_default() += 1;
+        }
+        // Each of the 4! / (2! 2!) = 6 interleavings is equally likely
+        assert_eq!(seen.len(), 6);
+        for count in seen.values() {
+            let frac = *count as f64 / iter as f64;
+            assert!((frac - 1.0 / 6.0).abs() < 0.015, "{:?}", seen);
+        }
+        let mut schedule = Interleaving::new(&mut rng, &[3, 1]);
+        assert_eq!(schedule.total_pending(), 4);
+        let first = schedule.next().unwrap();
+        assert_eq!(schedule.pending(first), if first == 0 { 2 } else { 0 });
+        assert_eq!(schedule.count(), 3);
+    }
+}
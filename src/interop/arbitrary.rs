@@ -0,0 +1,71 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::arbitrary::{Arbitrary, Unstructured};
+use rand::{RngCore, SeedableRng};
+
+/// A buffer of random bytes to feed `arbitrary`,
+/// standing in for a fuzz corpus file
+///
+/// `Unstructured` borrows its input, so the bytes are
+/// drawn up front. The length caps how much structure
+/// can be generated from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryBytes {
+    bytes: Vec<u8>,
+}
+
+impl ArbitraryBytes {
+    /// Borrow the bytes as an `Unstructured`
+    pub fn unstructured(&self) -> Unstructured<'_> {
+        Unstructured::new(&self.bytes)
+    }
+
+    /// The bytes themselves
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Draw `len` bytes to feed `arbitrary`
+    pub fn arbitrary_bytes(&mut self, len: usize) -> ArbitraryBytes {
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            bytes.extend_from_slice(&self.get_u64().to_le_bytes());
+        }
+        bytes.truncate(len);
+        ArbitraryBytes { bytes }
+    }
+
+    /// Generate a structured value from `len` random bytes
+    /// The value must own its data, as the bytes are dropped
+    pub fn arbitrary<A>(&mut self, len: usize) -> ::arbitrary::Result<A>
+    where
+        A: for<'a> Arbitrary<'a>,
+    {
+        let bytes = self.arbitrary_bytes(len);
+        A::arbitrary_take_rest(Unstructured::new(&bytes.bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SplittingRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_arbitrary_from_seed() {
+        let mut a = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut b = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let ops_a: Vec<(u16, Option<bool>)> = a.arbitrary(256).unwrap();
+        let ops_b: Vec<(u16, Option<bool>)> = b.arbitrary(256).unwrap();
+        assert_eq!(ops_a, ops_b);
+        let bytes = a.arbitrary_bytes(13);
+        assert_eq!(bytes.as_bytes().len(), 13);
+        let mut u = bytes.unstructured();
+        let _: u32 = u.arbitrary().unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::timing::{TimePoint, NANOS_PER_SEC};
+use ::chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+
+fn delta_nanos(delta: TimeDelta) -> u128 {
+    delta.num_seconds() as u128 * NANOS_PER_SEC + delta.subsec_nanos() as u128
+}
+
+fn nanos_delta(nanos: u128) -> TimeDelta {
+    TimeDelta::new(
+        (nanos / NANOS_PER_SEC) as i64,
+        (nanos % NANOS_PER_SEC) as u32,
+    )
+    .expect("Offset within a valid range")
+}
+
+impl TimePoint for NaiveDate {
+    fn units_since(&self, start: &Self) -> u128 {
+        (*self - *start).num_days() as u128
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + Days::new(units as u64)
+    }
+}
+
+impl TimePoint for NaiveTime {
+    fn units_since(&self, start: &Self) -> u128 {
+        delta_nanos(*self - *start)
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + nanos_delta(units)
+    }
+}
+
+impl TimePoint for NaiveDateTime {
+    fn units_since(&self, start: &Self) -> u128 {
+        delta_nanos(*self - *start)
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + nanos_delta(units)
+    }
+}
+
+impl<Tz: TimeZone> TimePoint for DateTime<Tz> {
+    fn units_since(&self, start: &Self) -> u128 {
+        delta_nanos(self.clone() - start.clone())
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        self.clone() + nanos_delta(units)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SplittingRng;
+    use ::chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_chrono_times() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let start = NaiveDate::from_ymd_opt(2024, 2, 27).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let mut leap_days = 0;
+        for _ in 0..1000 {
+            let date = rng.get_time_in(start..end);
+            assert!((start..end).contains(&date));
+            if date == NaiveDate::from_ymd_opt(2024, 2, 29).unwrap() {
+                leap_days += 1;
+            }
+        }
+        assert!(leap_days > 200 && leap_days < 300, "{}", leap_days);
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let five = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+        assert!((nine..five).contains(&rng.get_time_in(nine..five)));
+        let a = Utc.with_ymd_and_hms(1900, 1, 1, 0, 0, 0).unwrap();
+        let b = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        assert!((a..b).contains(&rng.get_time_in(a..b)));
+    }
+}
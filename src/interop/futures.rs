@@ -0,0 +1,104 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use futures_core::Stream;
+use rand::{RngCore, SeedableRng};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// An endless stream of draws from an owned rng
+///
+/// Each value is drawn only when the stream is polled, so a
+/// slow consumer holds the rng back rather than values piling
+/// up, and the values are the same as calling the draw in a loop.
+pub struct RngStream<T: RngCore + SeedableRng, F> {
+    rng: SplittingRng<T>,
+    draw: F,
+}
+
+impl<T: RngCore + SeedableRng, F> RngStream<T, F> {
+    /// The rng, as far as the stream has drawn
+    pub fn rng(&self) -> &SplittingRng<T> {
+        &self.rng
+    }
+
+    /// Stop streaming, handing back the rng
+    pub fn into_rng(self) -> SplittingRng<T> {
+        self.rng
+    }
+}
+
+impl<T, V, F> Stream for RngStream<T, F>
+where
+    T: RngCore + SeedableRng + Unpin,
+    F: FnMut(&mut SplittingRng<T>) -> V + Unpin,
+{
+    type Item = V;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<V>> {
+        let this = self.get_mut();
+        Poll::Ready(Some((this.draw)(&mut this.rng)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<T: RngCore + SeedableRng + Unpin> SplittingRng<T> {
+    /// Stream values from any draw
+    pub fn into_stream_with<V, F>(self, draw: F) -> RngStream<T, F>
+    where
+        F: FnMut(&mut SplittingRng<T>) -> V + Unpin,
+    {
+        RngStream { rng: self, draw }
+    }
+
+    /// Stream values from `get_u64`
+    pub fn into_u64_stream(self) -> impl Stream<Item = u64> {
+        self.into_stream_with(|rng| rng.get_u64())
+    }
+
+    /// Stream values from `get_bool`
+    pub fn into_bool_stream(self) -> impl Stream<Item = bool> {
+        self.into_stream_with(|rng| rng.get_bool())
+    }
+
+    /// Stream rolls from `fair_roll`
+    pub fn into_roll_stream(self, sides: u32) -> impl Stream<Item = u32> {
+        self.into_stream_with(move |rng| rng.fair_roll(sides))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::task::Waker;
+
+    fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(stream).poll_next(&mut cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn test_streams_match_calls() {
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let mut rolls = rng.into_roll_stream(6);
+        for _ in 0..10 {
+            assert_eq!(next(&mut rolls), Some(copy.fair_roll(6)));
+        }
+        let mut angles = copy.clone().into_stream_with(|r| r.get_angle());
+        assert_eq!(next(&mut angles), Some(copy.get_angle()));
+        assert_eq!(angles.into_rng().steps(), copy.steps());
+        let mut words = SplittingRng::<Xoshiro256StarStar>::new(12345).into_u64_stream();
+        let mut again = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(next(&mut words), Some(again.get_u64()));
+    }
+}
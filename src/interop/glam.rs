@@ -0,0 +1,86 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::glam::{Mat3, Quat, Vec2, Vec3};
+use rand::{RngCore, SeedableRng};
+
+fn vec2(v: [f64; 2]) -> Vec2 {
+    Vec2::new(v[0] as f32, v[1] as f32)
+}
+
+fn vec3(v: [f64; 3]) -> Vec3 {
+    Vec3::new(v[0] as f32, v[1] as f32, v[2] as f32)
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a point uniformly within the rectangle from `min` to `max`,
+    /// as `get_point_in_rect`
+    ///
+    /// Narrowing to `f32` may round a coordinate up to `max`, so
+    /// unlike `get_point_in_rect` both bounds are inclusive.
+    pub fn get_glam_vec2_in_rect(&mut self, min: Vec2, max: Vec2) -> Vec2 {
+        vec2(self.get_point_in_rect(min.as_dvec2().into(), max.as_dvec2().into()))
+    }
+
+    /// Provide a point uniformly within the box from `min` to `max`,
+    /// as `get_point_in_box`, with both bounds inclusive as for
+    /// `get_glam_vec2_in_rect`
+    pub fn get_glam_vec3_in_box(&mut self, min: Vec3, max: Vec3) -> Vec3 {
+        vec3(self.get_point_in_box(min.as_dvec3().into(), max.as_dvec3().into()))
+    }
+
+    /// Provide a 2D unit vector, as `get_unit_vec2`
+    pub fn get_glam_unit_vec2(&mut self) -> Vec2 {
+        vec2(self.get_unit_vec2())
+    }
+
+    /// Provide a 3D unit vector, as `get_unit_vec3`
+    pub fn get_glam_unit_vec3(&mut self) -> Vec3 {
+        vec3(self.get_unit_vec3())
+    }
+
+    /// Provide a point uniformly within a ball, as `get_point_in_ball`
+    pub fn get_glam_vec3_in_ball(&mut self, center: Vec3, radius: f32) -> Vec3 {
+        vec3(self.get_point_in_ball(center.as_dvec3().into(), radius as f64))
+    }
+
+    /// Provide a uniformly random rotation, as `get_quaternion`
+    pub fn get_glam_quat(&mut self) -> Quat {
+        let [x, y, z, w] = self.get_quaternion();
+        Quat::from_xyzw(x as f32, y as f32, z as f32, w as f32).normalize()
+    }
+
+    /// Provide a uniformly random rotation matrix
+    pub fn get_glam_rotation(&mut self) -> Mat3 {
+        Mat3::from_quat(self.get_glam_quat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_glam_values() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..100 {
+            let p = rng.get_glam_vec3_in_box(Vec3::ZERO, Vec3::new(1.0, 2.0, 3.0));
+            assert!(p.cmpge(Vec3::ZERO).all() && p.cmple(Vec3::new(1.0, 2.0, 3.0)).all());
+            let b = rng.get_glam_vec3_in_ball(Vec3::X, 2.0);
+            assert!(b.distance(Vec3::X) <= 2.0 + 1e-5);
+            assert!((rng.get_glam_unit_vec3().length() - 1.0).abs() < 1e-5);
+            let m = rng.get_glam_rotation();
+            assert!((m.determinant() - 1.0).abs() < 1e-5);
+            // Rotations keep lengths
+            assert!(((m * Vec3::Y).length() - 1.0).abs() < 1e-5);
+        }
+        let r = rng.get_glam_vec2_in_rect(Vec2::ZERO, Vec2::ONE);
+        assert!(r.cmpge(Vec2::ZERO).all() && r.cmple(Vec2::ONE).all());
+        // Just below 1 in f64 is 1 in f32
+        let narrowed = vec2([1.0 - f64::EPSILON, 0.5]);
+        assert_eq!(narrowed.x, 1.0);
+    }
+}
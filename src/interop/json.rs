@@ -0,0 +1,182 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::distributions::{DiscreteDistribution, Distribution, DistributionError};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use serde_json::{Map, Number, Value};
+
+/// The kinds of JSON value a generator can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+const KINDS: [JsonKind; 6] = [
+    JsonKind::Null,
+    JsonKind::Bool,
+    JsonKind::Number,
+    JsonKind::String,
+    JsonKind::Array,
+    JsonKind::Object,
+];
+
+/// Collects the limits and type mix of a `JsonGenerator`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonGeneratorBuilder {
+    max_depth: usize,
+    max_len: usize,
+    max_string_len: usize,
+    weights: [f64; 6],
+}
+
+impl JsonGeneratorBuilder {
+    /// How deeply arrays and objects may nest, where
+    /// containers at the deepest level are left empty
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The most items in an array or keys in an object
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// The longest string value, in characters
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    /// How often a kind of value is chosen, relative to the others
+    pub fn weight(mut self, kind: JsonKind, weight: f64) -> Self {
+        self.weights[kind as usize] = weight;
+        self
+    }
+
+    /// Validate the weights and build the generator
+    pub fn build(self) -> Result<JsonGenerator, DistributionError> {
+        Ok(JsonGenerator {
+            max_depth: self.max_depth,
+            max_len: self.max_len,
+            max_string_len: self.max_string_len,
+            kinds: DiscreteDistribution::new(&self.weights)?,
+        })
+    }
+}
+
+/// A reproducible source of random JSON documents
+///
+/// Every kind of value is equally likely by default, with
+/// up to 3 levels of nesting and 4 items per container.
+/// Numbers are integers half of the time and finite floats
+/// otherwise, and strings and keys are ASCII alphanumeric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonGenerator {
+    max_depth: usize,
+    max_len: usize,
+    max_string_len: usize,
+    kinds: DiscreteDistribution,
+}
+
+impl JsonGenerator {
+    /// Start building a generator
+    pub fn builder() -> JsonGeneratorBuilder {
+        JsonGeneratorBuilder {
+            max_depth: 3,
+            max_len: 4,
+            max_string_len: 12,
+            weights: [1.0; 6],
+        }
+    }
+
+    fn value<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>, depth: usize) -> Value {
+        let nested = depth < self.max_depth;
+        match KINDS[rng.sample(&self.kinds)] {
+            JsonKind::Null => Value::Null,
+            JsonKind::Bool => Value::Bool(rng.get_bool()),
+            JsonKind::Number if rng.get_bool() => Value::from(rng.get_u64() as i64),
+            JsonKind::Number => {
+                let float = (rng.get_f64() - 0.5) * 2e6;
+                Value::Number(Number::from_f64(float).expect("Floats drawn are finite"))
+            }
+            JsonKind::String => Value::String(self.string(rng)),
+            JsonKind::Array if nested => {
+                let len = rng.up_to(self.max_len as u32) as usize;
+                Value::Array((0..len).map(|_| self.value(rng, depth + 1)).collect())
+            }
+            JsonKind::Array => Value::Array(Vec::new()),
+            JsonKind::Object if nested => {
+                let len = rng.up_to(self.max_len as u32) as usize;
+                let mut map = Map::new();
+                for _ in 0..len {
+                    let key = self.string(rng);
+                    map.insert(key, self.value(rng, depth + 1));
+                }
+                Value::Object(map)
+            }
+            JsonKind::Object => Value::Object(Map::new()),
+        }
+    }
+
+    fn string<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> String {
+        let len = rng.up_to(self.max_string_len as u32) as usize;
+        rng.get_alphanumeric(len)
+    }
+}
+
+impl Distribution<Value> for JsonGenerator {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> Value {
+        self.value(rng, 0)
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a random JSON document, see `JsonGenerator`
+    pub fn get_json(&mut self, generator: &JsonGenerator) -> Value {
+        generator.sample(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    fn depth(value: &Value) -> usize {
+        match value {
+            Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+            Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_json_documents() {
+        let generator = JsonGenerator::builder()
+            .max_depth(2)
+            .weight(JsonKind::Object, 3.0)
+            .weight(JsonKind::Null, 0.0)
+            .build()
+            .unwrap();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut again = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..200 {
+            let doc = rng.get_json(&generator);
+            assert!(depth(&doc) <= 3, "{}", doc);
+            assert!(!doc.to_string().contains("null"), "{}", doc);
+            assert_eq!(doc, again.get_json(&generator));
+        }
+        let none = JsonGenerator::builder();
+        let none = KINDS.iter().fold(none, |b, k| b.weight(*k, 0.0)).build();
+        assert_eq!(none, Err(DistributionError::ZeroTotal));
+    }
+}
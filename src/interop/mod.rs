@@ -0,0 +1,51 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Optional integrations with other crates,
+//! each behind a feature of the same name.
+//!
+//! The rng is built on `rand` 0.8, whose backends it always
+//! takes. The `rand08` feature, on by default, lends the rng to
+//! code written for `rand` 0.8 through `AsRngCore`, and the
+//! `rand09` feature bridges to `rand_core` 0.9 in both directions.
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "chacha")]
+mod chacha;
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "futures")]
+mod futures;
+#[cfg(feature = "glam")]
+mod glam;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "num-bigint")]
+mod num_bigint;
+#[cfg(feature = "proptest")]
+mod proptest;
+#[cfg(feature = "quickcheck")]
+mod quickcheck;
+#[cfg(feature = "rand09")]
+mod rand09;
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "uuid")]
+mod uuid;
+
+#[cfg(feature = "arbitrary")]
+pub use self::arbitrary::ArbitraryBytes;
+#[cfg(feature = "chacha")]
+pub use self::chacha::RobustSplittingRng;
+#[cfg(feature = "futures")]
+pub use self::futures::RngStream;
+#[cfg(feature = "json")]
+pub use self::json::{JsonGenerator, JsonGeneratorBuilder, JsonKind};
+#[cfg(feature = "rand09")]
+pub use self::rand09::Rand09;
@@ -0,0 +1,74 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::nalgebra::{Point3, Quaternion, Rotation3, UnitQuaternion, Vector2, Vector3};
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a point uniformly within the rectangle from `min` to `max`,
+    /// as `get_point_in_rect`
+    pub fn get_nalgebra_vector2_in_rect(
+        &mut self,
+        min: Vector2<f64>,
+        max: Vector2<f64>,
+    ) -> Vector2<f64> {
+        self.get_point_in_rect(min.into(), max.into()).into()
+    }
+
+    /// Provide a point uniformly within the box from `min` to `max`,
+    /// as `get_point_in_box`
+    pub fn get_nalgebra_point3_in_box(
+        &mut self,
+        min: Point3<f64>,
+        max: Point3<f64>,
+    ) -> Point3<f64> {
+        self.get_point_in_box(min.coords.into(), max.coords.into())
+            .into()
+    }
+
+    /// Provide a 3D unit vector, as `get_unit_vec3`
+    pub fn get_nalgebra_unit_vector3(&mut self) -> Vector3<f64> {
+        self.get_unit_vec3().into()
+    }
+
+    /// Provide a point uniformly within a ball, as `get_point_in_ball`
+    pub fn get_nalgebra_point3_in_ball(&mut self, center: Point3<f64>, radius: f64) -> Point3<f64> {
+        self.get_point_in_ball(center.coords.into(), radius).into()
+    }
+
+    /// Provide a uniformly random rotation, as `get_quaternion`
+    pub fn get_nalgebra_rotation(&mut self) -> UnitQuaternion<f64> {
+        let [x, y, z, w] = self.get_quaternion();
+        UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z))
+    }
+
+    /// Provide a uniformly random rotation matrix
+    pub fn get_nalgebra_rotation_matrix(&mut self) -> Rotation3<f64> {
+        self.get_nalgebra_rotation().to_rotation_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_nalgebra_values() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let origin = Point3::origin();
+        for _ in 0..100 {
+            let p = rng.get_nalgebra_point3_in_box(origin, Point3::new(1.0, 2.0, 3.0));
+            assert!(p.x < 1.0 && p.y < 2.0 && p.z < 3.0);
+            let b = rng.get_nalgebra_point3_in_ball(origin, 2.0);
+            assert!(b.coords.norm() <= 2.0);
+            assert!((rng.get_nalgebra_unit_vector3().norm() - 1.0).abs() < 1e-9);
+            let m = rng.get_nalgebra_rotation_matrix();
+            assert!((m.matrix().determinant() - 1.0).abs() < 1e-9);
+        }
+        let r = rng.get_nalgebra_vector2_in_rect(Vector2::zeros(), Vector2::new(1.0, 1.0));
+        assert!(r.x < 1.0 && r.y < 1.0);
+    }
+}
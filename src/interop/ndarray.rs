@@ -0,0 +1,80 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::distributions::{Distribution, DistributionError, Normal};
+use crate::SplittingRng;
+use ::ndarray::{Array, ArrayBase, DataMut, Dimension, ShapeBuilder};
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Fill an array with draws from a distribution
+    ///
+    /// The entries are filled in logical order, last index fastest,
+    /// so the values don't depend on the memory layout.
+    pub fn fill_ndarray<S, D, V>(&mut self, array: &mut ArrayBase<S, D>, dist: &V)
+    where
+        S: DataMut<Elem = f64>,
+        D: Dimension,
+        V: Distribution<f64>,
+    {
+        array.iter_mut().for_each(|x| *x = dist.sample(self));
+    }
+
+    /// Provide an array of any shape, such as `(rows, cols)`,
+    /// filled with draws from a distribution
+    pub fn get_ndarray<Sh, V>(&mut self, shape: Sh, dist: &V) -> Array<f64, Sh::Dim>
+    where
+        Sh: ShapeBuilder,
+        V: Distribution<f64>,
+    {
+        let mut array = Array::zeros(shape);
+        self.fill_ndarray(&mut array, dist);
+        array
+    }
+
+    /// Provide an array filled with values from `get_f64`
+    pub fn get_uniform_ndarray<Sh: ShapeBuilder>(&mut self, shape: Sh) -> Array<f64, Sh::Dim> {
+        let mut array = Array::zeros(shape);
+        array.iter_mut().for_each(|x| *x = self.get_f64());
+        array
+    }
+
+    /// Provide an array filled with normal values
+    /// Requires a standard deviation above zero
+    pub fn get_normal_ndarray<Sh: ShapeBuilder>(
+        &mut self,
+        shape: Sh,
+        mean: f64,
+        std_dev: f64,
+    ) -> Result<Array<f64, Sh::Dim>, DistributionError> {
+        Ok(self.get_ndarray(shape, &Normal::new(mean, std_dev)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::ndarray::{Array1, Array2};
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_random_arrays() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let a: Array2<f64> = rng.get_uniform_ndarray((3, 4));
+        assert_eq!(a.dim(), (3, 4));
+        assert_eq!(a[[0, 1]], {
+            copy.get_f64();
+            copy.get_f64()
+        });
+        // Column-major arrays are filled in the same logical order
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let f: Array2<f64> = rng.get_uniform_ndarray((3, 4).f());
+        assert_eq!(a, f);
+        let v: Array1<f64> = rng.get_normal_ndarray(20000, 5.0, 2.0).unwrap();
+        let mean = v.mean().unwrap();
+        assert!((mean - 5.0).abs() < 0.05, "{}", mean);
+        assert!(rng.get_normal_ndarray(3, 0.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::num_bigint::BigUint;
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Roll fairly below a bound of any size,
+    /// or `None` if the bound is zero
+    ///
+    /// Draws a step for each 64 bits of the bound, masks
+    /// off the bits above its highest bit, and draws again
+    /// if the result is too large, which is less than half
+    /// of the time. Reproducible, not secure.
+    pub fn fair_roll_biguint(&mut self, bound: &BigUint) -> Option<BigUint> {
+        let bits = bound.bits();
+        if bits == 0 {
+            return None;
+        }
+        let words = bits.div_ceil(64) as usize;
+        let spare = (words as u64 * 64 - bits) as u32;
+        loop {
+            let mut digits: Vec<u32> = Vec::with_capacity(words * 2);
+            for i in 0..words {
+                let mut word = self.get_u64();
+                if i == words - 1 {
+                    word = word.checked_shr(spare).unwrap_or(0);
+                }
+                digits.push(word as u32);
+                digits.push((word >> 32) as u32);
+            }
+            let value = BigUint::new(digits);
+            if &value < bound {
+                return Some(value);
+            }
+        }
+    }
+
+    /// Roll fairly within `low..high`, or `None` if it is empty
+    pub fn fair_roll_biguint_range(&mut self, low: &BigUint, high: &BigUint) -> Option<BigUint> {
+        if low >= high {
+            return None;
+        }
+        let offset = self.fair_roll_biguint(&(high - low))?;
+        Some(low + offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_biguint_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let bound = BigUint::from(1u8) << 200u32;
+        let bound = bound + 7u8;
+        let mut high = 0;
+        for _ in 0..1000 {
+            let roll = rng.fair_roll_biguint(&bound).unwrap();
+            assert!(roll < bound);
+            if roll.bits() == 200 {
+                high += 1;
+            }
+        }
+        // About half of the values below 2^200 have their top bit set
+        assert!((high - 500i32).abs() < 60, "{}", high);
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            let roll = rng.fair_roll_biguint(&BigUint::from(3u8)).unwrap();
+            counts[roll.to_u32_digits().first().copied().unwrap_or(0) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|c| (*c - 1000i32).abs() < 100),
+            "{:?}",
+            counts
+        );
+        assert_eq!(rng.fair_roll_biguint(&BigUint::from(0u8)), None);
+        let low = BigUint::from(10u8);
+        let high = BigUint::from(11u8);
+        assert_eq!(rng.fair_roll_biguint_range(&low, &high), Some(low.clone()));
+        assert_eq!(rng.fair_roll_biguint_range(&high, &low), None);
+    }
+}
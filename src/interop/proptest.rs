@@ -0,0 +1,55 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Split off a proptest `TestRng`
+    ///
+    /// Fills a full ChaCha seed from four steps of this rng,
+    /// so a failing case can be replayed by recreating this
+    /// rng from its origin and splitting at the same point.
+    pub fn proptest_rng(&mut self) -> TestRng {
+        let mut seed = [0; 32];
+        for chunk in seed.chunks_exact_mut(8) {
+            chunk.copy_from_slice(&self.get_u64().to_le_bytes());
+        }
+        TestRng::from_seed(RngAlgorithm::ChaCha, &seed)
+    }
+
+    /// Split off a proptest `TestRunner` driven by `proptest_rng`
+    pub fn proptest_runner(&mut self, config: Config) -> TestRunner {
+        TestRunner::new_with_rng(config, self.proptest_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SplittingRng;
+    use ::proptest::strategy::{Strategy, ValueTree};
+    use ::proptest::test_runner::Config;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_proptest_replay() {
+        let mut a = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut b = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let strategy = ::proptest::collection::vec(0u32..1000, 0..20);
+        let mut runner_a = a.proptest_runner(Config::default());
+        let mut runner_b = b.proptest_runner(Config::default());
+        for _ in 0..10 {
+            let va = strategy.new_tree(&mut runner_a).unwrap().current();
+            let vb = strategy.new_tree(&mut runner_b).unwrap().current();
+            assert_eq!(va, vb);
+        }
+        runner_a
+            .run(&(0u8..10), |x| {
+                assert!(x < 10);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
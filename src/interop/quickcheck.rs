@@ -0,0 +1,47 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::quickcheck::Gen;
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Split off a quickcheck `Gen` with the given size
+    ///
+    /// Like `split`, this advances the rng by one step and
+    /// seeds the generator from it, so property tests share
+    /// the same origin as the rest of the program. Pass it to
+    /// `QuickCheck::new().rng(gen)` to drive a whole test run.
+    ///
+    /// Quickcheck's own generator is used under the hood, so
+    /// the values may change between quickcheck releases.
+    pub fn quickcheck_gen(&mut self, size: usize) -> Gen {
+        Gen::from_size_and_seed(size, self.get_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SplittingRng;
+    use ::quickcheck::{Arbitrary, QuickCheck};
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_quickcheck_gen() {
+        let mut a = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut b = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let from_a = Vec::<u32>::arbitrary(&mut a.quickcheck_gen(50));
+        let from_b = Vec::<u32>::arbitrary(&mut b.quickcheck_gen(50));
+        assert_eq!(from_a, from_b);
+        fn reverse_twice(v: Vec<u8>) -> bool {
+            let mut r = v.clone();
+            r.reverse();
+            r.reverse();
+            r == v
+        }
+        QuickCheck::new()
+            .rng(a.quickcheck_gen(20))
+            .quickcheck(reverse_twice as fn(Vec<u8>) -> bool);
+    }
+}
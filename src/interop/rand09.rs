@@ -0,0 +1,119 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use rand_core09 as core09;
+
+/// Wraps a `rand_core` 0.9 backend so a `SplittingRng` can use it
+///
+/// Seeding goes through the backend's own `seed_from_u64`, so
+/// `SplittingRng::<Rand09<R>>::new(origin)` starts from the same
+/// state as `R::seed_from_u64(origin)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rand09<R>(pub R);
+
+impl<R: core09::RngCore> RngCore for Rand09<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<R: core09::SeedableRng> SeedableRng for Rand09<R> {
+    type Seed = R::Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Rand09(R::from_seed(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Rand09(R::seed_from_u64(state))
+    }
+}
+
+/// Lends the rng to code written for `rand_core` 0.9
+///
+/// Words come from `get_u32` and `get_u64`, so every draw is
+/// counted. Bytes are the same as read from `into_reader`, the
+/// highest seven of each `get_u64` unless a finalizer is in use.
+impl<T: RngCore + SeedableRng> core09::RngCore for SplittingRng<T> {
+    fn next_u32(&mut self) -> u32 {
+        self.get_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.get_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.fill_bytes_recorded(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    // A minimal 0.9 backend, as no 0.9 generator is a dependency
+    struct SplitMix(u64);
+
+    impl core09::RngCore for SplitMix {
+        fn next_u32(&mut self) -> u32 {
+            (self.next_u64() >> 32) as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            crate::keyed::mix64(self.0)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            core09::impls::fill_bytes_via_next(self, dest)
+        }
+    }
+
+    impl core09::SeedableRng for SplitMix {
+        type Seed = [u8; 8];
+
+        fn from_seed(seed: [u8; 8]) -> Self {
+            SplitMix(u64::from_le_bytes(seed))
+        }
+    }
+
+    #[test]
+    fn test_rand09_bridge() {
+        let mut rng = SplittingRng::<Rand09<SplitMix>>::new(12345);
+        let mut backend = <SplitMix as core09::SeedableRng>::seed_from_u64(12345);
+        // The first output seeds the bool pool
+        core09::RngCore::next_u64(&mut backend);
+        assert_eq!(rng.get_u64(), core09::RngCore::next_u64(&mut backend));
+        assert!(rng.fair_roll(6) < 6);
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut bytes = [0; 12];
+        core09::RngCore::fill_bytes(&mut rng, &mut bytes);
+        assert_eq!(bytes[..7], copy.get_u64().to_le_bytes()[1..]);
+        assert_eq!(rng.steps(), 2);
+        let mut read = [0; 12];
+        copy.seek(2);
+        std::io::Read::read_exact(&mut copy.clone().into_reader(), &mut read).unwrap();
+        core09::RngCore::fill_bytes(&mut rng, &mut bytes);
+        assert_eq!(bytes, read);
+    }
+}
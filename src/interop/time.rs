@@ -0,0 +1,80 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::timing::{TimePoint, NANOS_PER_SEC};
+use ::time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time};
+
+fn duration_nanos(duration: Duration) -> u128 {
+    duration.whole_seconds() as u128 * NANOS_PER_SEC + duration.subsec_nanoseconds() as u128
+}
+
+fn nanos_duration(nanos: u128) -> Duration {
+    Duration::new(
+        (nanos / NANOS_PER_SEC) as i64,
+        (nanos % NANOS_PER_SEC) as i32,
+    )
+}
+
+impl TimePoint for Date {
+    fn units_since(&self, start: &Self) -> u128 {
+        (*self - *start).whole_days() as u128
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + Duration::days(units as i64)
+    }
+}
+
+impl TimePoint for Time {
+    fn units_since(&self, start: &Self) -> u128 {
+        duration_nanos(*self - *start)
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + nanos_duration(units)
+    }
+}
+
+impl TimePoint for PrimitiveDateTime {
+    fn units_since(&self, start: &Self) -> u128 {
+        duration_nanos(*self - *start)
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + nanos_duration(units)
+    }
+}
+
+impl TimePoint for OffsetDateTime {
+    fn units_since(&self, start: &Self) -> u128 {
+        duration_nanos(*self - *start)
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + nanos_duration(units)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SplittingRng;
+    use ::time::{Date, Month, OffsetDateTime, Time};
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_time_times() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let start = Date::from_calendar_date(2023, Month::December, 30).unwrap();
+        let end = Date::from_calendar_date(2024, Month::January, 2).unwrap();
+        for _ in 0..100 {
+            assert!((start..end).contains(&rng.get_time_in(start..end)));
+        }
+        let nine = Time::from_hms(9, 0, 0).unwrap();
+        let five = Time::from_hms(17, 0, 0).unwrap();
+        assert!((nine..five).contains(&rng.get_time_in(nine..five)));
+        let a = OffsetDateTime::UNIX_EPOCH;
+        let b = a + ::time::Duration::days(365);
+        assert!((a..b).contains(&rng.get_time_in(a..b)));
+    }
+}
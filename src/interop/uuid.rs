@@ -0,0 +1,38 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+use crate::SplittingRng;
+use ::uuid::{Builder, Uuid};
+use rand::{RngCore, SeedableRng};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a version 4 UUID
+    ///
+    /// The version and variant bits are set correctly, so the
+    /// result is well formed, but it is only as unique as
+    /// the stream it was drawn from. Reproducible, not secure.
+    pub fn get_uuid_v4(&mut self) -> Uuid {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.get_u64().to_be_bytes());
+        bytes[8..].copy_from_slice(&self.get_u64().to_be_bytes());
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_uuid_v4() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let a = rng.get_uuid_v4();
+        assert_eq!(a.get_version_num(), 4);
+        assert_eq!(a.get_variant(), ::uuid::Variant::RFC4122);
+        assert_ne!(a, rng.get_uuid_v4());
+        let mut again = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(a, again.get_uuid_v4());
+    }
+}
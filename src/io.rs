@@ -0,0 +1,87 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Reading random bytes through `std::io::Read`.
+
+use crate::{Finalizer, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::io::{self, Read};
+
+/// An endless reader of random bytes
+///
+/// Each step gives its 7 highest bytes, skipping the low
+/// bits, or all 8 bytes with a finalizer. Read through
+/// `Read::take` for a fixed amount of data.
+pub struct RandomReader<T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    buf: [u8; 8],
+    pos: usize,
+}
+
+impl<T: RngCore + SeedableRng> RandomReader<T> {
+    /// Read random bytes from an rng
+    pub fn new(rng: SplittingRng<T>) -> Self {
+        RandomReader {
+            rng,
+            buf: [0; 8],
+            pos: 8,
+        }
+    }
+
+    /// The rng being read from
+    pub fn get_ref(&self) -> &SplittingRng<T> {
+        &self.rng
+    }
+
+    /// Stop reading, giving back the rng
+    ///
+    /// Any unread bytes of the last step are lost
+    pub fn into_inner(self) -> SplittingRng<T> {
+        self.rng
+    }
+}
+
+impl<T: RngCore + SeedableRng> Read for RandomReader<T> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        for byte in out.iter_mut() {
+            if self.pos == self.buf.len() {
+                self.buf = self.rng.step().to_le_bytes();
+                self.pos = match self.rng.finalizer {
+                    Finalizer::None => 1,
+                    Finalizer::Mix64 => 0,
+                };
+            }
+            *byte = self.buf[self.pos];
+            self.pos += 1;
+        }
+        Ok(out.len())
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Turn this rng into a reader of random bytes
+    pub fn into_reader(self) -> RandomReader<T> {
+        RandomReader::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_random_reader() {
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut data = Vec::new();
+        SplittingRng::<Xoshiro256StarStar>::new(12345)
+            .into_reader()
+            .take(10)
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), 10);
+        assert_eq!(data[..7], copy.get_u64().to_le_bytes()[1..]);
+        assert_eq!(data[7..], copy.get_u64().to_le_bytes()[1..4]);
+    }
+}
@@ -0,0 +1,211 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Endless iterators which borrow an rng, and
+//! `map_with_rng` for giving each item its own child.
+//!
+//! Each item is drawn only when the iterator is advanced,
+//! so `rng.iter_rolls(6).take(10)` steps exactly as ten
+//! calls to `fair_roll` would.
+
+use crate::distributions::DistributionError;
+use crate::keyed::SplitBlock;
+use crate::{ChildSettings, SplittingRng, DENSITY_BITS};
+use rand::{RngCore, SeedableRng};
+use std::marker::PhantomData;
+
+// Each step is split into lanes of `DENSITY_BITS` bits,
+// from the highest down, leaving out the lowest three bits
+const DENSITY_LANES: u32 = 61 / DENSITY_BITS;
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide endless values from `get_u64`
+    pub fn iter_u64(&mut self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::repeat_with(move || self.get_u64())
+    }
+
+    /// Provide endless rolls from `fair_roll`
+    pub fn iter_rolls(&mut self, sides: u32) -> impl Iterator<Item = u32> + '_ {
+        std::iter::repeat_with(move || self.fair_roll(sides))
+    }
+
+    /// Provide endless values from `get_bool`
+    pub fn iter_bools(&mut self) -> impl Iterator<Item = bool> + '_ {
+        std::iter::repeat_with(move || self.get_bool())
+    }
+
+    /// Provide endless bools which are true with probability `p`
+    /// Requires `p` to be within [0, 1]
+    ///
+    /// The high 48 bits of each step are split into three 16-bit
+    /// lanes, and each lane is compared with a fixed threshold, so
+    /// three bools cost one step.
+    /// The probability is rounded to the nearest multiple of 2^-16,
+    /// which is plenty for noise masks but not for tiny odds.
+    pub fn bools_with_density(
+        &mut self,
+        p: f64,
+    ) -> Result<impl Iterator<Item = bool> + '_, DistributionError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(DistributionError::BadProbability);
+        }
+        let threshold = (p * (1u64 << DENSITY_BITS) as f64).round() as u64;
+        let mut word = 0;
+        let mut lanes = 0;
+        Ok(std::iter::repeat_with(move || {
+            if lanes == 0 {
+                word = self.get_u64();
+                lanes = DENSITY_LANES;
+            }
+            lanes -= 1;
+            // Take the high bits, as the low bits are low entropy
+            let lane = word >> (64 - DENSITY_BITS);
+            word <<= DENSITY_BITS;
+            lane < threshold
+        }))
+    }
+
+    /// Provide endless values from any draw
+    pub fn iter_with<'a, V, F>(&'a mut self, mut draw: F) -> impl Iterator<Item = V> + 'a
+    where
+        F: FnMut(&mut Self) -> V + 'a,
+    {
+        std::iter::repeat_with(move || draw(self))
+    }
+}
+
+/// Map the items of any iterator with a child rng each
+pub trait MapWithRng: Iterator + Sized {
+    /// Map each item with its own child of `rng`, found from the
+    /// item's index in this iterator
+    ///
+    /// Takes one step of `rng` up front, as `split_block` does,
+    /// and then none, so an item's value depends only on its index.
+    /// Taking part of the iterator, or advancing it lazily between
+    /// other draws, gives the same values as collecting all of it,
+    /// which splitting inside a plain `map` would not. The
+    /// iterator doesn't borrow `rng`, which stays free to draw.
+    fn map_with_rng<T, F, B>(self, rng: &mut SplittingRng<T>, f: F) -> RngMap<Self, T, F>
+    where
+        T: RngCore + SeedableRng,
+        F: FnMut(&mut SplittingRng<T>, Self::Item) -> B,
+    {
+        let block = rng.split_block(usize::MAX);
+        RngMap {
+            iter: self,
+            settings: rng.child_settings(),
+            block,
+            index: 0,
+            f,
+            rng: PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator> MapWithRng for I {}
+
+/// The iterator from `map_with_rng`
+pub struct RngMap<I, T: RngCore + SeedableRng, F> {
+    iter: I,
+    settings: ChildSettings,
+    block: SplitBlock,
+    index: usize,
+    f: F,
+    rng: PhantomData<fn() -> SplittingRng<T>>,
+}
+
+impl<I, T, F, B> Iterator for RngMap<I, T, F>
+where
+    I: Iterator,
+    T: RngCore + SeedableRng,
+    F: FnMut(&mut SplittingRng<T>, I::Item) -> B,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<B> {
+        let item = self.iter.next()?;
+        let mut child = self.settings.child(self.block.origin(self.index));
+        self.index += 1;
+        Some((self.f)(&mut child, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_iterators_match_calls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let rolls: Vec<u32> = rng.iter_rolls(6).take(10).collect();
+        let expected: Vec<u32> = (0..10).map(|_| copy.fair_roll(6)).collect();
+        assert_eq!(rolls, expected);
+        assert_eq!(rng.iter_u64().next(), Some(copy.get_u64()));
+        let angles: Vec<f64> = rng.iter_with(|r| r.get_angle()).take(3).collect();
+        assert_eq!(angles[0], copy.get_angle());
+        assert_eq!(rng.iter_bools().take(61).count(), 61);
+    }
+
+    #[test]
+    fn test_map_with_rng() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let names = ["ant", "bee", "cat", "dog"];
+        let all: Vec<(&str, u32)> = names
+            .iter()
+            .map_with_rng(&mut rng, |r, name| (*name, r.fair_roll(100)))
+            .collect();
+        assert_eq!(rng.steps(), 1);
+        // Taking a few, between other draws, changes nothing
+        let mut lazy = names
+            .iter()
+            .map_with_rng(&mut copy, |r, name| (*name, r.fair_roll(100)));
+        assert_eq!(lazy.next(), Some(all[0]));
+        assert_eq!(lazy.nth(2), Some(all[3]));
+        let mut block_copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let block = block_copy.split_block(usize::MAX);
+        assert_eq!(block_copy.block_child(&block, 2).fair_roll(100), all[2].1);
+    }
+
+    #[test]
+    fn test_map_with_rng_between_draws() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let expected: Vec<u32> = (0..4)
+            .map_with_rng(&mut copy, |r, _| r.fair_roll(100))
+            .collect();
+        let lazy = (0..4).map_with_rng(&mut rng, |r, _| r.fair_roll(100));
+        let mut got = Vec::new();
+        for value in lazy {
+            got.push(value);
+            rng.fair_roll(6);
+        }
+        assert_eq!(got, expected);
+        assert_eq!(rng.steps(), 5);
+    }
+
+    #[test]
+    fn test_bool_density() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 30000;
+        let hits = rng
+            .bools_with_density(0.3)
+            .unwrap()
+            .take(iter)
+            .filter(|b| *b)
+            .count();
+        assert_eq!(rng.steps(), iter as u64 / 3);
+        let frac = hits as f64 / iter as f64;
+        assert!((frac - 0.3).abs() < 0.01, "{}", frac);
+        assert!(rng.bools_with_density(1.0).unwrap().take(100).all(|b| b));
+        assert!(!rng.bools_with_density(0.0).unwrap().take(100).any(|b| b));
+        assert!(rng.bools_with_density(1.5).is_err());
+    }
+}
@@ -0,0 +1,588 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Stateless randomness keyed by coordinates,
+//! other small integer keys, bytes or hashable values.
+//!
+//! Keyed values depend only on the origin of the
+//! rng and the key, never on how far the rng has been
+//! stepped, so they can be looked up in any order.
+//! Since every child takes its origin from its parent's
+//! stream, the origin already identifies the lineage.
+//! Named substreams, keyed splits and the rngs for world
+//! coordinates are found the same way, and registered
+//! children start from their substream.
+//!
+//! Blocks of children are numbered from a single step,
+//! so their seeds can be handed out in bulk, to a GPU kernel
+//! with one seed per thread for example.
+//!
+//! The tree of registered children can be drawn as
+//! Graphviz DOT or as indented text, for checking how the
+//! randomness of a simulation is laid out.
+
+use crate::{SplitMix64, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+// Separates keyed values from any other use of the origin
+const KEYED_SALT: u64 = 0x6b65_7965_645f_7631;
+// Separates substream origins from keyed values
+const SUBSTREAM_SALT: u64 = 0x7375_6273_7472_6d31;
+// Separates keyed splits from named substreams
+const SPLIT_SALT: u64 = 0x7370_6c69_745f_6b31;
+// Separates tagged splits from plain ones
+const TAG_SALT: u64 = 0x7461_6767_6564_5f31;
+// Separates block children from tagged splits
+const BLOCK_SALT: u64 = 0x626c_6f63_6b5f_7631;
+
+/// The SplitMix64 finalizer, a fast and well
+/// distributed bijection on u64
+pub(crate) fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fold one more word into a running hash
+pub(crate) fn mix_word(state: u64, word: u64) -> u64 {
+    mix64(state ^ word.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+}
+
+/// The keyed value for an origin, shared with the noise module
+pub(crate) fn keyed_value<K: Key>(origin: u64, key: K) -> u64 {
+    mix64(key.mix_into(mix64(origin ^ KEYED_SALT)))
+}
+
+/// A value which can key a stateless lookup
+///
+/// Signed integers are sign extended, so `-1i32` and
+/// `-1i64` are the same key. The mixing is fixed and does
+/// not depend on the platform or the standard library's `Hash`.
+pub trait Key {
+    /// Fold this key into a running hash
+    fn mix_into(&self, state: u64) -> u64;
+}
+
+macro_rules! unsigned_key {
+    ($($t:ty),*) => {
+        $(impl Key for $t {
+            fn mix_into(&self, state: u64) -> u64 {
+                mix_word(state, *self as u64)
+            }
+        })*
+    };
+}
+
+macro_rules! signed_key {
+    ($($t:ty),*) => {
+        $(impl Key for $t {
+            fn mix_into(&self, state: u64) -> u64 {
+                mix_word(state, *self as i64 as u64)
+            }
+        })*
+    };
+}
+
+unsigned_key!(u8, u16, u32, u64, usize);
+signed_key!(i8, i16, i32, i64, isize);
+
+/// Bytes are mixed by their length, then in
+/// little-endian words of eight
+impl Key for [u8] {
+    fn mix_into(&self, state: u64) -> u64 {
+        mix_bytes(state, self)
+    }
+}
+
+/// Strings are mixed as their bytes, so `"id"`
+/// and `b"id"` are the same key
+impl Key for str {
+    fn mix_into(&self, state: u64) -> u64 {
+        mix_bytes(state, self.as_bytes())
+    }
+}
+
+fn mix_bytes(state: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .chunks(8)
+        .fold(mix_word(state, bytes.len() as u64), |s, chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            mix_word(s, u64::from_le_bytes(word))
+        })
+}
+
+/// A key made from any `Hash` value, such as an entity's
+/// UUID or a file path, fed through a `KeyHasher`
+///
+/// The hash is only as stable as the value's `Hash` impl. Derived
+/// impls on integers, strings and bytes are stable, but the standard
+/// library does not promise the same of every type it provides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hashed<H>(pub H);
+
+impl<H: Hash> Key for Hashed<H> {
+    fn mix_into(&self, state: u64) -> u64 {
+        let mut hasher = KeyHasher::new(state);
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// A `Hasher` which gives the same hash on every
+/// platform and in every version of this crate
+///
+/// Each write is mixed as a `Key`: integers as their value,
+/// `usize` and `isize` as 64-bit, and byte writes as a
+/// length then little-endian words, as `[u8]` keys are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyHasher {
+    state: u64,
+}
+
+impl KeyHasher {
+    /// Start hashing from the given state
+    pub fn new(state: u64) -> Self {
+        KeyHasher { state }
+    }
+}
+
+impl Default for KeyHasher {
+    fn default() -> Self {
+        KeyHasher::new(0)
+    }
+}
+
+macro_rules! hasher_writes {
+    ($($name:ident: $t:ty),*) => {
+        $(fn $name(&mut self, i: $t) {
+            self.state = i.mix_into(self.state);
+        })*
+    };
+}
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = mix_bytes(self.state, bytes);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.state = mix_word(mix_word(self.state, i as u64), (i >> 64) as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    hasher_writes!(
+        write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_usize: usize,
+        write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_isize: isize
+    );
+}
+
+impl<K: Key + ?Sized> Key for &K {
+    fn mix_into(&self, state: u64) -> u64 {
+        (**self).mix_into(state)
+    }
+}
+
+impl<A: Key, B: Key> Key for (A, B) {
+    fn mix_into(&self, state: u64) -> u64 {
+        self.1.mix_into(self.0.mix_into(state))
+    }
+}
+
+impl<A: Key, B: Key, C: Key> Key for (A, B, C) {
+    fn mix_into(&self, state: u64) -> u64 {
+        self.2.mix_into(self.1.mix_into(self.0.mix_into(state)))
+    }
+}
+
+impl<A: Key, B: Key, C: Key, D: Key> Key for (A, B, C, D) {
+    fn mix_into(&self, state: u64) -> u64 {
+        self.3
+            .mix_into(self.2.mix_into(self.1.mix_into(self.0.mix_into(state))))
+    }
+}
+
+impl<K: Key, const N: usize> Key for [K; N] {
+    fn mix_into(&self, state: u64) -> u64 {
+        self.iter().fold(state, |s, k| k.mix_into(s))
+    }
+}
+
+/// The children of one `split_block`, numbered from 0
+///
+/// Holds only the step it was split at, and works out each
+/// child's origin from its number on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SplitBlock {
+    key: u64,
+    len: usize,
+}
+
+impl SplitBlock {
+    /// The number of children
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no children
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The origin of child `i`
+    ///
+    /// Panics if `i` is not below the length
+    pub fn origin(&self, i: usize) -> u64 {
+        assert!(i < self.len, "Child out of range of the block");
+        mix64((i as u64).mix_into(mix64(self.key ^ BLOCK_SALT)))
+    }
+
+    /// The origins of every child, in order
+    pub fn origins(&self) -> Vec<u64> {
+        (0..self.len).map(|i| self.origin(i)).collect()
+    }
+
+    /// The 32-byte seeds of every child, in order
+    ///
+    /// Each is the origin expanded by SplitMix64, which is the
+    /// seed that `Seeding::SplitMix` gives a backend with a 32-byte
+    /// seed, such as `Xoshiro256StarStar`. A kernel seeding its own
+    /// xoshiro256** from these follows the same streams as the
+    /// children from `block_child` of an rng seeded that way.
+    pub fn seeds(&self) -> Vec<[u8; 32]> {
+        (0..self.len)
+            .map(|i| {
+                let mut seed = [0; 32];
+                SplitMix64::seed_from_u64(self.origin(i)).fill_bytes(&mut seed);
+                seed
+            })
+            .collect()
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Look up the random value for a key, without
+    /// advancing this rng
+    ///
+    /// The same origin and key always give the same value,
+    /// so a world generator can ask for "the value for this tile"
+    /// with `rng.value_at((chunk_x, chunk_y))` in any order.
+    pub fn value_at<K: Key>(&self, key: K) -> u64 {
+        keyed_value(self.origin, key)
+    }
+
+    /// Provide the child stream with the given name, without
+    /// advancing this rng
+    ///
+    /// A name always gives the same stream for the same origin,
+    /// however the rng was used before. Comparing two policies
+    /// in a simulation with `substream("arrivals")` for the
+    /// arrivals in both runs gives them common random numbers,
+    /// so the difference between runs is not noise.
+    pub fn substream(&self, name: &str) -> SplittingRng<T> {
+        self.child_at(mix64(name.mix_into(mix64(self.origin ^ SUBSTREAM_SALT))))
+    }
+
+    /// The registered child with the given name, which keeps its
+    /// position from one call to the next
+    ///
+    /// A child starts as `substream(name)` the first time it is
+    /// asked for, and is kept by this rng from then on, so each
+    /// subsystem can draw from `rng.child("physics")` without
+    /// holding its own rng. Clones copy their children too.
+    pub fn child(&mut self, name: &str) -> &mut SplittingRng<T> {
+        if !self.children.contains_key(name) {
+            let child = self.substream(name);
+            self.children.insert(name.to_string(), child);
+        }
+        self.children
+            .get_mut(name)
+            .expect("Just registered the child")
+    }
+
+    /// The names of the registered children, in order
+    pub fn child_names(&self) -> impl Iterator<Item = &str> {
+        self.children.keys().map(|name| name.as_str())
+    }
+
+    /// Draw this rng and its registered children, and theirs
+    /// in turn, as a Graphviz DOT digraph
+    ///
+    /// Each node is labelled with its name, or `root` for this
+    /// rng, and its summary, as `Display` writes it. Render it
+    /// with `dot -Tsvg`.
+    pub fn lineage_dot(&self) -> String {
+        let mut out = String::from("digraph lineage {\n");
+        let mut next = 0;
+        self.dot_node("root", &mut out, &mut next);
+        out.push_str("}\n");
+        out
+    }
+
+    // Write this node and its children, returning its id
+    fn dot_node(&self, name: &str, out: &mut String, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+        let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(out, "    n{} [label=\"{}\\n{}\"];", id, name, self).expect("Writing to a string");
+        for (child_name, child) in self.children.iter() {
+            let child_id = child.dot_node(child_name, out, next);
+            writeln!(out, "    n{} -> n{};", id, child_id).expect("Writing to a string");
+        }
+        id
+    }
+
+    /// Write this rng and its registered children, and theirs in
+    /// turn, one per line, indented two spaces for each level
+    ///
+    /// Each line holds the name, or `root` for this rng, and its
+    /// summary, as in `physics v1:0000000000003039@10`.
+    pub fn lineage_text(&self) -> String {
+        let mut out = String::new();
+        self.text_node("root", 0, &mut out);
+        out
+    }
+
+    fn text_node(&self, name: &str, depth: usize, out: &mut String) {
+        writeln!(out, "{:indent$}{} {}", "", name, self, indent = 2 * depth)
+            .expect("Writing to a string");
+        for (child_name, child) in self.children.iter() {
+            child.text_node(child_name, depth + 1, out);
+        }
+    }
+
+    /// Split off the child for a key, without advancing this rng
+    ///
+    /// Unlike `split`, the child depends only on the origin and
+    /// the key, so modules which each take `split_keyed(MODULE_ID)`
+    /// get the same streams whatever order they start up in.
+    pub fn split_keyed<K: Key>(&self, key: K) -> SplittingRng<T> {
+        self.child_at(mix64(key.mix_into(mix64(self.origin ^ SPLIT_SALT))))
+    }
+
+    /// Split off a child, as `split` does, with a tag mixed
+    /// into its origin
+    ///
+    /// Children split at the same step with different tags get
+    /// unrelated origins, so if a refactor reorders the splits of
+    /// two subsystems, tagging each with its own id still keeps
+    /// them from ever sharing a stream.
+    pub fn split_tagged(&mut self, tag: u64) -> SplittingRng<T> {
+        let step = self.step();
+        let origin = mix64(tag.mix_into(mix64(step ^ TAG_SALT)));
+        self.record(crate::trace::Method::Split, origin);
+        self.child_at(origin)
+    }
+
+    /// Split off a block of `n` children from one step
+    ///
+    /// Child `i` has an origin worked out from the step and `i`
+    /// alone, so a block of a million children costs no more of
+    /// this rng than one, and the seeds can be taken as a flat
+    /// buffer from `origins` or `seeds`.
+    pub fn split_block(&mut self, n: usize) -> SplitBlock {
+        let key = self.step();
+        self.record(crate::trace::Method::Split, key);
+        SplitBlock { key, len: n }
+    }
+
+    /// Provide child `i` of a block split from this rng,
+    /// inheriting its settings as `split` does
+    ///
+    /// Panics if `i` is not below the length of the block
+    pub fn block_child(&self, block: &SplitBlock, i: usize) -> SplittingRng<T> {
+        self.child_at(block.origin(i))
+    }
+
+    /// Split off the child for a world coordinate, without
+    /// advancing this rng
+    ///
+    /// The same as `split_keyed((x, y))`, so chunks can be
+    /// generated in any order, on any thread, and come out the same.
+    pub fn rng_at(&self, x: i64, y: i64) -> SplittingRng<T> {
+        self.split_keyed((x, y))
+    }
+
+    /// Split off the child for a coordinate within a named layer,
+    /// so `"terrain"` and `"ores"` at one tile are unrelated
+    ///
+    /// The same as `split_keyed((layer, x, y))`.
+    pub fn rng_at_layer(&self, layer: &str, x: i64, y: i64) -> SplittingRng<T> {
+        self.split_keyed((layer, x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Seeding;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_value_at_is_stateless() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let before = rng.value_at((3i32, -7i32));
+        rng.get_u64();
+        assert_eq!(rng.value_at((3i32, -7i32)), before);
+        assert_eq!(rng.value_at((3i64, -7i64)), before);
+        assert_ne!(rng.value_at((-7i32, 3i32)), before);
+        assert_ne!(rng.split().value_at((3i32, -7i32)), before);
+        // Neighbouring keys should look unrelated
+        let mut ones = 0;
+        for x in 0..1000u64 {
+            ones += rng.value_at(x).count_ones();
+        }
+        let avg = ones as f64 / 1000.0;
+        assert!((avg - 32.0).abs() < 0.5, "{}", avg);
+    }
+
+    #[test]
+    fn test_named_children() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let first = rng.substream("physics").get_u64();
+        assert_eq!(rng.child("physics").get_u64(), first);
+        // The registered child carries on rather than restarting
+        let second = rng.child("physics").get_u64();
+        let mut fresh = rng.substream("physics");
+        fresh.get_u64();
+        assert_eq!(fresh.get_u64(), second);
+        rng.child("audio");
+        assert_eq!(rng.child_names().collect::<Vec<_>>(), ["audio", "physics"]);
+        let mut copy = rng.clone();
+        assert_eq!(
+            copy.child("physics").get_u64(),
+            rng.child("physics").get_u64()
+        );
+    }
+
+    #[test]
+    fn test_substreams() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let first = rng.substream("arrivals").get_u64();
+        rng.get_u64();
+        rng.split();
+        assert_eq!(rng.substream("arrivals").get_u64(), first);
+        assert_ne!(rng.substream("service").get_u64(), first);
+        assert_ne!(rng.substream("arrivals").origin(), rng.value_at("arrivals"));
+    }
+
+    #[test]
+    fn test_keyed_splits() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.get_u64();
+        let steps = rng.steps();
+        let audio = rng.split_keyed(7u32);
+        assert_eq!(rng.steps(), steps);
+        assert_eq!(audio.parent_origin(), Some(rng.origin()));
+        assert_eq!(rng.split_keyed(7u64).origin(), audio.origin());
+        assert_ne!(rng.split_keyed(8u32).origin(), audio.origin());
+        assert_ne!(
+            rng.split_keyed("arrivals").origin(),
+            rng.substream("arrivals").origin()
+        );
+    }
+
+    #[test]
+    fn test_tagged_splits() {
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let (mut a, mut b) = (rng.clone(), rng.clone());
+        let audio = a.split_tagged(1);
+        let physics = b.split_tagged(2);
+        assert_eq!(a.steps(), b.steps());
+        assert_ne!(audio.origin(), physics.origin());
+        assert_eq!(audio.origin(), rng.clone().split_tagged(1).origin());
+        assert_ne!(audio.origin(), rng.clone().split().origin());
+        assert_eq!(audio.parent_origin(), Some(rng.origin()));
+    }
+
+    #[test]
+    fn test_byte_and_hashed_keys() {
+        #[derive(Hash)]
+        struct Entity {
+            id: u128,
+            path: &'static str,
+        }
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let bytes: &[u8] = b"saves/world_1.dat";
+        assert_eq!(rng.value_at(bytes), rng.value_at("saves/world_1.dat"));
+        assert_ne!(rng.value_at(bytes), rng.value_at(&bytes[1..]));
+        let key = Hashed(Entity {
+            id: 0x1234_5678_9abc_def0_0fed_cba9_8765_4321,
+            path: "saves/world_1.dat",
+        });
+        let child = rng.split_keyed(&key);
+        assert_eq!(child.origin(), rng.split_keyed(&key).origin());
+        // The hash must not change between platforms or releases
+        assert_eq!(rng.value_at(&key), 7842138268356392068);
+        let mut hasher = KeyHasher::default();
+        hasher.write_usize(3);
+        assert_eq!(hasher.finish(), 3usize.mix_into(0));
+    }
+
+    #[test]
+    fn test_coordinate_rngs() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let tile = rng.rng_at(-3, 8).get_u64();
+        rng.get_u64();
+        let tiles: Vec<u64> = (0..4).rev().map(|x| rng.rng_at(x, 0).get_u64()).collect();
+        assert_eq!(rng.rng_at(-3, 8).get_u64(), tile);
+        assert_eq!(rng.rng_at(0, 0).get_u64(), tiles[3]);
+        assert_ne!(rng.rng_at(8, -3).get_u64(), tile);
+        assert_ne!(rng.rng_at_layer("ores", -3, 8).get_u64(), tile);
+        assert_eq!(
+            rng.rng_at_layer("ores", -3, 8).origin(),
+            rng.split_keyed(("ores", -3i64, 8i64)).origin()
+        );
+    }
+
+    #[test]
+    fn test_split_blocks() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345).seeded_with(Seeding::SplitMix);
+        let steps = rng.steps();
+        let block = rng.split_block(1000);
+        assert_eq!(rng.steps(), steps + 1);
+        let mut origins = block.origins();
+        let seeds = block.seeds();
+        assert_eq!(seeds.len(), 1000);
+        let mut child = rng.block_child(&block, 7);
+        assert_eq!(child.origin(), origins[7]);
+        // The backend's first output fills the bool pool
+        let mut backend = Xoshiro256StarStar::from_seed(seeds[7]);
+        backend.next_u64();
+        assert_eq!(child.get_u64(), backend.next_u64());
+        origins.sort_unstable();
+        origins.dedup();
+        assert_eq!(origins.len(), 1000);
+        assert_ne!(rng.split_block(1000).origin(0), block.origin(0));
+    }
+
+    #[test]
+    fn test_lineage_exports() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.child("physics").child("particles").get_u64();
+        rng.child("say \"hi\"");
+        let text = rng.lineage_text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!("root {}", rng));
+        assert!(lines[1].starts_with("  physics v1:"));
+        assert!(lines[2].starts_with("    particles v1:") && lines[2].ends_with("@1"));
+        let dot = rng.lineage_dot();
+        assert!(dot.starts_with("digraph lineage {\n    n0 [label=\"root\\n"));
+        assert!(
+            dot.contains("n1 -> n2;") && dot.contains("n0 -> n1;") && dot.contains("n0 -> n3;")
+        );
+        assert!(dot.contains("say \\\"hi\\\""));
+        assert!(dot.ends_with("}\n"));
+    }
+}
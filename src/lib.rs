@@ -4,10 +4,298 @@
 
 use fnv::FnvHasher;
 use rand::{RngCore, SeedableRng};
-use std::hash::Hasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+#[cfg(feature = "rand08")]
+pub mod adapter;
+pub mod bag;
+pub mod bits;
+pub mod budget;
+pub mod bulk;
+pub mod color;
+pub mod compat;
+pub mod deck;
+pub mod dice;
+pub mod distributions;
+pub mod dynamic;
+pub mod external;
+pub mod fixed;
+pub mod fuzz;
+pub mod geometry;
+pub mod golden;
+pub mod graphgen;
+pub mod history;
+pub mod interleave;
+pub mod interop;
+pub mod io;
+pub mod iter;
+pub mod keyed;
+pub mod loot;
+pub mod markov;
+pub mod mock;
+pub mod montecarlo;
+pub mod netdata;
+pub mod noise;
+pub mod partition;
+pub mod permutation;
+pub mod pity;
+pub mod playback;
+pub mod prefetch;
+pub mod process;
+pub mod quasi;
+pub mod random;
+pub mod ranges;
+pub mod sampling;
+pub mod search;
+pub mod splitmix;
+pub mod stats;
+pub mod tasks;
+pub mod text;
+pub mod texttable;
+pub mod timing;
+pub mod trace;
+#[cfg(feature = "wordlist")]
+pub mod words;
+
+use bits::BitPool;
+use distributions::Distribution;
+
+#[cfg(feature = "rand08")]
+pub use adapter::AsRngCore;
+pub use dynamic::SplittableRandom;
+#[cfg(feature = "chacha")]
+pub use interop::RobustSplittingRng;
+pub use iter::MapWithRng;
+pub use random::{BoundedRandom, Random, Variants};
+pub use splitmix::SplitMix64;
+#[cfg(feature = "derive")]
+pub use splittable_random_derive::{Random, Variants};
+
+// Lets the derive macro name this crate from inside it
+extern crate self as splittable_random;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use rand::{RngCore, SeedableRng};
+}
 
 const LARGEST_SAFE_INDEX: u8 = 61;
 
+// The bits of precision a density is rounded to, shared by
+// density iterators and grids so they round it the same way
+pub(crate) const DENSITY_BITS: u32 = 16;
+
+/// The version of the algorithms behind each method
+///
+/// Each version is frozen once released, so an origin saved
+/// under one version always replays the same way. Improvements
+/// only ever land in a new version, which has to be asked for.
+/// See the `golden` module for the pinned outputs of each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Algorithm {
+    /// The original algorithms
+    #[default]
+    V1,
+    /// Fixes the last 9 bools of each pool always being true,
+    /// makes `fair_roll` actually reroll biased results, and
+    /// gives `shuffle` full-width keys sorted once
+    V2,
+    /// As `V2`, but `biased_roll` and `fair_roll` scale by a
+    /// widening multiply instead of taking a remainder, so the
+    /// bias of `biased_roll` no longer grows with the sides and
+    /// `fair_roll` rarely needs a division
+    V3,
+}
+
+/// An optional stage applied to every backend output
+///
+/// Most methods shift away the low bits of each step, which
+/// are weak for some backends. A finalizer scrambles every
+/// output so that all 64 bits of `get_u64` are usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Finalizer {
+    /// Use the backend's outputs as they are
+    #[default]
+    None,
+    /// Apply the SplitMix64 finalizer, a multiply-xorshift bijection
+    Mix64,
+}
+
+impl Finalizer {
+    pub(crate) fn apply(self, raw: u64) -> u64 {
+        match self {
+            Finalizer::None => raw,
+            Finalizer::Mix64 => keyed::mix64(raw),
+        }
+    }
+}
+
+/// Which bits of each word the bool pool draws from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BoolPolicy {
+    /// Whatever the algorithm version does, 61 bools per word
+    #[default]
+    Standard,
+    /// Every bit, for backends whose low bits are sound
+    /// or when a finalizer is in use
+    All64,
+    /// Every bit but the lowest three
+    Skip3,
+    /// The set bits of a mask, from lowest to highest
+    Custom(u64),
+}
+
+impl BoolPolicy {
+    /// The bits used from each word for bulk bools, where the
+    /// standard policy uses the same bits as `Skip3`
+    fn bulk_mask(self) -> u64 {
+        match self {
+            BoolPolicy::Standard | BoolPolicy::Skip3 => !0b111,
+            BoolPolicy::All64 => !0,
+            BoolPolicy::Custom(mask) => mask,
+        }
+    }
+
+    fn capacity(self) -> u8 {
+        match self {
+            BoolPolicy::Standard | BoolPolicy::Skip3 => LARGEST_SAFE_INDEX,
+            BoolPolicy::All64 => 64,
+            BoolPolicy::Custom(mask) => mask.count_ones() as u8,
+        }
+    }
+}
+
+/// How `shuffle` orders a list
+///
+/// Choosing another algorithm changes every shuffle, so it is
+/// kept in saved state, and old saves keep their permutations.
+/// All three take two steps per shuffle, whatever the length:
+/// `HashSort` a salt and a hash key, `FisherYates` the seed of
+/// its SplitMix64 stream, and `BatchedHash` a bijection key and
+/// a salt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ShuffleAlgorithm {
+    /// Sort by salted hashes of the indices, as each algorithm
+    /// version does
+    #[default]
+    HashSort,
+    /// Swap items into place from the end, with the swaps drawn
+    /// fairly from a SplitMix64 stream seeded by the two steps.
+    /// Unbiased, and needs no sort or extra allocation.
+    FisherYates,
+    /// Sort by keys from a multiply-xorshift bijection of each
+    /// index, worked out eight at a time so the compiler can
+    /// vectorize them. Keys never tie, so the sort needs no
+    /// tiebreak, and shuffling millions of items is bound by
+    /// the sort rather than by hashing.
+    BatchedHash,
+}
+
+/// How the backend is seeded from an origin
+///
+/// Backends with seeds wider than 64 bits have most of their
+/// seed filled in by their own `seed_from_u64`. The expanded
+/// seeding fills every byte of the seed from SplitMix64 outputs
+/// instead, so each seed byte depends on the whole origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Seeding {
+    /// The backend's own `seed_from_u64`
+    #[default]
+    FromU64,
+    /// Fill the whole seed from SplitMix64, started at the origin
+    SplitMix,
+}
+
+impl Seeding {
+    fn seed<T: SeedableRng>(self, origin: u64) -> T {
+        match self {
+            Seeding::FromU64 => T::seed_from_u64(origin),
+            Seeding::SplitMix => {
+                let mut seed = T::Seed::default();
+                SplitMix64::seed_from_u64(origin).fill_bytes(seed.as_mut());
+                T::from_seed(seed)
+            }
+        }
+    }
+}
+
+/// The full state of an rng, from `to_state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RngState {
+    /// The algorithm version in use
+    pub algorithm: Algorithm,
+    /// The output finalizer in use
+    pub finalizer: Finalizer,
+    /// The bits the bool pool draws from
+    pub bool_policy: BoolPolicy,
+    /// How the backend was seeded
+    pub seeding: Seeding,
+    /// Whether unit floats are mirrored, see `antithetic_pair`
+    pub mirrored: bool,
+    /// How lists are shuffled
+    pub shuffle: ShuffleAlgorithm,
+    /// The seed the rng was created from
+    pub origin: u64,
+    /// How many times the backend has been stepped
+    pub steps: u64,
+    /// The word the current bools are drawn from
+    pub bool_pool: u64,
+    /// How many bools of that word are used up
+    pub bools_used: u8,
+    /// How many words are prefetched at a time, or zero
+    pub prefetch: usize,
+}
+
+/// The reasons a checked raw state may not be loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateError {
+    /// The checksum did not match the rest of the state
+    BadChecksum,
+    /// More bools were used than any pool holds
+    BadBoolCount(u8),
+    /// A custom bool policy had no bits to draw from
+    EmptyBoolPolicy,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::BadChecksum => write!(f, "the state checksum did not match"),
+            StateError::BadBoolCount(n) => {
+                write!(f, "{} bools used is more than any pool holds", n)
+            }
+            StateError::EmptyBoolPolicy => write!(f, "the bool policy had no bits set"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// Separates raw state checksums from other uses of the mixer
+const RAW_STATE_SALT: u64 = 0x7261_775f_7374_6174;
+
+fn raw_checksum(raw: (u64, u64, u64, u8)) -> u64 {
+    let (origin, steps, inner, last) = raw;
+    [origin, steps, inner, last as u64]
+        .into_iter()
+        .fold(keyed::mix64(RAW_STATE_SALT), keyed::mix_word)
+}
+
+/// The state of an rng and all its registered children,
+/// from `to_tree_state`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TreeState {
+    /// The rng itself
+    pub state: RngState,
+    /// Each registered child, by name, see `child`
+    pub children: BTreeMap<String, TreeState>,
+}
+
 /// A splitting rng which provides
 /// several types of random value
 /// and also produces seeded child
@@ -21,36 +309,243 @@ pub struct SplittingRng<T: RngCore + SeedableRng> {
     origin: u64,
     steps: u64,
     prng: T,
-    bool_pool: BooleanList,
+    bool_pool: BitPool,
+    algorithm: Algorithm,
+    finalizer: Finalizer,
+    bool_policy: BoolPolicy,
+    seeding: Seeding,
+    mirrored: bool,
+    shuffle: ShuffleAlgorithm,
+    tracer: Option<Box<dyn trace::TraceSink + Send + Sync>>,
+    audit: trace::Audit,
+    limit: Option<budget::StepLimit>,
+    parent: Option<u64>,
+    depth: u32,
+    clone_watch: Option<(Arc<CloneWatch>, u64)>,
+    thread_watch: Option<ThreadWatch>,
+    children: BTreeMap<String, SplittingRng<T>>,
+    prefetch: Option<prefetch::Prefetch>,
+    history: Option<history::History<T>>,
 }
 
 impl<T: RngCore + SeedableRng> SplittingRng<T> {
     /// Create a new RNG using the origin RNG
+    /// Uses `Algorithm::V1`, for compatibility
     pub fn new(origin: u64) -> Self {
-        let mut root_rng: T = SeedableRng::seed_from_u64(origin);
-        let bool_p = BooleanList::new(root_rng.next_u64());
+        Self::with_algorithm(origin, Algorithm::V1)
+    }
+
+    /// Create a new RNG with an origin taken from the system clock,
+    /// along with that origin
+    ///
+    /// The nanoseconds since the Unix epoch are folded to 64 bits
+    /// and mixed, so runs started close together still differ.
+    /// For tools which want a fresh stream each run but can
+    /// still reproduce any one of them: report the origin with
+    /// the run, and repeat it with `new(origin)`.
+    /// Uses `Algorithm::V1`.
+    pub fn new_from_time() -> (Self, u64) {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let origin = keyed::mix64(nanos as u64 ^ (nanos >> 64) as u64);
+        (Self::new(origin), origin)
+    }
+
+    /// Create a new RNG using a specific algorithm version
+    /// Children made by `split` use the same version
+    pub fn with_algorithm(origin: u64, algorithm: Algorithm) -> Self {
+        Self::build(origin, algorithm, Seeding::FromU64)
+    }
+
+    fn build(origin: u64, algorithm: Algorithm, seeding: Seeding) -> Self {
+        let mut root_rng: T = seeding.seed(origin);
+        let bool_p = BitPool::for_rng(root_rng.next_u64(), algorithm, BoolPolicy::Standard);
         SplittingRng {
             origin,
             steps: 0,
             prng: root_rng,
             bool_pool: bool_p,
+            algorithm,
+            finalizer: Finalizer::None,
+            bool_policy: BoolPolicy::Standard,
+            seeding,
+            mirrored: false,
+            shuffle: ShuffleAlgorithm::HashSort,
+            tracer: None,
+            audit: trace::Audit::default(),
+            limit: None,
+            parent: None,
+            depth: 0,
+            clone_watch: None,
+            thread_watch: None,
+            children: BTreeMap::new(),
+            prefetch: None,
+            history: None,
+        }
+    }
+
+    /// The algorithm version in use
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Scramble every output from now on, including
+    /// any bools already pooled, and in all children
+    ///
+    /// Meant to be chained onto a constructor, as in
+    /// `SplittingRng::new(origin).finalize_with(Finalizer::Mix64)`
+    pub fn finalize_with(mut self, finalizer: Finalizer) -> Self {
+        if self.finalizer == Finalizer::None {
+            self.bool_pool.inner = finalizer.apply(self.bool_pool.inner);
+        }
+        self.finalizer = finalizer;
+        self
+    }
+
+    /// The output finalizer in use
+    pub fn finalizer(&self) -> Finalizer {
+        self.finalizer
+    }
+
+    /// Draw bools from other bits of each word, from now
+    /// on and in all children
+    ///
+    /// Panics if a custom mask has no bits set
+    pub fn with_bool_policy(mut self, policy: BoolPolicy) -> Self {
+        assert!(
+            policy.capacity() > 0,
+            "A bool policy needs at least one bit"
+        );
+        self.set_bool_policy(policy);
+        self
+    }
+
+    fn set_bool_policy(&mut self, policy: BoolPolicy) {
+        self.bool_policy = policy;
+        self.bool_pool.policy = policy;
+    }
+
+    /// The bits the bool pool draws from
+    pub fn bool_policy(&self) -> BoolPolicy {
+        self.bool_policy
+    }
+
+    /// Shuffle with another algorithm, from now on and in all children
+    pub fn with_shuffle_algorithm(mut self, shuffle: ShuffleAlgorithm) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// How lists are shuffled
+    pub fn shuffle_algorithm(&self) -> ShuffleAlgorithm {
+        self.shuffle
+    }
+
+    /// Reseed the backend from the origin another way, here
+    /// and in all children
+    ///
+    /// Meant to be chained onto a constructor, as it starts the
+    /// stream over from the origin with a fresh bool pool.
+    pub fn seeded_with(mut self, seeding: Seeding) -> Self {
+        self.seeding = seeding;
+        self.prng = seeding.seed(self.origin);
+        self.steps = 0;
+        if let Some(prefetch) = self.prefetch.as_mut() {
+            prefetch.clear();
         }
+        let word = self.finalizer.apply(self.prng.next_u64());
+        self.bool_pool = BitPool::for_rng(word, self.algorithm, self.bool_policy);
+        self
+    }
+
+    /// How the backend is seeded from the origin
+    pub fn seeding(&self) -> Seeding {
+        self.seeding
+    }
+
+    /// The seed this rng was created from
+    pub fn origin(&self) -> u64 {
+        self.origin
+    }
+
+    /// The origin of the rng this was split from, if known
+    ///
+    /// Loaded states forget their parent
+    pub fn parent_origin(&self) -> Option<u64> {
+        self.parent
+    }
+
+    /// How many splits separate this rng from its root,
+    /// counting from a loaded state as a root
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// How many times the backend has been stepped,
+    /// which is the position in the stream
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Move to an absolute position in the stream
+    ///
+    /// Seeking forward steps over the values in between, and
+    /// seeking backward replays from the origin, so both take
+    /// time in proportion to the distance, as `RngCore` offers
+    /// no jump to an arbitrary position. The words passed over
+    /// are not draws, so they aren't charged to the step limit,
+    /// audited, traced or seen by the watches. Any pooled bools
+    /// are dropped, so every rng with the same origin seeked to
+    /// the same position continues identically.
+    pub fn seek(&mut self, target_steps: u64) {
+        if target_steps < self.steps {
+            let mut prng: T = self.seeding.seed(self.origin);
+            // The first output seeds the initial bool pool
+            prng.next_u64();
+            self.prng = prng;
+            self.steps = 0;
+            if let Some(prefetch) = self.prefetch.as_mut() {
+                prefetch.clear();
+            }
+        }
+        let count = target_steps.saturating_sub(self.steps);
+        match self.prefetch.as_mut() {
+            Some(prefetch) => prefetch.skip(&mut self.prng, count),
+            None => {
+                for _ in 0..count {
+                    self.prng.next_u64();
+                }
+            }
+        }
+        self.steps += count;
+        self.bool_pool.last = BoolPolicy::All64.capacity();
     }
 
     /// Catch up this rng to a certain number of steps in the future
     /// Possibly slow, as the underlying implementation is not able to jump ahead
     /// Prefer to same the interior state
-    fn fast_forward_from_origin(origin: u64, steps: u64, bools: (u64, u8)) -> Self {
-        let mut result = Self::new(origin);
+    fn fast_forward_from_origin(
+        origin: u64,
+        steps: u64,
+        bools: (u64, u8),
+        algorithm: Algorithm,
+        seeding: Seeding,
+    ) -> Self {
+        let mut result = Self::build(origin, algorithm, seeding);
         for _ in 0..steps {
             result.step();
         }
-        result.bool_pool = BooleanList::new(bools.0);
+        result.bool_pool.inner = bools.0;
         result.bool_pool.last = bools.1;
         result
     }
 
     /// Dump this rng and its current state to numbers
+    ///
+    /// This format predates algorithm versions and always
+    /// loads as `Algorithm::V1`. Prefer `to_state`
     pub fn to_raw(&self) -> (u64, u64, u64, u8) {
         (
             self.origin,
@@ -65,7 +560,121 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
     /// Gets slower the more the generator was used
     pub fn from_raw(raw: (u64, u64, u64, u8)) -> Self {
         let (origin, steps, inner, last) = raw;
-        Self::fast_forward_from_origin(origin, steps, (inner, last))
+        Self::fast_forward_from_origin(
+            origin,
+            steps,
+            (inner, last),
+            Algorithm::V1,
+            Seeding::FromU64,
+        )
+    }
+
+    /// Dump this rng and its current state, including
+    /// the algorithm version
+    pub fn to_state(&self) -> RngState {
+        RngState {
+            algorithm: self.algorithm,
+            finalizer: self.finalizer,
+            bool_policy: self.bool_policy,
+            seeding: self.seeding,
+            mirrored: self.mirrored,
+            shuffle: self.shuffle,
+            origin: self.origin,
+            steps: self.steps,
+            bool_pool: self.bool_pool.inner,
+            bools_used: self.bool_pool.last,
+            prefetch: self.prefetch_size(),
+        }
+    }
+
+    /// Load an rng from a dumped state
+    /// Note that the same T type must be used
+    /// Gets slower the more the generator was used
+    ///
+    /// Panics if no rng could have dumped the state,
+    /// as `try_from_state` reports
+    pub fn from_state(state: RngState) -> Self {
+        match Self::try_from_state(state) {
+            Ok(rng) => rng,
+            Err(e) => panic!("Can't load the state: {}", e),
+        }
+    }
+
+    /// Load an rng from a dumped state, or an error if no rng
+    /// could have dumped it, such as one whose custom bool
+    /// policy has no bits, which would never fill its pool
+    pub fn try_from_state(state: RngState) -> Result<Self, StateError> {
+        if state.bool_policy.capacity() == 0 {
+            return Err(StateError::EmptyBoolPolicy);
+        }
+        let mut result = Self::fast_forward_from_origin(
+            state.origin,
+            state.steps,
+            (state.bool_pool, state.bools_used),
+            state.algorithm,
+            state.seeding,
+        );
+        result.finalizer = state.finalizer;
+        result.set_bool_policy(state.bool_policy);
+        result.mirrored = state.mirrored;
+        result.shuffle = state.shuffle;
+        result.prefetch(state.prefetch);
+        Ok(result)
+    }
+
+    /// Dump this rng as `to_raw` does, with a checksum
+    /// of the other numbers last
+    pub fn to_raw_checked(&self) -> (u64, u64, u64, u8, u64) {
+        let raw = self.to_raw();
+        (raw.0, raw.1, raw.2, raw.3, raw_checksum(raw))
+    }
+
+    /// Load an rng from `to_raw_checked`, or an error if the numbers
+    /// were corrupted or edited, rather than a different stream
+    /// Note that the same T type must be used
+    /// Gets slower the more the generator was used
+    pub fn try_from_raw(raw: (u64, u64, u64, u8, u64)) -> Result<Self, StateError> {
+        let (origin, steps, inner, last, checksum) = raw;
+        let raw = (origin, steps, inner, last);
+        if raw_checksum(raw) != checksum {
+            return Err(StateError::BadChecksum);
+        }
+        if last > 64 {
+            return Err(StateError::BadBoolCount(last));
+        }
+        Ok(Self::from_raw(raw))
+    }
+
+    /// Dump this rng along with every registered child,
+    /// and theirs in turn, for a save which restores every
+    /// subsystem at once
+    pub fn to_tree_state(&self) -> TreeState {
+        TreeState {
+            state: self.to_state(),
+            children: self
+                .children
+                .iter()
+                .map(|(name, child)| (name.clone(), child.to_tree_state()))
+                .collect(),
+        }
+    }
+
+    /// Load an rng and its registered children from a dumped tree
+    /// Note that the same T type must be used
+    pub fn from_tree_state(tree: TreeState) -> Self {
+        let mut result = Self::from_state(tree.state);
+        result.load_children(tree.children);
+        result
+    }
+
+    fn load_children(&mut self, children: BTreeMap<String, TreeState>) {
+        for (name, tree) in children {
+            let mut child = Self::from_state(tree.state);
+            child.parent = Some(self.origin);
+            child.depth = self.depth + 1;
+            child.load_children(tree.children);
+            self.children.insert(name, child);
+        }
     }
 
     /// Split this rng into itself and a child
@@ -74,25 +683,116 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
     /// so multiple sequential calls to `child`
     /// will produce distinct RNGs
     pub fn split(&mut self) -> SplittingRng<T> {
-        SplittingRng::new(self.step())
+        let origin = self.step();
+        self.record(trace::Method::Split, origin);
+        self.child_at(origin)
+    }
+
+    /// A child with the given origin, inheriting the settings of this rng
+    pub(crate) fn child_at(&self, origin: u64) -> SplittingRng<T> {
+        self.child_settings().child(origin)
+    }
+
+    /// The settings children split from here inherit
+    pub(crate) fn child_settings(&self) -> ChildSettings {
+        ChildSettings {
+            algorithm: self.algorithm,
+            finalizer: self.finalizer,
+            bool_policy: self.bool_policy,
+            seeding: self.seeding,
+            mirrored: self.mirrored,
+            shuffle: self.shuffle,
+            parent: self.origin,
+            depth: self.depth + 1,
+            watch_split_at: self.thread_watch.as_ref().map(|_| self.steps),
+        }
+    }
+
+    /// Make an independent child, the same as `split`
+    ///
+    /// Prefer this to `clone` unless an exact duplicate of
+    /// the stream is wanted, as a clone repeats every value.
+    pub fn fork(&mut self) -> SplittingRng<T> {
+        self.split()
+    }
+
+    /// In debug builds, warn on stderr when this rng and a
+    /// clone of it are both advanced
+    ///
+    /// Two clones drawing from the same position give the same
+    /// values, which is rarely intended. After each `clone`, the
+    /// first of the rngs sharing the watch to step is allowed,
+    /// and any other one warns once. Does nothing
+    /// in release builds.
+    pub fn watch_clones(&mut self) {
+        if cfg!(debug_assertions) {
+            self.clone_watch = Some((Arc::new(CloneWatch::default()), 0));
+        }
+    }
+
+    /// In debug builds, warn on stderr when this rng is
+    /// advanced from more than one thread at once
+    ///
+    /// An rng shared behind a lock gives each thread whatever
+    /// values are next when it gets the lock, so the results
+    /// depend on scheduling even though there is no data race.
+    /// Split a child for each thread instead. Moving the rng to
+    /// another thread is fine, as is handing it on from there,
+    /// but a step from a thread which already handed it on warns
+    /// once, as threads sharing it take turns. Children split
+    /// from now on are watched as well, each from its own first
+    /// step, and their warnings name the step of the parent they
+    /// were split at. Does nothing in release builds.
+    pub fn watch_threads(&mut self) {
+        if cfg!(debug_assertions) {
+            self.thread_watch = Some(ThreadWatch {
+                thread: None,
+                earlier: Vec::new(),
+                split_at: None,
+            });
+        }
     }
 
     /// Provide a random boolean
     pub fn get_bool(&mut self) -> bool {
-        if let Some(r) = self.bool_pool.next() {
-            return r;
+        let result = match self.bool_pool.next() {
+            Some(r) => r,
+            None => {
+                self.bool_pool = self.bit_pool();
+                self.bool_pool
+                    .next()
+                    .expect("Failed to use new boolean pool")
+            }
+        };
+        self.record(trace::Method::GetBool, result as u64);
+        result
+    }
+
+    /// Provide +1 or -1, from the bool pool
+    pub fn get_sign(&mut self) -> i8 {
+        if self.get_bool() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Negate a value half of the time, from the bool pool
+    pub fn flip<N: std::ops::Neg<Output = N>>(&mut self, value: N) -> N {
+        if self.get_bool() {
+            value
+        } else {
+            -value
         }
-        self.bool_pool = BooleanList::new(self.step());
-        self.bool_pool
-            .next()
-            .expect("Failed to use new boolean pool")
     }
 
     /// Provide an unsigned 32-bit integer
     pub fn get_u32(&mut self) -> u32 {
         // Shift away the lowest bits,
         // which are not usable
-        (self.step() >> 32) as u32
+        let result = (self.step() >> 32) as u32;
+        self.record(trace::Method::GetU32, result as u64);
+        result
     }
     /// Provide an unsigned 64-bit integer
     ///
@@ -100,20 +800,55 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
     /// are not as entropic as expected due to the
     /// underlying implementation
     pub fn get_u64(&mut self) -> u64 {
-        self.step()
+        let result = self.step();
+        self.record(trace::Method::GetU64, result);
+        result
     }
 
-    /// Roll a die with up to 2^32 sides
+    /// Provide a float in the range [0, 1)
     ///
-    /// Note that the  distribution is not even, because the possible values are probably
-    /// not perfectly divisible by the number of sides. This inaccuracy grows with the
-    /// number of sides.
+    /// Built from the 53 highest bits of a step,
+    /// so every representable value is evenly spaced
+    pub fn get_f64(&mut self) -> f64 {
+        let result = unit_f64(self.step(), self.mirrored);
+        self.record(trace::Method::GetF64, result.to_bits());
+        result
+    }
+
+    /// Draw a value from a distribution
+    /// See the `distributions` module
+    pub fn sample<V, D: Distribution<V>>(&mut self, dist: &D) -> V {
+        dist.sample(self)
+    }
+
+    /// Roll a die with up to 2^32 sides, slightly biased
+    ///
+    /// The same as `compat::BiasedRoll::biased_roll`, which gives
+    /// the same values as it always has for each algorithm version.
+    #[deprecated(
+        note = "use `roll` for an unbiased roll, or call `compat::BiasedRoll::biased_roll` to keep the old values"
+    )]
     pub fn biased_roll(&mut self, sides: u32) -> u32 {
-        if sides == 0 {
-            return 0;
-        }
-        // lowest 3 bits are low entropy, shift away
-        ((self.step() >> 3) % (sides as u64)) as u32
+        compat::BiasedRoll::biased_roll(self, sides)
+    }
+
+    /// Roll a fair die with up to 2^32 sides, from 0 to `sides - 1`
+    ///
+    /// The recommended roll. Uses Lemire's nearly divisionless
+    /// method under every algorithm version, so it is as fast as
+    /// the old `compat::BiasedRoll` with none of its bias, and
+    /// gives the same values whichever version is in use, which
+    /// are those of `fair_roll` under `Algorithm::V3`.
+    /// Zero sides gives zero.
+    pub fn roll(&mut self, sides: u32) -> u32 {
+        let result = match sides {
+            0 => 0,
+            _ => match self.lemire_roll(sides as u64, u64::MAX) {
+                Ok(value) | Err(value) => value as u32,
+            },
+        };
+        self.record(trace::Method::Roll, result as u64);
+        result
     }
 
     /// Roll a die with up to 2^32 sides
@@ -123,23 +858,98 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
     /// time.
     ///
     /// Note that this slows down more when the number of sides
-    /// is very large.
+    /// is very large. A die with no sides gives zero without
+    /// taking a step; `try_fair_roll` makes it an error.
     pub fn fair_roll(&mut self, sides: u32) -> u32 {
+        let result = self.fair_roll_inner(sides);
+        self.record(trace::Method::FairRoll, result as u64);
+        result
+    }
+
+    /// Roll a fair die with up to 2^64 sides, from 0 to `sides - 1`
+    ///
+    /// Rejects from the smallest power of two covering the sides,
+    /// using the highest bits. Zero sides gives zero.
+    /// Traced and audited as a fair roll.
+    pub fn fair_roll_u64(&mut self, sides: u64) -> u64 {
+        self.fair_roll_u128(sides as u128) as u64
+    }
+
+    /// Roll a fair die with up to 2^128 sides, from 0 to `sides - 1`
+    ///
+    /// Dice of more than 2^64 sides take two steps per try, with
+    /// the first step as the high half. A die with no sides gives
+    /// zero without taking a step, as for `fair_roll`; use
+    /// `roll_range_u128` to have an empty range give `None`.
+    /// Traced and audited as a fair roll, with the low 64 bits
+    /// of the result as the traced value.
+    pub fn fair_roll_u128(&mut self, sides: u128) -> u128 {
+        let result = self.below_u128(sides);
+        self.record(trace::Method::FairRoll, result as u64);
+        result
+    }
+
+    /// Roll a fair die with any `usize` number of sides,
+    /// as for indexing
+    ///
+    /// Traced and audited as a fair roll.
+    pub fn fair_roll_usize(&mut self, sides: usize) -> usize {
+        self.fair_roll_u128(sides as u128) as usize
+    }
+
+    fn fair_roll_inner(&mut self, sides: u32) -> u32 {
+        match self.fair_roll_within(sides, u64::MAX) {
+            Ok(value) | Err(value) => value,
+        }
+    }
+
+    /// A fair roll from at most `max_draws` draws, or if every
+    /// draw was rejected, the biased value of the last one
+    /// `max_draws` of zero is taken as one
+    pub(crate) fn fair_roll_within(&mut self, sides: u32, max_draws: u64) -> Result<u32, u32> {
         if sides == 0 {
-            return 0;
+            return Ok(0);
+        }
+        if self.algorithm == Algorithm::V3 {
+            return match self.lemire_roll(sides as u64, max_draws) {
+                Ok(value) => Ok(value as u32),
+                Err(value) => Err(value as u32),
+            };
+        }
+        let mut draws = 1;
+        if self.algorithm != Algorithm::V1 {
+            // Only rolls below the largest multiple of
+            // `sides` within 61 bits are fair
+            let range = 1u64 << 61;
+            let zone = range - range % sides as u64;
+            loop {
+                let step = self.step() >> 3;
+                if step < zone {
+                    return Ok((step % sides as u64) as u32);
+                }
+                if draws >= max_draws {
+                    return Err((step % sides as u64) as u32);
+                }
+                draws += 1;
+            }
         }
+        // V1 compares a 61 bit roll against a 64 bit limit,
+        // so in practice this never rerolls
         // Roll first
         let mut step = self.step() >> 3;
         loop {
             // Find the largest number under which our roll will be fair
             let biggest = (sides as u64) * (u64::MAX / (sides as u64));
-            if step > biggest {
-                // the roll would not be fair
-                // roll again
-                step = self.step() >> 3;
-            } else {
-                return (step % (sides as u64)) as u32;
+            if step <= biggest {
+                return Ok((step % (sides as u64)) as u32);
+            }
+            if draws >= max_draws {
+                return Err((step % (sides as u64)) as u32);
             }
+            // the roll would not be fair
+            // roll again
+            step = self.step() >> 3;
+            draws += 1;
         }
     }
 
@@ -152,10 +962,67 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
     ///
     /// When rolling on a list with replacement, it is suggested
     /// to shuffle that list at intervals if using `biased_roll`.
+    ///
+    /// The order is the same on every platform, for every
+    /// version. Indices are widened to `u64` before any arithmetic,
+    /// and hashed as little-endian bytes, so 32-bit, 64-bit and
+    /// wasm targets all agree.
     pub fn shuffle<L>(&mut self, list: &[L]) -> Vec<L>
     where
         L: Copy,
     {
+        self.shuffle_with(list, self.shuffle)
+    }
+
+    /// Shuffle a list with a given algorithm, whichever
+    /// this rng uses otherwise
+    pub fn shuffle_with<L: Copy>(&mut self, list: &[L], algorithm: ShuffleAlgorithm) -> Vec<L> {
+        let result = match algorithm {
+            ShuffleAlgorithm::HashSort => self.shuffle_inner(list),
+            ShuffleAlgorithm::FisherYates => self.fisher_yates(list),
+            ShuffleAlgorithm::BatchedHash => {
+                let key = self.step();
+                batched_order(list, key, self.step())
+            }
+        };
+        let value = trace::shuffle_value(list.len(), algorithm);
+        self.record(trace::Method::Shuffle, value);
+        result
+    }
+
+    fn fisher_yates<L: Copy>(&mut self, list: &[L]) -> Vec<L> {
+        let mut result = list.to_vec();
+        self.fisher_yates_in_place(&mut result);
+        result
+    }
+
+    fn fisher_yates_in_place<L>(&mut self, list: &mut [L]) {
+        let seed = self.step() ^ self.step().rotate_left(32);
+        let mut swaps = SplitMix64::seed_from_u64(seed);
+        for i in (1..list.len()).rev() {
+            let j = splitmix::below(&mut swaps, i as u64 + 1);
+            list.swap(i, j as usize);
+        }
+    }
+
+    /// Shuffle a slice in place, without allocating
+    ///
+    /// Always uses `ShuffleAlgorithm::FisherYates`, whichever this
+    /// rng uses otherwise, as it is the one which needs no space,
+    /// and gives the same order as it would for `shuffle`.
+    pub fn shuffle_slice<L>(&mut self, list: &mut [L]) {
+        self.fisher_yates_in_place(list);
+        let value = trace::shuffle_value(list.len(), ShuffleAlgorithm::FisherYates);
+        self.record(trace::Method::Shuffle, value);
+    }
+
+    /// Shuffle a small array in place, as `shuffle_slice`,
+    /// for hot loops and targets without a heap
+    pub fn shuffle_array<L, const N: usize>(&mut self, array: &mut [L; N]) {
+        self.shuffle_slice(array);
+    }
+
+    fn shuffle_inner<L: Copy>(&mut self, list: &[L]) -> Vec<L> {
         let item_ct = list.len();
         let mut intermediate = Vec::with_capacity(item_ct);
         let item_ct = item_ct as u64;
@@ -165,57 +1032,717 @@ impl<T: RngCore + SeedableRng> SplittingRng<T> {
         // TODO: Add prop tests to ensure there's no change
         let salt = self.step();
         let mut hasher = FnvHasher::with_key(self.step());
+        if self.algorithm != Algorithm::V1 {
+            return hashed_order(list, salt, hasher);
+        }
         for (idx, item) in list.iter().enumerate() {
-            let salted = idx as u64 + salt;
-            hasher.write_u64(salted);
+            write_index(&mut hasher, idx, salt);
             //lowest bits are low entropy
             //Reduce width to 32 bits with XOR to improve behavior
             let unsmushed = hasher.finish();
-            let naive_dest =
-                ((unsmushed & (u32::max_value() as u64)) | (unsmushed >> 32)) % item_ct;
+            let naive_dest = ((unsmushed & (u32::MAX as u64)) | (unsmushed >> 32)) % item_ct;
             intermediate.push((naive_dest, *item));
-            intermediate.sort_unstable_by(|(lhash, _), (rhash, _)| lhash.cmp(rhash));
+            intermediate.sort_unstable_by_key(|(lhash, _)| *lhash);
         }
         intermediate.iter().map(|(_, item)| *item).collect()
     }
 
+    /// Shuffle the items of a vector, which need not be `Copy`
+    ///
+    /// Gives the same order as `shuffle` would for a slice of the
+    /// same length, moving each item rather than copying it.
+    pub fn shuffle_vec<L>(&mut self, list: Vec<L>) -> Vec<L> {
+        let indices: Vec<usize> = (0..list.len()).collect();
+        let order = self.shuffle(&indices);
+        let mut slots: Vec<Option<L>> = list.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .map(|idx| slots[idx].take().expect("Shuffled an index twice"))
+            .collect()
+    }
+
+    /// Collect any iterator and shuffle its items, as `shuffle_vec`
+    pub fn shuffle_iter<I: IntoIterator>(&mut self, items: I) -> Vec<I::Item> {
+        self.shuffle_vec(items.into_iter().collect())
+    }
+
+    /// Shuffle a list, hashing with any hasher instead of FNV
+    ///
+    /// Always uses the full width keys of `Algorithm::V2`. Each
+    /// hasher is keyed by writing a step into it first, so the
+    /// order is only as reproducible as the hasher: `fnv` and
+    /// other fixed algorithms are stable, while the standard
+    /// library's default hasher may change between releases.
+    /// Indices are written as little-endian bytes, as for `shuffle`.
+    pub fn shuffle_with_hasher<L: Copy, B: BuildHasher>(
+        &mut self,
+        list: &[L],
+        build: &B,
+    ) -> Vec<L> {
+        let salt = self.step();
+        let mut hasher = build.build_hasher();
+        hasher.write_u64(self.step());
+        let result = hashed_order(list, salt, hasher);
+        let value = trace::hasher_shuffle_value(list.len());
+        self.record(trace::Method::Shuffle, value);
+        result
+    }
+
+    /// Lemire's nearly divisionless fair roll, for nonzero `sides`
+    ///
+    /// Each product of a step and `sides` has a high half in
+    /// `0..sides`. The low half only falls below `2^64 % sides`
+    /// for the few steps which would bias it, so the remainder
+    /// is only worked out when the low half is that small.
+    ///
+    /// Gives up after `max_draws`, as `fair_roll_within` does
+    fn lemire_roll(&mut self, sides: u64, max_draws: u64) -> Result<u64, u64> {
+        let mut product = self.step() as u128 * sides as u128;
+        if (product as u64) < sides {
+            let threshold = sides.wrapping_neg() % sides;
+            let mut draws = 1;
+            while (product as u64) < threshold {
+                if draws >= max_draws {
+                    return Err((product >> 64) as u64);
+                }
+                product = self.step() as u128 * sides as u128;
+                draws += 1;
+            }
+        }
+        Ok((product >> 64) as u64)
+    }
+
+    /// A fair value below `bound`, of up to 128 bits
+    /// A bound of zero gives zero
+    pub(crate) fn below_u128(&mut self, bound: u128) -> u128 {
+        if bound <= 1 {
+            return 0;
+        }
+        // Reject from the smallest covering power of two,
+        // so at worst half of the draws are wasted.
+        // Take the high bits, as the low bits are low entropy
+        let bits = 128 - (bound - 1).leading_zeros();
+        loop {
+            let value = if bits <= 64 {
+                (self.step() >> (64 - bits)) as u128
+            } else {
+                let wide = ((self.step() as u128) << 64) | self.step() as u128;
+                wide >> (128 - bits)
+            };
+            if value < bound {
+                return value;
+            }
+        }
+    }
+
     fn step(&mut self) -> u64 {
+        if let Some((watch, id)) = self.clone_watch.as_ref() {
+            if watch.check(*id) {
+                eprintln!(
+                    "warning: two clones of {} were both advanced, \
+                     so they will repeat the same values",
+                    self
+                );
+                self.clone_watch = None;
+            }
+        }
+        if let Some(watch) = self.thread_watch.as_mut() {
+            let current = thread::current().id();
+            match watch.thread {
+                None => watch.thread = Some(current),
+                Some(owner) if owner == current => {}
+                // A change of owner, rather than a return to an earlier one
+                Some(owner) if !watch.earlier.contains(&current) => {
+                    watch.earlier.push(owner);
+                    watch.thread = Some(current);
+                }
+                Some(_) => {
+                    let split_at = match (self.parent, watch.split_at) {
+                        (Some(parent), Some(step)) => {
+                            format!(", split from {:016x} at step {}", parent, step)
+                        }
+                        _ => String::new(),
+                    };
+                    eprintln!(
+                        "warning: {}{} was advanced from a second thread, \
+                         so its values depend on scheduling",
+                        self, split_at
+                    );
+                    self.thread_watch = None;
+                }
+            }
+        }
+        if let Some(limit) = self.limit.as_mut() {
+            limit.charge();
+        }
         self.steps += 1;
-        self.prng.next_u64()
+        match self.prefetch.as_mut() {
+            Some(prefetch) => prefetch.next(&mut self.prng, self.finalizer),
+            None => self.finalizer.apply(self.prng.next_u64()),
+        }
     }
 }
 
-#[doc(hidden)]
-/// A helper structure to generate 61 random bools
-/// from each 64-bit output of an RngCore
-struct BooleanList {
-    inner: u64,
-    last: u8,
+/// Clones continue from exactly the same position, so they
+/// produce the same values as the original. Use `fork` for an
+/// independent child.
+///
+/// A clone is not traced and keeps the step limit without its
+/// callback, as neither can be duplicated.
+impl<T: RngCore + SeedableRng + Clone> Clone for SplittingRng<T> {
+    fn clone(&self) -> Self {
+        let clone_watch = self.clone_watch.as_ref().map(|(watch, _)| {
+            // Watching restarts from the newest clone
+            watch.first.store(0, Ordering::Relaxed);
+            (
+                watch.clone(),
+                watch.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            )
+        });
+        SplittingRng {
+            origin: self.origin,
+            steps: self.steps,
+            prng: self.prng.clone(),
+            bool_pool: self.bool_pool.clone(),
+            algorithm: self.algorithm,
+            finalizer: self.finalizer,
+            bool_policy: self.bool_policy,
+            seeding: self.seeding,
+            mirrored: self.mirrored,
+            shuffle: self.shuffle,
+            tracer: None,
+            audit: self.audit,
+            limit: self.limit.as_ref().map(|l| l.without_callback()),
+            parent: self.parent,
+            depth: self.depth,
+            clone_watch,
+            // A clone is a separate rng, which may go to another thread
+            thread_watch: self.thread_watch.as_ref().map(|watch| ThreadWatch {
+                thread: None,
+                earlier: Vec::new(),
+                split_at: watch.split_at,
+            }),
+            children: self.children.clone(),
+            prefetch: self.prefetch.clone(),
+            history: self.history.clone(),
+        }
+    }
+}
+
+/// What a child inherits from its parent, kept apart so
+/// children can be made without borrowing the parent
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChildSettings {
+    algorithm: Algorithm,
+    finalizer: Finalizer,
+    bool_policy: BoolPolicy,
+    seeding: Seeding,
+    mirrored: bool,
+    shuffle: ShuffleAlgorithm,
+    parent: u64,
+    depth: u32,
+    // The parent's step, if it was watching threads
+    watch_split_at: Option<u64>,
+}
+
+impl ChildSettings {
+    pub(crate) fn child<T: RngCore + SeedableRng>(&self, origin: u64) -> SplittingRng<T> {
+        let mut child =
+            SplittingRng::build(origin, self.algorithm, self.seeding).finalize_with(self.finalizer);
+        child.set_bool_policy(self.bool_policy);
+        child.mirrored = self.mirrored;
+        child.shuffle = self.shuffle;
+        child.parent = Some(self.parent);
+        child.depth = self.depth;
+        child.thread_watch = self.watch_split_at.map(|step| ThreadWatch {
+            thread: None,
+            earlier: Vec::new(),
+            split_at: Some(step),
+        });
+        child
+    }
+}
+
+/// Shared between an rng and its clone, to notice both advancing
+#[derive(Default)]
+struct CloneWatch {
+    // One more than the id of the first to step, or zero
+    first: AtomicU64,
+    // The last id handed out, where the original is zero
+    next_id: AtomicU64,
 }
 
-impl BooleanList {
-    fn new(base: u64) -> BooleanList {
-        BooleanList {
-            inner: base,
-            last: 0,
+impl CloneWatch {
+    // Whether a different member already stepped
+    fn check(&self, id: u64) -> bool {
+        match self
+            .first
+            .compare_exchange(0, id + 1, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => false,
+            Err(first) => first != id + 1,
         }
     }
+}
+
+/// The thread an rng last stepped on and the ones it was
+/// handed on from, to notice a thread taking it back
+#[derive(Debug, Clone)]
+struct ThreadWatch {
+    thread: Option<ThreadId>,
+    earlier: Vec<ThreadId>,
+    // The step of the parent this was split at, if a child
+    split_at: Option<u64>,
+}
+
+impl<T: RngCore + SeedableRng> fmt::Debug for SplittingRng<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplittingRng")
+            .field("algorithm", &self.algorithm)
+            .field("finalizer", &self.finalizer)
+            .field("bool_policy", &self.bool_policy)
+            .field("seeding", &self.seeding)
+            .field("mirrored", &self.mirrored)
+            .field("shuffle", &self.shuffle)
+            .field("origin", &format_args!("{:#018x}", self.origin))
+            .field("steps", &self.steps)
+            .field("bools_left", &self.bool_pool.remaining())
+            .field("parent", &self.parent.map(|p| format!("{:#018x}", p)))
+            .field("depth", &self.depth)
+            .field("tracing", &self.tracer.is_some())
+            .field("steps_remaining", &self.steps_remaining())
+            .field("children", &self.children.keys().collect::<Vec<_>>())
+            .field("prefetched", &self.prefetch.as_ref().map(|p| p.buffered()))
+            .field("frames_kept", &self.history.as_ref().map_or(0, |h| h.len()))
+            .finish()
+    }
+}
 
-    fn next(&mut self) -> Option<bool> {
-        if self.last < LARGEST_SAFE_INDEX {
-            //We should avoid the last 3 bits because they aren't really random
-            let result = ((0x1000 << self.last) & self.inner) == 0;
-            self.last += 1;
-            return Some(result);
+impl<T: RngCore + SeedableRng> fmt::Display for SplittingRng<T> {
+    /// A compact summary such as `v1:0000000000003039@10`,
+    /// the version, origin in hex and steps taken
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self.algorithm {
+            Algorithm::V1 => 1,
+            Algorithm::V2 => 2,
+            Algorithm::V3 => 3,
+        };
+        write!(f, "v{}:{:016x}@{}", version, self.origin, self.steps)
+    }
+}
+
+/// Order items by the running hash of their salted indices
+fn hashed_order<L: Copy, H: Hasher>(list: &[L], salt: u64, mut hasher: H) -> Vec<L> {
+    // Full width keys rarely tie, and a stable sort
+    // settles any ties by the original order
+    let mut keyed: Vec<(u64, L)> = list
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            write_index(&mut hasher, idx, salt);
+            (hasher.finish(), *item)
+        })
+        .collect();
+    keyed.sort_by_key(|(hash, _)| *hash);
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+/// The sort key of an index for `ShuffleAlgorithm::BatchedHash`,
+/// a bijection of the index, so no two keys are equal
+pub(crate) fn batched_key(idx: u64, key: u64, salt: u64) -> u64 {
+    keyed::mix64(idx.wrapping_add(salt) ^ key)
+}
+
+// How many keys are worked out together
+const KEY_BATCH: usize = 8;
+
+fn batched_order<L: Copy>(list: &[L], key: u64, salt: u64) -> Vec<L> {
+    let mut keyed: Vec<(u64, L)> = Vec::with_capacity(list.len());
+    for (batch, items) in list.chunks(KEY_BATCH).enumerate() {
+        let base = (batch * KEY_BATCH) as u64;
+        let mut keys = [0u64; KEY_BATCH];
+        for (i, k) in keys.iter_mut().enumerate() {
+            *k = batched_key(base + i as u64, key, salt);
         }
-        None
+        keyed.extend(keys.iter().zip(items).map(|(k, item)| (*k, *item)));
     }
+    keyed.sort_unstable_by_key(|(k, _)| *k);
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Hash a salted index the same way on every platform
+///
+/// `write_u64` would use the native byte order, and a plain
+/// add of the salt could overflow and panic in debug builds
+fn write_index<H: Hasher>(hasher: &mut H, idx: usize, salt: u64) {
+    hasher.write(&(idx as u64).wrapping_add(salt).to_le_bytes());
+}
+
+/// A float in [0, 1) from the 53 highest bits of a step,
+/// or its mirror image about the middle of the range
+pub(crate) fn unit_f64(step: u64, mirrored: bool) -> f64 {
+    let bits = step >> 11;
+    let bits = if mirrored { (1 << 53) - 1 - bits } else { bits };
+    bits as f64 * (1.0 / (1u64 << 53) as f64)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use rand_xoshiro::Xoshiro256StarStar;
+    #[test]
+    fn test_debug_and_display() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.get_u64();
+        assert_eq!(rng.to_string(), "v1:0000000000003039@1");
+        let child = rng.split();
+        assert_eq!(child.parent_origin(), Some(12345));
+        assert_eq!(child.depth(), 1);
+        let debug = format!("{:?}", child);
+        assert!(
+            debug.contains("parent: Some(\"0x0000000000003039\")"),
+            "{}",
+            debug
+        );
+        assert!(debug.contains("bools_left: 61"), "{}", debug);
+    }
+
+    #[test]
+    fn test_clone_and_fork() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.get_bool();
+        let mut copy = rng.clone();
+        assert_eq!(copy.get_u64(), rng.get_u64());
+        assert_eq!(copy.get_bool(), rng.get_bool());
+        let mut fork = rng.fork();
+        assert_ne!(fork.get_u64(), rng.get_u64());
+        // Watching clones does nothing in release builds
+        #[cfg(debug_assertions)]
+        {
+            rng.watch_clones();
+            let mut copy = rng.clone();
+            rng.get_u64();
+            assert!(rng.clone_watch.is_some());
+            // The second to step warns once, then stops watching
+            copy.get_u64();
+            assert!(copy.clone_watch.is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_watch_threads() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.watch_threads();
+        rng.get_u64();
+        let mut child = rng.split();
+        assert_eq!(
+            child.thread_watch.as_ref().and_then(|w| w.split_at),
+            Some(2)
+        );
+        // A new child may move to its own thread
+        let child = thread::spawn(move || {
+            child.get_u64();
+            child
+        })
+        .join()
+        .unwrap();
+        assert!(child.thread_watch.is_some());
+        // So may the parent after it stepped, and on from there
+        let rng = thread::spawn(move || {
+            rng.get_u64();
+            thread::spawn(move || {
+                rng.get_u64();
+                rng
+            })
+            .join()
+            .unwrap()
+        })
+        .join()
+        .unwrap();
+        assert!(rng.thread_watch.is_some());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_watch_threads_sharing() {
+        let rng = Arc::new(std::sync::Mutex::new(
+            SplittingRng::<Xoshiro256StarStar>::new(12345),
+        ));
+        rng.lock().unwrap().watch_threads();
+        rng.lock().unwrap().get_u64();
+        let shared = rng.clone();
+        thread::spawn(move || shared.lock().unwrap().get_u64())
+            .join()
+            .unwrap();
+        // Taking a turn after another thread warns once, then stops watching
+        let mut rng = rng.lock().unwrap();
+        rng.get_u64();
+        assert!(rng.thread_watch.is_none());
+    }
+
+    #[test]
+    fn test_finalizer() {
+        let mut raw = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut mixed =
+            SplittingRng::<Xoshiro256StarStar>::new(12345).finalize_with(Finalizer::Mix64);
+        assert_eq!(mixed.get_u64(), keyed::mix64(raw.get_u64()));
+        let mut child = mixed.split();
+        assert_eq!(child.finalizer(), Finalizer::Mix64);
+        child.get_bool();
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::from_state(child.to_state());
+        assert_eq!(copy.get_bool(), child.get_bool());
+        assert_eq!(copy.get_u64(), child.get_u64());
+    }
+
+    #[test]
+    fn test_bool_policy() {
+        let mut rng =
+            SplittingRng::<Xoshiro256StarStar>::new(12345).with_bool_policy(BoolPolicy::All64);
+        let word = rng.get_u64();
+        // Seeking drops the pool, so the next bools come from the first word
+        rng.seek(0);
+        for i in 0..64 {
+            assert_eq!(rng.get_bool(), (word >> i) & 1 == 0, "{}", i);
+        }
+        assert_eq!(rng.steps(), 1);
+        let mut rng = rng.with_bool_policy(BoolPolicy::Custom(0b1010));
+        rng.seek(0);
+        assert_eq!(rng.get_bool(), word & 0b10 == 0);
+        assert_eq!(rng.get_bool(), word & 0b1000 == 0);
+        rng.get_bool();
+        assert_eq!(rng.steps(), 2);
+    }
+
+    #[test]
+    fn test_signs() {
+        // V1 bools lean towards true, see `Algorithm`
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V2);
+        let total: i64 = (0..10000).map(|_| rng.get_sign() as i64).sum();
+        assert!(total.abs() < 300, "{}", total);
+        let flipped = rng.flip(2.5f64);
+        assert_eq!(flipped.abs(), 2.5);
+        // Drawn from the pool, so about one step per 61 signs
+        assert!(rng.steps() < 200);
+    }
+
+    #[test]
+    fn test_shuffle_with_hasher() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let items: Vec<u32> = (0..100).collect();
+        let fnv = fnv::FnvBuildHasher::default();
+        let shuffled = rng.shuffle_with_hasher(&items, &fnv);
+        assert_ne!(shuffled, items);
+        let mut sorted = shuffled.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, items);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(copy.shuffle_with_hasher(&items, &fnv), shuffled);
+    }
+
+    #[test]
+    fn test_fair_roll_u64() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let sides = 3 << 40;
+        let mut high = 0;
+        for _ in 0..3000 {
+            let roll = rng.fair_roll_u64(sides);
+            assert!(roll < sides);
+            if roll >= 2 << 40 {
+                high += 1;
+            }
+        }
+        assert!((high - 1000i32).abs() < 100, "{}", high);
+        assert_eq!(rng.fair_roll_u64(0), 0);
+        assert!(rng.fair_roll_usize(7) < 7);
+    }
+
+    #[test]
+    fn test_checked_raw() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.get_bool();
+        rng.get_u64();
+        let raw = rng.to_raw_checked();
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::try_from_raw(raw).unwrap();
+        assert_eq!(loaded.get_u64(), rng.get_u64());
+        let edited = (raw.0, raw.1 + 1, raw.2, raw.3, raw.4);
+        assert_eq!(
+            SplittingRng::<Xoshiro256StarStar>::try_from_raw(edited).err(),
+            Some(StateError::BadChecksum)
+        );
+        let (origin, steps, inner) = (raw.0, raw.1, raw.2);
+        let bools = (
+            origin,
+            steps,
+            inner,
+            70,
+            raw_checksum((origin, steps, inner, 70)),
+        );
+        assert_eq!(
+            SplittingRng::<Xoshiro256StarStar>::try_from_raw(bools).err(),
+            Some(StateError::BadBoolCount(70))
+        );
+    }
+
+    #[test]
+    fn test_state_rejects_empty_bool_policy() {
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut state = rng.to_state();
+        state.bool_policy = BoolPolicy::Custom(0);
+        assert_eq!(
+            SplittingRng::<Xoshiro256StarStar>::try_from_state(state).err(),
+            Some(StateError::EmptyBoolPolicy)
+        );
+        let result = std::panic::catch_unwind(|| {
+            SplittingRng::<Xoshiro256StarStar>::from_state(state);
+        });
+        assert!(result.is_err());
+        state.bool_policy = BoolPolicy::Custom(0b1010);
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::try_from_state(state).unwrap();
+        assert_eq!(loaded.bool_policy(), BoolPolicy::Custom(0b1010));
+        loaded.get_bool();
+    }
+
+    #[test]
+    fn test_new_from_time() {
+        let (mut rng, origin) = SplittingRng::<Xoshiro256StarStar>::new_from_time();
+        assert_eq!(rng.origin(), origin);
+        let mut replay = SplittingRng::<Xoshiro256StarStar>::new(origin);
+        assert_eq!(rng.get_u64(), replay.get_u64());
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..10 {
+            rng.get_u64();
+        }
+        other.seek(10);
+        assert_eq!(other.steps(), 10);
+        assert_eq!(other.get_u64(), rng.get_u64());
+        rng.seek(3);
+        other.seek(3);
+        assert_eq!(rng.get_bool(), other.get_bool());
+        assert_eq!(rng.steps(), 4);
+    }
+
+    #[test]
+    fn test_seek_is_not_drawing() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.limit_steps(5);
+        rng.seek(1000);
+        assert_eq!(rng.steps_remaining(), Some(5));
+        assert_eq!(rng.audit().total(), 0);
+        other.prefetch(16);
+        other.get_u64();
+        other.seek(1000);
+        assert_eq!(other.get_u64(), rng.get_u64());
+        other.seek(10);
+        rng.seek(10);
+        assert_eq!(other.get_u64(), rng.get_u64());
+    }
+
+    #[test]
+    fn test_roll() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut v3 = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V3);
+        let mut counts = [0; 6];
+        for _ in 0..60000 {
+            let face = rng.roll(6);
+            assert_eq!(v3.roll(6), face);
+            counts[face as usize] += 1;
+        }
+        for c in counts {
+            assert!((c - 10000i32).abs() < 300, "{:?}", counts);
+        }
+        // The same as a fair roll under V3, from any version
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut fair = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V3);
+        for sides in 1..1000 {
+            assert_eq!(rng.roll(sides), fair.fair_roll(sides));
+        }
+        assert_eq!(rng.steps(), fair.steps());
+        assert_eq!(rng.roll(0), 0);
+        assert_eq!(rng.audit().count(trace::Method::Roll), 1000);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_multiply_shift_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V3);
+        let mut counts = [0; 3];
+        for _ in 0..30000 {
+            counts[rng.biased_roll(3) as usize] += 1;
+            counts[rng.fair_roll(3) as usize] += 1;
+            assert!(rng.fair_roll(u32::MAX) < u32::MAX);
+        }
+        for c in counts {
+            assert!((c - 20000i32).abs() < 600, "{:?}", counts);
+        }
+        // Half of a large die is still half the rolls
+        let high = (0..1000)
+            .filter(|_| rng.biased_roll(3 << 30) >= 3 << 29)
+            .count();
+        assert!((high as i32 - 500).abs() < 60, "{}", high);
+        assert_eq!(rng.biased_roll(0), 0);
+        // The deprecated method still gives the old values
+        let mut old = rng.clone();
+        assert_eq!(
+            rng.biased_roll(1000),
+            compat::BiasedRoll::biased_roll(&mut old, 1000)
+        );
+    }
+
+    #[test]
+    fn test_shuffle_owned() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let names: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let shuffled = rng.shuffle_iter(names.iter().cloned());
+        let order = copy.shuffle(&(0..20).collect::<Vec<usize>>());
+        assert!(order.iter().zip(&shuffled).all(|(i, n)| names[*i] == *n));
+        assert_eq!(rng.shuffle_vec(Vec::<String>::new()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_tree_state() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::with_algorithm(12345, Algorithm::V2);
+        rng.child("world").child("caves").get_u64();
+        rng.child("audio").get_bool();
+        let tree = rng.to_tree_state();
+        assert_eq!(tree.children["world"].children.len(), 1);
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::from_tree_state(tree.clone());
+        assert_eq!(loaded.to_tree_state(), tree);
+        let caves = loaded.child("world").child("caves");
+        assert_eq!(caves.depth(), 2);
+        assert_eq!(caves.get_u64(), rng.child("world").child("caves").get_u64());
+        assert_eq!(
+            loaded.child("audio").get_bool(),
+            rng.child("audio").get_bool()
+        );
+    }
+
+    #[test]
+    fn test_expanded_seeding() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345).seeded_with(Seeding::SplitMix);
+        let mut seed = [0u8; 32];
+        SplitMix64::seed_from_u64(12345).fill_bytes(&mut seed);
+        let mut backend = Xoshiro256StarStar::from_seed(seed);
+        backend.next_u64();
+        assert_eq!(rng.get_u64(), backend.next_u64());
+        let mut child = rng.split();
+        assert_eq!(child.seeding(), Seeding::SplitMix);
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::from_state(child.to_state());
+        assert_eq!(loaded.get_u64(), child.get_u64());
+        child.seek(0);
+        assert_eq!(
+            child.get_u64(),
+            child.clone().seeded_with(Seeding::SplitMix).get_u64()
+        );
+    }
+
     #[test]
     fn test_shuffle_uniformity() {
         // This is a silly prop-test style exercise
@@ -233,4 +1760,71 @@ mod tests {
         assert!(avg > 49.5);
         assert!(avg < 50.5);
     }
+
+    #[test]
+    fn test_fisher_yates() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345)
+            .with_shuffle_algorithm(ShuffleAlgorithm::FisherYates);
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..6000 {
+            let before = rng.steps();
+            let order = rng.shuffle(&[0, 1, 2]);
+            assert_eq!(rng.steps() - before, 2);
+            *counts.entry(order).or_insert(0i32) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 100),
+            "{:?}",
+            counts
+        );
+        let mut child = rng.split();
+        assert_eq!(child.shuffle_algorithm(), ShuffleAlgorithm::FisherYates);
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::from_state(child.to_state());
+        assert_eq!(loaded.shuffle(&[1, 2, 3, 4]), child.shuffle(&[1, 2, 3, 4]));
+        // The hash sort is still there when asked for
+        let mut plain = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = rng.clone();
+        other.seek(0);
+        assert_eq!(
+            other.shuffle_with(&[1, 2, 3, 4], ShuffleAlgorithm::HashSort),
+            plain.shuffle(&[1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn test_batched_hash_shuffle() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345)
+            .with_shuffle_algorithm(ShuffleAlgorithm::BatchedHash);
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..6000 {
+            *counts.entry(rng.shuffle(&[0, 1, 2])).or_insert(0i32) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 100),
+            "{:?}",
+            counts
+        );
+        let items: Vec<u32> = (0..100_003).collect();
+        let mut shuffled = rng.shuffle(&items);
+        assert_ne!(shuffled, items);
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, items);
+    }
+
+    #[test]
+    fn test_shuffle_in_place() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let mut hand = [1, 2, 3, 4, 5, 6, 7];
+        rng.shuffle_array(&mut hand);
+        let expected = copy.shuffle_with(&[1, 2, 3, 4, 5, 6, 7], ShuffleAlgorithm::FisherYates);
+        assert_eq!(hand, expected[..]);
+        // Items need not be Copy
+        let mut names = [String::from("a"), String::from("b")];
+        rng.shuffle_slice(&mut names);
+        assert!(names.contains(&String::from("a")));
+        assert_eq!(rng.audit().count(trace::Method::Shuffle), 2);
+    }
 }
@@ -0,0 +1,302 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Loot tables with rarity tiers, weighted entries,
+//! guaranteed drops and nested tables.
+//!
+//! Each roll first picks a tier by its weight, then an
+//! entry within that tier by the entry weights. An entry
+//! may itself be a table, in which case it is rolled once.
+
+use crate::distributions::{DiscreteDistribution, Distribution, DistributionError};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// The reasons a loot table may fail to build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LootError {
+    /// An entry or guarantee named a tier which was never added
+    UnknownTier(String),
+    /// A guarantee named a tier with no entries
+    EmptyTier(String),
+    /// Two tiers were added with the same name
+    DuplicateTier(String),
+    /// The tier or entry weights were invalid
+    Weights(DistributionError),
+}
+
+impl fmt::Display for LootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LootError::UnknownTier(name) => write!(f, "unknown tier {:?}", name),
+            LootError::EmptyTier(name) => write!(f, "tier {:?} has no entries", name),
+            LootError::DuplicateTier(name) => write!(f, "tier {:?} was added twice", name),
+            LootError::Weights(e) => write!(f, "bad weights: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LootError {}
+
+impl From<DistributionError> for LootError {
+    fn from(e: DistributionError) -> Self {
+        LootError::Weights(e)
+    }
+}
+
+/// Something that can be dropped by a loot table
+#[derive(Debug, Clone, PartialEq)]
+pub enum LootEntry<T> {
+    /// A single item
+    Item(T),
+    /// Another table, rolled once when chosen
+    Table(LootTable<T>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Tier<T> {
+    name: String,
+    weight: f64,
+    entries: Vec<LootEntry<T>>,
+    weights: Vec<f64>,
+    dist: Option<DiscreteDistribution>,
+}
+
+/// Collects the tiers, entries and guarantees of a `LootTable`
+#[derive(Debug, Clone)]
+pub struct LootTableBuilder<T> {
+    tiers: Vec<Tier<T>>,
+    // Entries are kept aside until build, so tiers may be added in any order
+    entries: Vec<(String, f64, LootEntry<T>)>,
+    guarantees: Vec<(String, usize)>,
+}
+
+impl<T> LootTableBuilder<T> {
+    /// Add a rarity tier, chosen in proportion to its weight
+    pub fn tier(mut self, name: impl Into<String>, weight: f64) -> Self {
+        self.tiers.push(Tier {
+            name: name.into(),
+            weight,
+            entries: Vec::new(),
+            weights: Vec::new(),
+            dist: None,
+        });
+        self
+    }
+
+    /// Add an item to a tier
+    pub fn item(self, tier: impl Into<String>, weight: f64, item: T) -> Self {
+        self.entry(tier, weight, LootEntry::Item(item))
+    }
+
+    /// Add a nested table to a tier
+    pub fn table(self, tier: impl Into<String>, weight: f64, table: LootTable<T>) -> Self {
+        self.entry(tier, weight, LootEntry::Table(table))
+    }
+
+    /// Add any entry to a tier
+    pub fn entry(mut self, tier: impl Into<String>, weight: f64, entry: LootEntry<T>) -> Self {
+        self.entries.push((tier.into(), weight, entry));
+        self
+    }
+
+    /// Require every roll to include at least `count`
+    /// drops from the given tier
+    pub fn guarantee(mut self, tier: impl Into<String>, count: usize) -> Self {
+        self.guarantees.push((tier.into(), count));
+        self
+    }
+
+    /// Validate the tiers and weights and build the table
+    pub fn build(mut self) -> Result<LootTable<T>, LootError> {
+        for (i, tier) in self.tiers.iter().enumerate() {
+            if self.tiers[..i].iter().any(|t| t.name == tier.name) {
+                return Err(LootError::DuplicateTier(tier.name.clone()));
+            }
+        }
+        for (name, weight, entry) in self.entries {
+            let tier = self
+                .tiers
+                .iter_mut()
+                .find(|t| t.name == name)
+                .ok_or(LootError::UnknownTier(name))?;
+            tier.weights.push(weight);
+            tier.entries.push(entry);
+        }
+        for tier in self.tiers.iter_mut() {
+            if !tier.entries.is_empty() {
+                tier.dist = Some(DiscreteDistribution::new(&tier.weights)?);
+            }
+        }
+        let mut guarantees = Vec::with_capacity(self.guarantees.len());
+        for (name, count) in self.guarantees {
+            match self.tiers.iter().position(|t| t.name == name) {
+                Some(i) if self.tiers[i].dist.is_none() => return Err(LootError::EmptyTier(name)),
+                Some(i) => guarantees.push((i, count)),
+                None => return Err(LootError::UnknownTier(name)),
+            }
+        }
+        // Empty tiers can never be picked
+        let tier_weights: Vec<f64> = self
+            .tiers
+            .iter()
+            .map(|t| if t.dist.is_some() { t.weight } else { 0.0 })
+            .collect();
+        let tier_dist = DiscreteDistribution::new(&tier_weights)?;
+        Ok(LootTable {
+            tiers: self.tiers,
+            tier_dist,
+            guarantees,
+        })
+    }
+}
+
+/// A reproducible drop table
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::loot::LootTable;
+/// use splittable_random::SplittingRng;
+///
+/// let table = LootTable::builder()
+///     .tier("common", 90.0)
+///     .tier("rare", 10.0)
+///     .item("common", 3.0, "copper")
+///     .item("common", 1.0, "silver")
+///     .item("rare", 1.0, "gold")
+///     .guarantee("rare", 1)
+///     .build()
+///     .unwrap();
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let drops = table.roll(&mut rng, 3);
+/// assert_eq!(drops.len(), 3);
+/// assert_eq!(drops[0], "gold");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LootTable<T> {
+    tiers: Vec<Tier<T>>,
+    tier_dist: DiscreteDistribution,
+    guarantees: Vec<(usize, usize)>,
+}
+
+impl<T> LootTable<T> {
+    /// Start building a table
+    pub fn builder() -> LootTableBuilder<T> {
+        LootTableBuilder {
+            tiers: Vec::new(),
+            entries: Vec::new(),
+            guarantees: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> LootTable<T> {
+    /// Roll `count` drops
+    ///
+    /// Guaranteed drops are rolled first, directly from their
+    /// tier, and the rest are rolled freely. If the guarantees
+    /// add up to more than `count`, all of them are still dropped.
+    /// A nested table adds every drop of a single roll.
+    ///
+    /// The drops are in the order rolled, so the guaranteed ones
+    /// always come first, in the order they were added. Shuffle
+    /// the drops if players shouldn't see which were guaranteed.
+    pub fn roll<R: RngCore + SeedableRng>(
+        &self,
+        rng: &mut SplittingRng<R>,
+        count: usize,
+    ) -> Vec<T> {
+        let mut drops = Vec::with_capacity(count);
+        let mut rolled = 0;
+        for (tier, times) in self.guarantees.iter() {
+            for _ in 0..*times {
+                self.roll_tier(rng, *tier, &mut drops);
+                rolled += 1;
+            }
+        }
+        while rolled < count {
+            let tier = rng.sample(&self.tier_dist);
+            self.roll_tier(rng, tier, &mut drops);
+            rolled += 1;
+        }
+        drops
+    }
+
+    /// Roll a single drop, ignoring guarantees
+    pub fn roll_one<R: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<R>) -> Vec<T> {
+        let mut drops = Vec::new();
+        let tier = rng.sample(&self.tier_dist);
+        self.roll_tier(rng, tier, &mut drops);
+        drops
+    }
+
+    fn roll_tier<R: RngCore + SeedableRng>(
+        &self,
+        rng: &mut SplittingRng<R>,
+        tier: usize,
+        drops: &mut Vec<T>,
+    ) {
+        let tier = &self.tiers[tier];
+        let dist = tier.dist.as_ref().expect("Picked a tier with no entries");
+        match &tier.entries[dist.sample(rng)] {
+            LootEntry::Item(item) => drops.push(item.clone()),
+            LootEntry::Table(table) => drops.extend(table.roll(rng, 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_loot_tiers_and_nesting() {
+        let gems = LootTable::builder()
+            .tier("gem", 1.0)
+            .item("gem", 1.0, "ruby")
+            .item("gem", 1.0, "opal")
+            .build()
+            .unwrap();
+        let table = LootTable::builder()
+            .tier("junk", 3.0)
+            .tier("rare", 1.0)
+            .tier("empty", 100.0)
+            .item("junk", 1.0, "bone")
+            .table("rare", 1.0, gems)
+            .guarantee("rare", 1)
+            .build()
+            .unwrap();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 10000;
+        let mut rare = 0;
+        for _ in 0..iter {
+            let drops = table.roll(&mut rng, 2);
+            assert_eq!(drops.len(), 2);
+            assert!(drops[0] == "ruby" || drops[0] == "opal");
+            if drops[1] != "bone" {
+                rare += 1;
+            }
+        }
+        // The empty tier is never picked, so 1 in 4 free rolls is rare
+        let frac = rare as f64 / iter as f64;
+        assert!((frac - 0.25).abs() < 0.02, "{}", frac);
+        let bad = LootTable::builder()
+            .tier("a", 1.0)
+            .item("b", 1.0, 0)
+            .build();
+        assert_eq!(bad, Err(LootError::UnknownTier("b".into())));
+    }
+
+    #[test]
+    fn test_duplicate_tiers() {
+        let table = LootTable::builder()
+            .tier("common", 1.0)
+            .tier("common", 2.0)
+            .item("common", 1.0, "bone")
+            .build();
+        assert_eq!(table, Err(LootError::DuplicateTier("common".into())));
+    }
+}
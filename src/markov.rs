@@ -0,0 +1,119 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Markov chains over a fixed number of states.
+//!
+//! Each row of the transition matrix becomes an alias
+//! table, so a step costs the same however many states
+//! there are.
+
+use crate::distributions::{AliasTable, Distribution, DistributionError};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// The reasons a transition matrix may be refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkovError {
+    /// There were no states, or a row did not have
+    /// one weight per state
+    NotSquare,
+    /// The weights of a row were invalid
+    Row(usize, DistributionError),
+}
+
+impl fmt::Display for MarkovError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkovError::NotSquare => write!(f, "transition matrix is not square"),
+            MarkovError::Row(row, e) => write!(f, "bad weights in row {}: {}", row, e),
+        }
+    }
+}
+
+impl std::error::Error for MarkovError {}
+
+/// Steps a Markov chain given by rows of transition weights
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkovSampler {
+    rows: Vec<AliasTable>,
+}
+
+impl MarkovSampler {
+    /// Create a sampler where `matrix[from][to]` is the weight
+    /// of moving from one state to another
+    ///
+    /// Rows need not sum to 1, but each needs a weight above zero
+    pub fn new<R: AsRef<[f64]>>(matrix: &[R]) -> Result<Self, MarkovError> {
+        let n = matrix.len();
+        if n == 0 || matrix.iter().any(|row| row.as_ref().len() != n) {
+            return Err(MarkovError::NotSquare);
+        }
+        let rows = matrix
+            .iter()
+            .enumerate()
+            .map(|(i, row)| AliasTable::new(row.as_ref()).map_err(|e| MarkovError::Row(i, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(MarkovSampler { rows })
+    }
+
+    /// The number of states
+    pub fn states(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Pick the state after `state`
+    ///
+    /// Panics if the state is out of range
+    pub fn next_state<T: RngCore + SeedableRng>(
+        &self,
+        rng: &mut SplittingRng<T>,
+        state: usize,
+    ) -> usize {
+        self.rows[state].sample(rng)
+    }
+
+    /// Provide the endless walk of states after `start`
+    pub fn walk<'a, T: RngCore + SeedableRng>(
+        &'a self,
+        rng: &'a mut SplittingRng<T>,
+        start: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let mut state = start;
+        std::iter::repeat_with(move || {
+            state = self.next_state(rng, state);
+            state
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_markov_chain() {
+        // Sunny stays sunny 90% of the time, rain stays 50%
+        let weather = MarkovSampler::new(&[[9.0, 1.0], [1.0, 1.0]]).unwrap();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 50000;
+        let sunny = weather
+            .walk(&mut rng, 0)
+            .take(iter)
+            .filter(|s| *s == 0)
+            .count();
+        // The stationary chance of sun is 5/6
+        let frac = sunny as f64 / iter as f64;
+        assert!((frac - 5.0 / 6.0).abs() < 0.01, "{}", frac);
+        assert_eq!(
+            MarkovSampler::new(&[vec![1.0, 1.0], vec![1.0]]),
+            Err(MarkovError::NotSquare)
+        );
+        assert_eq!(
+            MarkovSampler::new(&[[1.0, 0.0], [0.0, 0.0]]),
+            Err(MarkovError::Row(1, DistributionError::ZeroTotal))
+        );
+    }
+}
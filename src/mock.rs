@@ -0,0 +1,204 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! A scripted stand-in for `SplittingRng` in unit tests.
+//!
+//! Rather than hunting for a seed which happens to roll a
+//! natural 20, script the roll. The mock has the same core
+//! methods, and each call takes the next scripted value.
+//! Calling a method the script does not expect panics, as
+//! does running out of script, so a test can't silently
+//! drift away from the scenario it sets up.
+
+use std::collections::VecDeque;
+
+/// One scripted result
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scripted {
+    /// The result of `get_bool`
+    Bool(bool),
+    /// The result of `get_u32`
+    U32(u32),
+    /// The result of `get_u64`
+    U64(u64),
+    /// The result of `get_f64`, within [0, 1)
+    F64(f64),
+    /// The result of `roll`, `biased_roll` or `fair_roll`, from 0,
+    /// so a natural 20 is `Roll(19)`
+    Roll(u32),
+    /// The order of `shuffle`, as the index each item is taken from
+    Order(Vec<usize>),
+    /// The script for the child of `split`
+    Split(Vec<Scripted>),
+}
+
+/// Replays scripted values through the core methods of `SplittingRng`
+///
+/// ```
+/// use splittable_random::mock::{MockSplittingRng, Scripted};
+///
+/// let mut rng = MockSplittingRng::new(vec![Scripted::Roll(19), Scripted::Bool(true)]);
+/// assert_eq!(rng.fair_roll(20) + 1, 20);
+/// assert!(rng.get_bool());
+/// assert!(rng.is_done());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockSplittingRng {
+    script: VecDeque<Scripted>,
+    calls: usize,
+}
+
+impl MockSplittingRng {
+    /// Create a mock which replays the script in order
+    pub fn new(script: impl IntoIterator<Item = Scripted>) -> Self {
+        MockSplittingRng {
+            script: script.into_iter().collect(),
+            calls: 0,
+        }
+    }
+
+    /// Add more values to the end of the script
+    pub fn push(&mut self, value: Scripted) {
+        self.script.push_back(value);
+    }
+
+    /// How many scripted values are left
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+
+    /// Whether every scripted value has been used
+    pub fn is_done(&self) -> bool {
+        self.script.is_empty()
+    }
+
+    fn next(&mut self, method: &str) -> Scripted {
+        self.calls += 1;
+        match self.script.pop_front() {
+            Some(value) => value,
+            None => panic!(
+                "mock rng script ran out at call {}, to {}",
+                self.calls, method
+            ),
+        }
+    }
+
+    fn unexpected(&self, method: &str, value: &Scripted) -> ! {
+        panic!(
+            "mock rng call {} was to {}, but the script had {:?}",
+            self.calls, method, value
+        )
+    }
+
+    /// Provide the scripted bool
+    pub fn get_bool(&mut self) -> bool {
+        match self.next("get_bool") {
+            Scripted::Bool(b) => b,
+            other => self.unexpected("get_bool", &other),
+        }
+    }
+
+    /// Provide the scripted u32
+    pub fn get_u32(&mut self) -> u32 {
+        match self.next("get_u32") {
+            Scripted::U32(x) => x,
+            other => self.unexpected("get_u32", &other),
+        }
+    }
+
+    /// Provide the scripted u64
+    pub fn get_u64(&mut self) -> u64 {
+        match self.next("get_u64") {
+            Scripted::U64(x) => x,
+            other => self.unexpected("get_u64", &other),
+        }
+    }
+
+    /// Provide the scripted float
+    /// Panics if it is outside of [0, 1)
+    pub fn get_f64(&mut self) -> f64 {
+        match self.next("get_f64") {
+            Scripted::F64(x) if (0.0..1.0).contains(&x) => x,
+            other => self.unexpected("get_f64", &other),
+        }
+    }
+
+    /// Provide the scripted roll
+    /// Panics if it is not below `sides`, or zero with no sides
+    pub fn biased_roll(&mut self, sides: u32) -> u32 {
+        self.scripted_roll("biased_roll", sides)
+    }
+
+    /// Provide the scripted roll, as `biased_roll`
+    pub fn fair_roll(&mut self, sides: u32) -> u32 {
+        self.scripted_roll("fair_roll", sides)
+    }
+
+    /// Provide the scripted roll, as `biased_roll`
+    pub fn roll(&mut self, sides: u32) -> u32 {
+        self.scripted_roll("roll", sides)
+    }
+
+    fn scripted_roll(&mut self, method: &str, sides: u32) -> u32 {
+        match self.next(method) {
+            Scripted::Roll(x) if x < sides.max(1) => x,
+            other => self.unexpected(method, &other),
+        }
+    }
+
+    /// Reorder the list in the scripted order
+    /// Panics if the order is not a permutation of the list's indices
+    pub fn shuffle<L: Copy>(&mut self, list: &[L]) -> Vec<L> {
+        match self.next("shuffle") {
+            Scripted::Order(order) if is_permutation(&order, list.len()) => {
+                order.iter().map(|i| list[*i]).collect()
+            }
+            other => self.unexpected("shuffle", &other),
+        }
+    }
+
+    /// Provide a child mock, with the scripted script
+    pub fn split(&mut self) -> MockSplittingRng {
+        match self.next("split") {
+            Scripted::Split(script) => MockSplittingRng::new(script),
+            other => self.unexpected("split", &other),
+        }
+    }
+}
+
+fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    order.len() == len
+        && order
+            .iter()
+            .all(|i| *i < len && !std::mem::replace(&mut seen[*i], true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_calls() {
+        let mut rng = MockSplittingRng::new(vec![
+            Scripted::Roll(5),
+            Scripted::Order(vec![2, 0, 1]),
+            Scripted::Split(vec![Scripted::U64(7)]),
+        ]);
+        rng.push(Scripted::F64(0.25));
+        assert_eq!(rng.biased_roll(6), 5);
+        assert_eq!(rng.shuffle(&['a', 'b', 'c']), ['c', 'a', 'b']);
+        let mut child = rng.split();
+        assert_eq!(child.get_u64(), 7);
+        assert_eq!(rng.remaining(), 1);
+        assert_eq!(rng.get_f64(), 0.25);
+        assert!(rng.is_done() && child.is_done());
+        let mismatch = std::panic::catch_unwind(|| {
+            MockSplittingRng::new(vec![Scripted::Roll(6)]).fair_roll(6)
+        });
+        assert!(mismatch.is_err());
+        let exhausted = std::panic::catch_unwind(|| MockSplittingRng::new(Vec::new()).get_bool());
+        assert!(exhausted.is_err());
+    }
+}
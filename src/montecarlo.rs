@@ -0,0 +1,144 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Monte Carlo estimates with error bars.
+//!
+//! Every trial gets its own child rng, split off in order,
+//! so an estimate is the same whether it ran on one thread
+//! or many.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// The result of running many trials
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// The number of trials
+    pub trials: usize,
+    /// The mean of the trials
+    pub mean: f64,
+    /// The sample variance of the trials
+    pub variance: f64,
+    /// The standard error of the mean
+    pub std_error: f64,
+}
+
+impl Estimate {
+    /// Summarize trial results, in order
+    ///
+    /// With fewer than two trials the variance and standard
+    /// error are NaN, and with no trials the mean is NaN too
+    pub fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Estimate {
+                trials: 0,
+                mean: f64::NAN,
+                variance: f64::NAN,
+                std_error: f64::NAN,
+            };
+        }
+        // Welford's method avoids cancellation in the variance
+        let mut mean = 0.0;
+        let mut m2 = 0.0;
+        for (i, x) in values.iter().enumerate() {
+            let delta = x - mean;
+            mean += delta / (i + 1) as f64;
+            m2 += delta * (x - mean);
+        }
+        let n = values.len() as f64;
+        let variance = m2 / (n - 1.0);
+        Estimate {
+            trials: values.len(),
+            mean,
+            variance,
+            std_error: (variance / n).sqrt(),
+        }
+    }
+
+    /// The interval of `z` standard errors around the mean,
+    /// where 1.96 gives about 95% confidence
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        (
+            self.mean - z * self.std_error,
+            self.mean + z * self.std_error,
+        )
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Run `trials` trials and estimate their mean
+    ///
+    /// No trials split off no children and give an estimate
+    /// of NaN, as described for `Estimate::from_values`.
+    pub fn estimate<F>(&mut self, trials: usize, mut trial: F) -> Estimate
+    where
+        F: FnMut(&mut SplittingRng<T>) -> f64,
+    {
+        let values: Vec<f64> = (0..trials).map(|_| trial(&mut self.split())).collect();
+        Estimate::from_values(&values)
+    }
+
+    /// Run `trials` trials across `threads` threads, giving the
+    /// same estimate as `estimate` would, even with no trials
+    pub fn estimate_parallel<F>(&mut self, trials: usize, threads: usize, trial: F) -> Estimate
+    where
+        T: Send,
+        F: Fn(&mut SplittingRng<T>) -> f64 + Sync,
+    {
+        let mut children: Vec<SplittingRng<T>> = (0..trials).map(|_| self.split()).collect();
+        let mut values = vec![0.0; trials];
+        let chunk = trials.div_ceil(threads.max(1)).max(1);
+        std::thread::scope(|scope| {
+            for (rngs, out) in children.chunks_mut(chunk).zip(values.chunks_mut(chunk)) {
+                let trial = &trial;
+                scope.spawn(move || {
+                    for (rng, slot) in rngs.iter_mut().zip(out) {
+                        *slot = trial(rng);
+                    }
+                });
+            }
+        });
+        Estimate::from_values(&values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_estimate_pi() {
+        let inside = |rng: &mut SplittingRng<Xoshiro256StarStar>| {
+            let [x, y] = rng.get_point_in_rect([0.0, 0.0], [1.0, 1.0]);
+            if x * x + y * y < 1.0 {
+                4.0
+            } else {
+                0.0
+            }
+        };
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let serial = rng.estimate(20000, inside);
+        let parallel = copy.estimate_parallel(20000, 4, inside);
+        assert_eq!(serial, parallel);
+        let (low, high) = serial.interval(4.0);
+        assert!(low < std::f64::consts::PI && std::f64::consts::PI < high);
+        assert!(serial.std_error < 0.02, "{:?}", serial);
+    }
+
+    #[test]
+    fn test_estimate_without_trials() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let none = rng.estimate(0, |rng| rng.get_f64());
+        assert_eq!(none.trials, 0);
+        assert!(none.mean.is_nan() && none.variance.is_nan() && none.std_error.is_nan());
+        assert_eq!(rng.steps(), 0);
+        let none = rng.estimate_parallel(0, 4, |rng| rng.get_f64());
+        assert!(none.mean.is_nan() && none.std_error.is_nan());
+        let one = rng.estimate(1, |_| 2.5);
+        assert_eq!(one.mean, 2.5);
+        assert!(one.variance.is_nan() && one.std_error.is_nan());
+    }
+}
@@ -0,0 +1,109 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random network addresses and ports,
+//! for network simulation fixtures.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// The IANA dynamic port range
+const EPHEMERAL_START: u16 = 49152;
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide any IPv4 address
+    pub fn get_ipv4(&mut self) -> Ipv4Addr {
+        Ipv4Addr::from(self.get_u32())
+    }
+
+    /// Provide an IPv4 address within a CIDR block,
+    /// such as `10.0.0.0/8`, or `None` if the prefix
+    /// is longer than 32 bits
+    ///
+    /// The network bits come from `network`, and only
+    /// the host bits are random
+    pub fn get_ipv4_in(&mut self, network: Ipv4Addr, prefix: u8) -> Option<Ipv4Addr> {
+        if prefix > 32 {
+            return None;
+        }
+        let host_mask = u32::MAX.checked_shr(prefix as u32).unwrap_or(0);
+        let bits = (u32::from(network) & !host_mask) | (self.get_u32() & host_mask);
+        Some(Ipv4Addr::from(bits))
+    }
+
+    /// Provide any IPv6 address
+    pub fn get_ipv6(&mut self) -> Ipv6Addr {
+        Ipv6Addr::from(self.get_u128_bits())
+    }
+
+    /// Provide an IPv6 address within a CIDR block,
+    /// such as `fd00::/8`, or `None` if the prefix
+    /// is longer than 128 bits
+    ///
+    /// The network bits come from `network`, and only
+    /// the host bits are random
+    pub fn get_ipv6_in(&mut self, network: Ipv6Addr, prefix: u8) -> Option<Ipv6Addr> {
+        if prefix > 128 {
+            return None;
+        }
+        let host_mask = u128::MAX.checked_shr(prefix as u32).unwrap_or(0);
+        let bits = (u128::from(network) & !host_mask) | (self.get_u128_bits() & host_mask);
+        Some(Ipv6Addr::from(bits))
+    }
+
+    /// Provide a MAC address
+    ///
+    /// The address is marked as locally administered and
+    /// unicast, so it can never clash with a real vendor's
+    /// hardware address
+    pub fn get_mac(&mut self) -> [u8; 6] {
+        let bits = self.get_u64().to_be_bytes();
+        let mut mac = [0; 6];
+        mac.copy_from_slice(&bits[..6]);
+        mac[0] = (mac[0] | 0b10) & !0b01;
+        mac
+    }
+
+    /// Provide any nonzero port number
+    pub fn get_port(&mut self) -> u16 {
+        1 + self.fair_roll(u16::MAX as u32) as u16
+    }
+
+    /// Provide a port from the dynamic range, 49152 to 65535,
+    /// as an operating system would for an outgoing connection
+    pub fn get_ephemeral_port(&mut self) -> u16 {
+        let count = (u16::MAX - EPHEMERAL_START) as u32 + 1;
+        EPHEMERAL_START + self.fair_roll(count) as u16
+    }
+
+    fn get_u128_bits(&mut self) -> u128 {
+        ((self.get_u64() as u128) << 64) | self.get_u64() as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_addresses_in_blocks() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..1000 {
+            let ip = rng.get_ipv4_in(Ipv4Addr::new(192, 168, 7, 99), 24).unwrap();
+            assert_eq!(ip.octets()[..3], [192, 168, 7]);
+            let ip = rng.get_ipv6_in("fd00::".parse().unwrap(), 8).unwrap();
+            assert_eq!(ip.octets()[0], 0xfd);
+            let mac = rng.get_mac();
+            assert_eq!(mac[0] & 0b11, 0b10);
+            assert!(rng.get_port() > 0);
+            assert!(rng.get_ephemeral_port() >= EPHEMERAL_START);
+        }
+        let exact = Ipv4Addr::new(1, 2, 3, 4);
+        assert_eq!(rng.get_ipv4_in(exact, 32), Some(exact));
+        assert_eq!(rng.get_ipv4_in(exact, 33), None);
+        assert_ne!(rng.get_ipv4_in(exact, 0), Some(exact));
+    }
+}
@@ -0,0 +1,183 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! White noise and smooth value noise over integer
+//! lattices in 1, 2 and 3 dimensions.
+//!
+//! Noise is built on the same stateless keyed lookup as
+//! `SplittingRng::value_at`, so any point can be sampled
+//! in any order and always gives the same result.
+
+use crate::keyed::keyed_value;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// A noise field, seeded from an rng
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Noise {
+    seed: u64,
+}
+
+fn unit(value: u64) -> f64 {
+    (value >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Eases the interpolation so the noise has
+/// no visible creases at lattice lines
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Split a coordinate into its lattice cell
+/// and the distance into that cell
+///
+/// Coordinates past the range of `i64` saturate, and the
+/// next cell along wraps, so huge coordinates still give noise
+fn cell(x: f64) -> (i64, f64) {
+    let floor = x.floor();
+    (floor as i64, x - floor)
+}
+
+impl Noise {
+    /// Create a noise field from an rng, usually a child
+    /// made with `split`
+    ///
+    /// The field depends only on the origin of the rng
+    /// and does not advance it.
+    pub fn new<T: RngCore + SeedableRng>(rng: &SplittingRng<T>) -> Self {
+        Noise { seed: rng.origin }
+    }
+
+    /// White noise in [0, 1) at a lattice point
+    pub fn white1(&self, x: i64) -> f64 {
+        unit(keyed_value(self.seed, x))
+    }
+
+    /// White noise in [0, 1) at a lattice point
+    pub fn white2(&self, x: i64, y: i64) -> f64 {
+        unit(keyed_value(self.seed, (x, y)))
+    }
+
+    /// White noise in [0, 1) at a lattice point
+    pub fn white3(&self, x: i64, y: i64, z: i64) -> f64 {
+        unit(keyed_value(self.seed, (x, y, z)))
+    }
+
+    /// Smooth value noise in [0, 1), which matches
+    /// the white noise at every lattice point
+    pub fn value1(&self, x: f64) -> f64 {
+        let (x0, tx) = cell(x);
+        lerp(
+            self.white1(x0),
+            self.white1(x0.wrapping_add(1)),
+            smoothstep(tx),
+        )
+    }
+
+    /// Smooth value noise in [0, 1), which matches
+    /// the white noise at every lattice point
+    pub fn value2(&self, x: f64, y: f64) -> f64 {
+        let (x0, tx) = cell(x);
+        let (y0, ty) = cell(y);
+        let (sx, sy) = (smoothstep(tx), smoothstep(ty));
+        let top = lerp(self.white2(x0, y0), self.white2(x0.wrapping_add(1), y0), sx);
+        let bottom = lerp(
+            self.white2(x0, y0.wrapping_add(1)),
+            self.white2(x0.wrapping_add(1), y0.wrapping_add(1)),
+            sx,
+        );
+        lerp(top, bottom, sy)
+    }
+
+    /// Smooth value noise in [0, 1), which matches
+    /// the white noise at every lattice point
+    pub fn value3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, tx) = cell(x);
+        let (y0, ty) = cell(y);
+        let (z0, tz) = cell(z);
+        let (sx, sy, sz) = (smoothstep(tx), smoothstep(ty), smoothstep(tz));
+        let plane = |z: i64| {
+            let top = lerp(
+                self.white3(x0, y0, z),
+                self.white3(x0.wrapping_add(1), y0, z),
+                sx,
+            );
+            let bottom = lerp(
+                self.white3(x0, y0.wrapping_add(1), z),
+                self.white3(x0.wrapping_add(1), y0.wrapping_add(1), z),
+                sx,
+            );
+            lerp(top, bottom, sy)
+        };
+        lerp(plane(z0), plane(z0.wrapping_add(1)), sz)
+    }
+
+    /// Layered value noise in [0, 1), for heightmaps
+    ///
+    /// Each octave doubles the frequency and halves
+    /// the amplitude of the one before it
+    pub fn fractal2(&self, x: f64, y: f64, octaves: u32) -> f64 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut norm = 0.0;
+        for octave in 0..octaves {
+            // Offset each octave so their lattices don't line up
+            let offset = octave as f64 * 17.31;
+            total += amplitude * self.value2(x * frequency + offset, y * frequency + offset);
+            norm += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        if norm == 0.0 {
+            0.0
+        } else {
+            total / norm
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_value_noise() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let noise = Noise::new(&rng.split());
+        assert_eq!(noise.value2(3.0, -4.0), noise.white2(3, -4));
+        assert_eq!(noise.value3(1.0, 2.0, 3.0), noise.white3(1, 2, 3));
+        let mut acc = 0.0;
+        let mut prev = noise.value1(0.0);
+        for i in 1..10000 {
+            let x = i as f64 * 0.01;
+            let v = noise.value1(x);
+            assert!((0.0..1.0).contains(&v));
+            // Small steps give small changes
+            assert!((v - prev).abs() < 0.02);
+            prev = v;
+            acc += noise.white1(i);
+        }
+        let avg = acc / 10000.0;
+        assert!((avg - 0.5).abs() < 0.02, "{}", avg);
+        let h = noise.fractal2(0.5, 0.5, 4);
+        assert!((0.0..1.0).contains(&h));
+    }
+
+    #[test]
+    fn test_extreme_coordinates() {
+        let noise = Noise::new(&SplittingRng::<Xoshiro256StarStar>::new(12345));
+        for x in [i64::MAX as f64, 1e300, f64::MAX, -1e300] {
+            assert!((0.0..1.0).contains(&noise.value1(x)));
+            assert!((0.0..1.0).contains(&noise.value2(x, x)));
+            assert!((0.0..1.0).contains(&noise.value3(x, x, x)));
+        }
+        assert!((0.0..1.0).contains(&noise.fractal2(1e300, 0.5, 3)));
+    }
+}
@@ -0,0 +1,199 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Splitting an integer total into random parts.
+//!
+//! The parts are ordered, so `[1, 2]` and `[2, 1]` are different
+//! ways to split 3, and each way is equally likely. This suits
+//! handing out stat points or sharding a workload.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The most counts `get_composition_within` will keep,
+/// one for each part and partial total
+pub const MAX_COMPOSITION_COUNTS: u128 = 1 << 24;
+
+/// The reasons a bounded composition may not be made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositionError {
+    /// No parts within the bounds add up to the total
+    Impossible,
+    /// Counting the ways would keep more than `MAX_COMPOSITION_COUNTS`
+    TooLarge,
+}
+
+impl fmt::Display for CompositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompositionError::Impossible => write!(f, "no parts within the bounds make the total"),
+            CompositionError::TooLarge => write!(f, "too many parts and totals to count the ways"),
+        }
+    }
+}
+
+impl std::error::Error for CompositionError {}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Split `total` into `parts` non-negative parts, uniformly
+    /// over every way to do so, or `None` if there are no parts
+    /// to put a non-zero total in
+    ///
+    /// Uses stars and bars: the bars between parts are placed
+    /// among the `total + parts - 1` slots with Floyd's algorithm,
+    /// so it costs `parts - 1` rolls however large the total is.
+    pub fn get_composition(&mut self, total: u64, parts: usize) -> Option<Vec<u64>> {
+        if parts == 0 {
+            return if total == 0 { Some(Vec::new()) } else { None };
+        }
+        let bars = (parts - 1) as u128;
+        let slots = total as u128 + bars;
+        let mut chosen = BTreeSet::new();
+        for j in slots - bars..slots {
+            let t = self.below_u128(j + 1);
+            if !chosen.insert(t) {
+                chosen.insert(j);
+            }
+        }
+        let mut result = Vec::with_capacity(parts);
+        let mut last = 0;
+        for bar in chosen.into_iter().chain(std::iter::once(slots)) {
+            // The slots before a bar, less the earlier bars, are its stars
+            result.push((bar - last) as u64);
+            last = bar + 1;
+        }
+        Some(result)
+    }
+
+    /// Split `total` into `parts` parts each from `min` to `max`,
+    /// uniformly over every way to do so
+    ///
+    /// When `max` constrains the parts this counts the ways to finish
+    /// from each partial total, which takes time and memory in
+    /// proportion to `parts` times `total`, so it fails with
+    /// `TooLarge` rather than keep more than `MAX_COMPOSITION_COUNTS`.
+    /// The counts are kept as floats, so the choice is uniform up to
+    /// rounding.
+    pub fn get_composition_within(
+        &mut self,
+        total: u64,
+        parts: usize,
+        min: u64,
+        max: u64,
+    ) -> Result<Vec<u64>, CompositionError> {
+        let floor = min as u128 * parts as u128;
+        if min > max || floor > total as u128 || (max as u128 * parts as u128) < total as u128 {
+            return Err(CompositionError::Impossible);
+        }
+        let rest = total - floor as u64;
+        let width = max - min;
+        if width >= rest {
+            let mut result = self
+                .get_composition(rest, parts)
+                .ok_or(CompositionError::Impossible)?;
+            result.iter_mut().for_each(|part| *part += min);
+            return Ok(result);
+        }
+        if (parts as u128 + 1) * (rest as u128 + 1) > MAX_COMPOSITION_COUNTS {
+            return Err(CompositionError::TooLarge);
+        }
+        let rest = rest as usize;
+        let width = width as usize;
+        // ways[j][n] is the number of ways to make n from j parts
+        let mut ways = vec![vec![0.0f64; rest + 1]; parts + 1];
+        ways[0][0] = 1.0;
+        for j in 1..=parts {
+            let mut window = 0.0;
+            for n in 0..=rest {
+                window += ways[j - 1][n];
+                if n > width {
+                    window -= ways[j - 1][n - width - 1];
+                }
+                ways[j][n] = window;
+            }
+        }
+        let mut result = Vec::with_capacity(parts);
+        let mut left = rest;
+        for j in (1..=parts).rev() {
+            let mut target = self.get_f64() * ways[j][left];
+            let mut part = 0;
+            for x in 0..=width.min(left) {
+                let count = ways[j - 1][left - x];
+                if count > 0.0 {
+                    // Rounding can only leave the last possible part
+                    part = x;
+                    if target < count {
+                        break;
+                    }
+                    target -= count;
+                }
+            }
+            result.push(part as u64 + min);
+            left -= part;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_uniform_compositions() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        // There are 10 ways to split 3 into 3 parts
+        let mut counts = HashMap::new();
+        for _ in 0..10000 {
+            let parts = rng.get_composition(3, 3).unwrap();
+            assert_eq!(parts.iter().sum::<u64>(), 3);
+            *counts.entry(parts).or_insert(0i32) += 1;
+        }
+        assert_eq!(counts.len(), 10);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 120),
+            "{:?}",
+            counts
+        );
+        // With parts of 1 or 2, 5 into 3 parts is 2 + 2 + 1 in any order
+        let mut counts = HashMap::new();
+        for _ in 0..3000 {
+            let parts = rng.get_composition_within(5, 3, 1, 2).unwrap();
+            *counts.entry(parts).or_insert(0i32) += 1;
+        }
+        assert_eq!(counts.len(), 3);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 120),
+            "{:?}",
+            counts
+        );
+        let stats = rng.get_composition_within(100, 6, 5, 30).unwrap();
+        assert_eq!(stats.iter().sum::<u64>(), 100);
+        assert!(stats.iter().all(|s| (5..=30).contains(s)));
+        assert_eq!(
+            rng.get_composition_within(10, 2, 6, 9),
+            Err(CompositionError::Impossible)
+        );
+        assert_eq!(rng.get_composition(1, 0), None);
+        assert_eq!(rng.get_composition(5, 1), Some(vec![5]));
+    }
+
+    #[test]
+    fn test_large_bounded_compositions() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(
+            rng.get_composition_within(u64::MAX / 2, 1 << 20, 0, 1 << 50),
+            Err(CompositionError::TooLarge)
+        );
+        // Wide enough bounds don't need the counts
+        let parts = rng
+            .get_composition_within(u64::MAX / 2, 4, 0, u64::MAX)
+            .unwrap();
+        assert_eq!(parts.iter().sum::<u64>(), u64::MAX / 2);
+    }
+}
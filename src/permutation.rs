@@ -0,0 +1,186 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Permutations of indices which need no table.
+//!
+//! A `RandomPermutation` is a small Feistel network over
+//! the bits of an index, so it maps `0..len` onto itself in
+//! a scrambled order with only a few keys in memory. Indices
+//! which land past `len` are fed through again until they
+//! fall inside, which is known as cycle walking.
+//!
+//! A `Ranker` gives a random sort key for any `u64`, so
+//! callers can put their own structures in a random order.
+
+use crate::keyed::mix_word;
+use crate::{batched_key, SplittingRng};
+use rand::{RngCore, SeedableRng};
+
+const ROUNDS: usize = 4;
+
+/// A bijection on `0..len`, with its inverse
+///
+/// This is one of a keyed family of permutations, not a
+/// uniform pick from all of them, much as a block cipher is.
+/// It is well mixed enough to scramble the order of a dataset,
+/// but use `shuffle` when exact uniformity matters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RandomPermutation {
+    len: u64,
+    half_bits: u32,
+    keys: [u64; ROUNDS],
+}
+
+impl RandomPermutation {
+    /// A random permutation of `0..len`
+    pub fn new<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>, len: u64) -> Self {
+        let bits = match len {
+            0..=1 => 2,
+            _ => 64 - (len - 1).leading_zeros(),
+        };
+        let mut keys = [0; ROUNDS];
+        rng.fill_u64(&mut keys);
+        RandomPermutation {
+            len,
+            // The halves must be equal, so round up to even
+            half_bits: bits.div_ceil(2),
+            keys,
+        }
+    }
+
+    /// The number of indices permuted
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether there are no indices
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Where index `i` is sent
+    ///
+    /// Panics if `i` is not below the length
+    pub fn apply(&self, i: u64) -> u64 {
+        assert!(i < self.len, "Index out of range of the permutation");
+        let mut x = self.encrypt(i);
+        while x >= self.len {
+            x = self.encrypt(x);
+        }
+        x
+    }
+
+    /// Which index is sent to `i`, undoing `apply`
+    ///
+    /// Panics if `i` is not below the length
+    pub fn inverse(&self, i: u64) -> u64 {
+        assert!(i < self.len, "Index out of range of the permutation");
+        let mut x = self.decrypt(i);
+        while x >= self.len {
+            x = self.decrypt(x);
+        }
+        x
+    }
+
+    /// Iterate over `apply(0)`, `apply(1)`, and so on
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(move |i| self.apply(i))
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.half_bits)
+    }
+
+    fn encrypt(&self, x: u64) -> u64 {
+        let mask = self.mask();
+        let (mut left, mut right) = (x >> self.half_bits, x & mask);
+        for key in self.keys {
+            (left, right) = (right, left ^ (mix_word(key, right) & mask));
+        }
+        (left << self.half_bits) | right
+    }
+
+    fn decrypt(&self, x: u64) -> u64 {
+        let mask = self.mask();
+        let (mut left, mut right) = (x >> self.half_bits, x & mask);
+        for key in self.keys.iter().rev() {
+            (left, right) = (right ^ (mix_word(*key, left) & mask), left);
+        }
+        (left << self.half_bits) | right
+    }
+}
+
+/// Random but reproducible sort keys, as the batched shuffle uses
+///
+/// Each key gets a rank which depends only on the key and the
+/// two steps the ranker was made from, so a scheduler can break
+/// ties with `sort_by_key(|job| ranker.rank(job.id))` and get
+/// the same order whenever it is asked again. Ranks are a
+/// bijection of the keys, so they never tie themselves. Sorting
+/// `0..len` by rank gives the order `ShuffleAlgorithm::BatchedHash`
+/// would from the same steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ranker {
+    key: u64,
+    salt: u64,
+}
+
+impl Ranker {
+    /// A ranker, from two steps of the rng
+    pub fn new<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        let key = rng.get_u64();
+        Ranker {
+            key,
+            salt: rng.get_u64(),
+        }
+    }
+
+    /// The rank of a key
+    pub fn rank(&self, key: u64) -> u64 {
+        batched_key(key, self.key, self.salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_permutation_round_trips() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for len in [1, 2, 3, 10, 1000, 4097] {
+            let perm = RandomPermutation::new(&mut rng, len);
+            let mut seen: Vec<u64> = perm.iter().collect();
+            for (i, x) in seen.iter().enumerate() {
+                assert_eq!(perm.inverse(*x), i as u64);
+            }
+            seen.sort_unstable();
+            assert_eq!(seen, (0..len).collect::<Vec<_>>());
+        }
+        let perm = RandomPermutation::new(&mut rng, 1000);
+        let fixed = perm
+            .iter()
+            .enumerate()
+            .filter(|(i, x)| *i as u64 == *x)
+            .count();
+        assert!(fixed < 10, "{}", fixed);
+        let huge = RandomPermutation::new(&mut rng, u64::MAX);
+        assert_eq!(huge.inverse(huge.apply(123)), 123);
+    }
+
+    #[test]
+    fn test_ranker_matches_shuffle() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng
+            .clone()
+            .with_shuffle_algorithm(crate::ShuffleAlgorithm::BatchedHash);
+        let ranker = Ranker::new(&mut rng);
+        let mut order: Vec<u64> = (0..100).collect();
+        order.sort_by_key(|i| ranker.rank(*i));
+        assert_eq!(order, copy.shuffle(&(0..100).collect::<Vec<u64>>()));
+        assert_eq!(rng.steps(), copy.steps());
+        assert_eq!(ranker.rank(7), ranker.rank(7));
+    }
+}
@@ -0,0 +1,174 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Pseudo-random distribution, where the chance of a hit
+//! grows with each miss.
+//!
+//! The n-th roll after a hit succeeds with chance `c * n`, so
+//! long unlucky streaks are impossible. The constant `c` is
+//! chosen so hits still happen at the configured average rate.
+
+use crate::distributions::DistributionError;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// A chance which rises after every miss and resets on a hit
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::pity::PityChance;
+/// use splittable_random::SplittingRng;
+///
+/// let mut crit = PityChance::new(0.25).unwrap();
+/// assert!((crit.constant() - 0.0847).abs() < 1e-4);
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let hits = (0..1000).filter(|_| crit.roll(&mut rng)).count();
+/// assert!((200..300).contains(&hits));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PityChance {
+    constant: f64,
+    misses: u64,
+}
+
+impl PityChance {
+    /// Create a chance which hits at the average rate `p`
+    /// Requires `p` to be within [0, 1]
+    pub fn new(p: f64) -> Result<Self, DistributionError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(DistributionError::BadProbability);
+        }
+        Ok(PityChance {
+            constant: prd_constant(p),
+            misses: 0,
+        })
+    }
+
+    /// Roll once, which is one step
+    pub fn roll<T: RngCore + SeedableRng>(&mut self, rng: &mut SplittingRng<T>) -> bool {
+        let hit = rng.get_f64() < self.chance();
+        self.misses = if hit { 0 } else { self.misses + 1 };
+        hit
+    }
+
+    /// The chance the next roll hits
+    pub fn chance(&self) -> f64 {
+        (self.constant * (self.misses + 1) as f64).min(1.0)
+    }
+
+    /// The constant the chance grows by after each miss
+    pub fn constant(&self) -> f64 {
+        self.constant
+    }
+
+    /// The misses since the last hit
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Continue from a saved number of misses
+    pub fn with_misses(mut self, misses: u64) -> Self {
+        self.misses = misses;
+        self
+    }
+
+    /// Forget any misses, as after a hit
+    pub fn reset(&mut self) {
+        self.misses = 0;
+    }
+}
+
+/// Below this constant the rate comes from the limit of the sum
+const SMALL_CONSTANT: f64 = 1e-8;
+
+/// The average rate of hits for a constant
+///
+/// The chance of reaching the n-th roll falls like
+/// `exp(-c n^2 / 2)`, so the sum stops once that chance is
+/// negligible, within about `7.5 / sqrt(c)` rolls. For tiny
+/// constants the sum is `sqrt(pi / 2c) - 1/3` to well within
+/// the precision of the bisection.
+fn prd_rate(constant: f64) -> f64 {
+    if constant < SMALL_CONSTANT {
+        let expected = (std::f64::consts::PI / (2.0 * constant)).sqrt() - 1.0 / 3.0;
+        return 1.0 / expected;
+    }
+    let mut expected = 0.0;
+    let mut reached = 1.0;
+    let mut n = 1.0;
+    while reached > 1e-12 {
+        let chance = (constant * n).min(1.0);
+        expected += n * reached * chance;
+        reached *= 1.0 - chance;
+        n += 1.0;
+    }
+    1.0 / expected
+}
+
+/// The constant which hits at the average rate `p`,
+/// found by bisection as the rate grows with the constant
+///
+/// The constant is near `p^2` for small `p`, so the halving
+/// goes on until the bounds meet rather than for fixed rounds.
+fn prd_constant(p: f64) -> f64 {
+    if p == 0.0 || p == 1.0 {
+        return p;
+    }
+    let (mut low, mut high) = (0.0, p);
+    loop {
+        let mid = (low + high) / 2.0;
+        if mid <= low || mid >= high {
+            break;
+        }
+        if prd_rate(mid) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_pity_rate_and_streaks() {
+        // The published constant for a 25% chance
+        let mut pity = PityChance::new(0.25).unwrap();
+        assert!((pity.constant() - 0.084744).abs() < 1e-5);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 40000;
+        let mut hits = 0;
+        let mut longest = 0;
+        for _ in 0..iter {
+            if pity.roll(&mut rng) {
+                hits += 1;
+            }
+            longest = longest.max(pity.misses());
+        }
+        let frac = hits as f64 / iter as f64;
+        assert!((frac - 0.25).abs() < 0.01, "{}", frac);
+        // The chance reaches 1 after ceil(1 / c) - 1 misses
+        assert!(longest <= 11, "{}", longest);
+        assert_eq!(pity.with_misses(100).chance(), 1.0);
+        assert!(PityChance::new(-0.1).is_err());
+        assert!(!PityChance::new(0.0).unwrap().roll(&mut rng));
+    }
+
+    #[test]
+    fn test_small_rates_are_quick() {
+        let start = std::time::Instant::now();
+        for &p in &[1e-3, 1e-4, 1e-6, 1e-9, 1e-15] {
+            let pity = PityChance::new(p).unwrap();
+            let rate = prd_rate(pity.constant());
+            assert!((rate - p).abs() < p * 1e-6, "{} {}", p, rate);
+        }
+        assert!(PityChance::new(1e-300).is_ok());
+        // Each of these used to take seconds, or never finish
+        assert!(start.elapsed().as_secs() < 2, "{:?}", start.elapsed());
+    }
+}
@@ -0,0 +1,295 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Serving a recorded trace back through the core methods.
+//!
+//! When two builds desync, record a trace from the first and
+//! play it back in the second. Every call returns what the first
+//! build got, and the first call which differs in method panics
+//! with both sides, pointing straight at the desync rather than
+//! at its symptoms much later.
+
+use crate::trace::{Method, TraceEvent};
+use crate::{ShuffleAlgorithm, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::hash::BuildHasher;
+
+/// Replays a recorded trace, checking each call against it
+///
+/// Traces hold each value, except that shuffles record only
+/// the length of the list and the algorithm, and splits the
+/// origin of the child.
+/// Those are rebuilt from an rng in the state the recording
+/// started from, which takes the same number of steps.
+#[derive(Debug, Clone)]
+pub struct Playback<T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    events: Vec<TraceEvent>,
+    pos: usize,
+}
+
+impl<T: RngCore + SeedableRng> Playback<T> {
+    /// Play back events recorded from `rng`, given as it
+    /// was when tracing started
+    pub fn new(rng: SplittingRng<T>, events: Vec<TraceEvent>) -> Self {
+        Playback {
+            rng,
+            events,
+            pos: 0,
+        }
+    }
+
+    /// How many events have been played back
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// How many events are left
+    pub fn remaining(&self) -> usize {
+        self.events.len() - self.pos
+    }
+
+    /// Whether every event has been played back
+    pub fn is_done(&self) -> bool {
+        self.pos == self.events.len()
+    }
+
+    fn next(&mut self, method: Method) -> TraceEvent {
+        let event = match self.events.get(self.pos) {
+            Some(event) => *event,
+            None => panic!(
+                "playback call {} was to {}, but the recording had ended",
+                self.pos, method
+            ),
+        };
+        if event.method != method {
+            panic!(
+                "playback call {} was to {}, but the recording had {}",
+                self.pos, method, event
+            );
+        }
+        self.pos += 1;
+        event
+    }
+
+    /// Provide the recorded bool
+    pub fn get_bool(&mut self) -> bool {
+        self.next(Method::GetBool).value != 0
+    }
+
+    /// Provide the recorded u32
+    pub fn get_u32(&mut self) -> u32 {
+        self.next(Method::GetU32).value as u32
+    }
+
+    /// Provide the recorded u64
+    pub fn get_u64(&mut self) -> u64 {
+        self.next(Method::GetU64).value
+    }
+
+    /// Provide the recorded float
+    pub fn get_f64(&mut self) -> f64 {
+        f64::from_bits(self.next(Method::GetF64).value)
+    }
+
+    /// Provide the recorded roll
+    /// Panics if it is not below `sides`, as the call must differ
+    pub fn biased_roll(&mut self, sides: u32) -> u32 {
+        self.recorded_roll(Method::BiasedRoll, sides)
+    }
+
+    /// Provide the recorded roll, as `biased_roll`
+    pub fn fair_roll(&mut self, sides: u32) -> u32 {
+        self.recorded_roll(Method::FairRoll, sides)
+    }
+
+    /// Provide the recorded roll, as `biased_roll`
+    pub fn roll(&mut self, sides: u32) -> u32 {
+        self.recorded_roll(Method::Roll, sides)
+    }
+
+    fn recorded_roll(&mut self, method: Method, sides: u32) -> u32 {
+        let event = self.next(method);
+        if event.value >= sides.max(1) as u64 {
+            panic!(
+                "playback call {} rolled {} sides, but the recording had {}",
+                self.pos - 1,
+                sides,
+                event
+            );
+        }
+        event.value as u32
+    }
+
+    /// Shuffle the list as the recording did, with the
+    /// algorithm it used
+    /// Panics if the recorded list had a different length, or
+    /// was shuffled by `shuffle_with_hasher`
+    pub fn shuffle<L: Copy>(&mut self, list: &[L]) -> Vec<L> {
+        let algorithm = self
+            .next_shuffle(list.len(), false)
+            .expect("Checked the shuffle had an algorithm");
+        self.rng.shuffle_with(list, algorithm)
+    }
+
+    /// Shuffle a slice in place as the recording did, which
+    /// may have used `shuffle` or `shuffle_slice`
+    pub fn shuffle_slice<L: Copy>(&mut self, list: &mut [L]) {
+        let order = self.shuffle(list);
+        list.copy_from_slice(&order);
+    }
+
+    /// Shuffle the list as `shuffle_with_hasher` did, given
+    /// the same hasher as the recording
+    /// Panics if the recording used another kind of shuffle
+    pub fn shuffle_with_hasher<L: Copy, B: BuildHasher>(
+        &mut self,
+        list: &[L],
+        build: &B,
+    ) -> Vec<L> {
+        self.next_shuffle(list.len(), true);
+        self.rng.shuffle_with_hasher(list, build)
+    }
+
+    /// Check the next shuffle, and seek to just before it
+    fn next_shuffle(&mut self, len: usize, hasher: bool) -> Option<ShuffleAlgorithm> {
+        let event = self.next(Method::Shuffle);
+        if event.shuffle_len() != Some(len as u64) {
+            panic!(
+                "playback call {} shuffled {} items, but the recording had {}",
+                self.pos - 1,
+                len,
+                event
+            );
+        }
+        let algorithm = event.shuffle_algorithm();
+        if event.is_hasher_shuffle() != hasher || (!hasher && algorithm.is_none()) {
+            panic!(
+                "playback call {} shuffled {}, but the recording had {}",
+                self.pos - 1,
+                if hasher {
+                    "with a hasher"
+                } else {
+                    "by algorithm"
+                },
+                event
+            );
+        }
+        // A shuffle takes two steps, ending at the recorded step
+        self.rng.seek(event.step - 2);
+        algorithm
+    }
+
+    /// Provide the child the recording split off
+    pub fn split(&mut self) -> SplittingRng<T> {
+        let origin = self.next(Method::Split).value;
+        self.rng.child_at(origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fnv::FnvHasher;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::hash::BuildHasherDefault;
+    use std::panic::AssertUnwindSafe;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_playback_matches_recording() {
+        let start = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut rng = start.clone();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        rng.trace_to(move |e| sink.lock().unwrap().push(e));
+        let roll = rng.fair_roll(20);
+        let d6 = rng.roll(6);
+        let order = rng.shuffle(&[1, 2, 3, 4, 5]);
+        let x = rng.get_f64();
+        let child = rng.split().get_u64();
+        rng.stop_trace();
+        let events = events.lock().unwrap().clone();
+
+        let mut playback = Playback::new(start, events);
+        assert_eq!(playback.fair_roll(20), roll);
+        assert_eq!(playback.roll(6), d6);
+        assert_eq!(playback.shuffle(&[1, 2, 3, 4, 5]), order);
+        assert_eq!(playback.get_f64(), x);
+        assert_eq!(playback.split().get_u64(), child);
+        assert!(playback.is_done());
+        let mut desync = Playback::new(
+            rng.clone(),
+            vec![TraceEvent {
+                step: 1,
+                method: Method::GetU64,
+                value: 3,
+            }],
+        );
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| desync.get_bool()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_playback_shuffle_algorithms() {
+        let list: Vec<u32> = (0..20).collect();
+        for algorithm in [
+            ShuffleAlgorithm::HashSort,
+            ShuffleAlgorithm::FisherYates,
+            ShuffleAlgorithm::BatchedHash,
+        ] {
+            let start = SplittingRng::<Xoshiro256StarStar>::new(12345);
+            let mut rng = start.clone();
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = events.clone();
+            rng.trace_to(move |e| sink.lock().unwrap().push(e));
+            let order = rng.shuffle_with(&list, algorithm);
+            rng.stop_trace();
+            let events = events.lock().unwrap().clone();
+            assert_eq!(events[0].shuffle_len(), Some(20));
+            assert_eq!(events[0].shuffle_algorithm(), Some(algorithm));
+
+            let mut playback = Playback::new(start, events);
+            assert_eq!(playback.shuffle(&list), order, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_playback_slices_and_hashers() {
+        let start = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut rng = start.clone();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        rng.trace_to(move |e| sink.lock().unwrap().push(e));
+        let mut slice = [1, 2, 3, 4, 5, 6, 7, 8];
+        rng.shuffle_slice(&mut slice);
+        let mut array = [10, 20, 30, 40];
+        rng.shuffle_array(&mut array);
+        let build = BuildHasherDefault::<FnvHasher>::default();
+        let hashed = rng.shuffle_with_hasher(&[1, 2, 3, 4, 5], &build);
+        rng.stop_trace();
+        let events = events.lock().unwrap().clone();
+        assert_eq!(
+            events[0].shuffle_algorithm(),
+            Some(ShuffleAlgorithm::FisherYates)
+        );
+        assert!(events[2].is_hasher_shuffle());
+        assert_eq!(events[2].shuffle_algorithm(), None);
+
+        let mut playback = Playback::new(start.clone(), events.clone());
+        let mut replayed = [1, 2, 3, 4, 5, 6, 7, 8];
+        playback.shuffle_slice(&mut replayed);
+        assert_eq!(replayed, slice);
+        assert_eq!(playback.shuffle(&[10, 20, 30, 40]), array);
+        assert_eq!(
+            playback.shuffle_with_hasher(&[1, 2, 3, 4, 5], &build),
+            hashed
+        );
+        assert!(playback.is_done());
+
+        let mut wrong = Playback::new(start, events[2..].to_vec());
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| wrong.shuffle(&[1, 2, 3, 4, 5])));
+        assert!(result.is_err());
+    }
+}
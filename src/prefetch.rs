@@ -0,0 +1,142 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Pulling backend outputs in batches.
+//!
+//! With prefetching on, each refill draws a whole buffer
+//! of words from the backend in one tight loop, and steps
+//! are served from the buffer. The values are the same
+//! either way, and `steps` still counts the words used,
+//! so saved states and seeking are unaffected.
+
+use crate::{Finalizer, SplittingRng};
+use rand::{RngCore, SeedableRng};
+
+#[derive(Clone)]
+pub(crate) struct Prefetch {
+    words: Vec<u64>,
+    pos: usize,
+    size: usize,
+}
+
+impl Prefetch {
+    pub(crate) fn next<T: RngCore>(&mut self, prng: &mut T, finalizer: Finalizer) -> u64 {
+        // Once stopped, the last buffer drains and is not refilled
+        if self.pos == self.words.len() && self.size == 0 {
+            return finalizer.apply(prng.next_u64());
+        }
+        if self.pos == self.words.len() {
+            self.words.resize(self.size, 0);
+            for word in self.words.iter_mut() {
+                *word = finalizer.apply(prng.next_u64());
+            }
+            self.pos = 0;
+        }
+        self.pos += 1;
+        self.words[self.pos - 1]
+    }
+
+    /// The number of words buffered but not yet used
+    pub(crate) fn buffered(&self) -> usize {
+        self.words.len() - self.pos
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Pass over `count` words, using up the buffer first and
+    /// then stepping the backend directly
+    pub(crate) fn skip<T: RngCore>(&mut self, prng: &mut T, count: u64) {
+        let buffered = (self.buffered() as u64).min(count);
+        self.pos += buffered as usize;
+        for _ in buffered..count {
+            prng.next_u64();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.words.clear();
+        self.pos = 0;
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Pull `size` words from the backend at a time from now on,
+    /// or stop prefetching with a size of zero
+    ///
+    /// Children don't inherit prefetching, as most are short lived.
+    /// Stopping still serves any words already buffered before
+    /// stepping the backend directly, so the values carry on
+    /// unchanged, as they would had prefetching never started.
+    pub fn prefetch(&mut self, size: usize) {
+        match (self.prefetch.as_mut(), size) {
+            (Some(prefetch), 0) if prefetch.buffered() > 0 => prefetch.size = 0,
+            (Some(_), 0) => self.prefetch = None,
+            // Resizing keeps the words already buffered
+            (Some(prefetch), _) => prefetch.size = size,
+            (None, 0) => {}
+            (None, _) => {
+                self.prefetch = Some(Prefetch {
+                    words: Vec::with_capacity(size),
+                    pos: 0,
+                    size,
+                })
+            }
+        }
+    }
+
+    /// How many words are pulled at a time, or zero when
+    /// prefetching is off
+    pub fn prefetch_size(&self) -> usize {
+        self.prefetch.as_ref().map_or(0, |p| p.size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_prefetch() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.prefetch(16);
+        for _ in 0..40 {
+            assert_eq!(rng.fair_roll(6), copy.fair_roll(6));
+        }
+        let mut words = [0; 5];
+        rng.fill_u64(&mut words);
+        assert_eq!(words.to_vec(), copy.get_u64s(5));
+        // Loaded states carry on prefetching from the same place
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::from_state(rng.to_state());
+        assert_eq!(loaded.prefetch_size(), 16);
+        assert_eq!(loaded.get_u64(), rng.get_u64());
+        // Stopping drains the buffer rather than skipping it
+        copy.get_u64();
+        rng.prefetch(0);
+        assert_eq!(rng.prefetch_size(), 0);
+        assert_eq!(rng.steps(), copy.steps());
+        assert_eq!(rng.get_u64s(40), copy.get_u64s(40));
+    }
+
+    #[test]
+    fn test_stopping_keeps_the_stream() {
+        let mut plain = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for size in [16, 0, 5, 0, 0, 3] {
+            rng.prefetch(size);
+            for _ in 0..7 {
+                assert_eq!(rng.get_u64(), plain.get_u64());
+            }
+            assert_eq!(rng.steps(), plain.steps());
+        }
+        let mut loaded = SplittingRng::<Xoshiro256StarStar>::from_state(rng.to_state());
+        let expected = plain.get_u64s(20);
+        assert_eq!(loaded.get_u64s(20), expected);
+        rng.prefetch(0);
+        assert_eq!(rng.get_u64s(20), expected);
+    }
+}
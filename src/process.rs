@@ -0,0 +1,219 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random processes over time, for quick path simulations.
+//!
+//! Each process owns a child rng split from the one it was
+//! created with, so it can be stepped independently.
+
+use crate::distributions::{standard_normal, Distribution, DistributionError, Exponential};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Increment {
+    Gaussian,
+    Sign,
+}
+
+/// A walk whose increments are `drift + volatility * x`,
+/// where `x` is standard normal or ±1
+///
+/// Iterating gives the position after each increment. A
+/// Gaussian walk with drift `mu * dt` and volatility
+/// `sigma * dt.sqrt()` samples Brownian motion every `dt`.
+pub struct RandomWalk<T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    position: f64,
+    drift: f64,
+    volatility: f64,
+    increment: Increment,
+}
+
+impl<T: RngCore + SeedableRng> RandomWalk<T> {
+    /// A walk with normally distributed increments
+    pub fn gaussian(
+        rng: &mut SplittingRng<T>,
+        start: f64,
+        drift: f64,
+        volatility: f64,
+    ) -> Result<Self, DistributionError> {
+        Self::new(rng, start, drift, volatility, Increment::Gaussian)
+    }
+
+    /// A walk with increments of ±1, scaled by the volatility
+    pub fn simple(
+        rng: &mut SplittingRng<T>,
+        start: f64,
+        drift: f64,
+        volatility: f64,
+    ) -> Result<Self, DistributionError> {
+        Self::new(rng, start, drift, volatility, Increment::Sign)
+    }
+
+    fn new(
+        rng: &mut SplittingRng<T>,
+        start: f64,
+        drift: f64,
+        volatility: f64,
+        increment: Increment,
+    ) -> Result<Self, DistributionError> {
+        if ![start, drift, volatility].iter().all(|v| v.is_finite()) {
+            return Err(DistributionError::NonFinite);
+        }
+        Ok(RandomWalk {
+            rng: rng.split(),
+            position: start,
+            drift,
+            volatility,
+            increment,
+        })
+    }
+
+    /// The current position
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+}
+
+impl<T: RngCore + SeedableRng> Iterator for RandomWalk<T> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let x = match self.increment {
+            Increment::Gaussian => standard_normal(&mut self.rng),
+            Increment::Sign => self.rng.get_sign() as f64,
+        };
+        self.position += self.drift + self.volatility * x;
+        Some(self.position)
+    }
+}
+
+/// The arrival times of a Poisson process, starting from zero
+///
+/// A varying rate is simulated by thinning: arrivals are drawn
+/// at the maximum rate, and each is kept with a chance of the
+/// rate at that time over the maximum.
+pub struct PoissonProcess<T: RngCore + SeedableRng, F = fn(f64) -> f64> {
+    rng: SplittingRng<T>,
+    time: f64,
+    gaps: Exponential,
+    max_rate: f64,
+    rate: Option<F>,
+}
+
+impl<T: RngCore + SeedableRng> PoissonProcess<T> {
+    /// A process with a constant rate of events per unit time
+    pub fn new(rng: &mut SplittingRng<T>, rate: f64) -> Result<Self, DistributionError> {
+        Ok(PoissonProcess {
+            rng: rng.split(),
+            time: 0.0,
+            gaps: Exponential::new(rate)?,
+            max_rate: rate,
+            rate: None,
+        })
+    }
+}
+
+impl<T: RngCore + SeedableRng, F: Fn(f64) -> f64> PoissonProcess<T, F> {
+    /// A process whose rate at time `t` is `rate(t)`, which
+    /// must never exceed `max_rate`
+    ///
+    /// Rates above the maximum are treated as the maximum,
+    /// and the closer the maximum, the fewer draws are wasted.
+    /// A rate which stays at zero forever makes `next` never return.
+    pub fn varying(
+        rng: &mut SplittingRng<T>,
+        max_rate: f64,
+        rate: F,
+    ) -> Result<Self, DistributionError> {
+        Ok(PoissonProcess {
+            rng: rng.split(),
+            time: 0.0,
+            gaps: Exponential::new(max_rate)?,
+            max_rate,
+            rate: Some(rate),
+        })
+    }
+
+    /// The time of the latest arrival
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+impl<T: RngCore + SeedableRng, F: Fn(f64) -> f64> Iterator for PoissonProcess<T, F> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        loop {
+            self.time += self.gaps.sample(&mut self.rng);
+            match &self.rate {
+                None => return Some(self.time),
+                Some(rate) => {
+                    if self.rng.get_f64() * self.max_rate < rate(self.time) {
+                        return Some(self.time);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_random_walks() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let walks = 2000;
+        let mut ends = Vec::with_capacity(walks);
+        for _ in 0..walks {
+            let walk = RandomWalk::gaussian(&mut rng, 1.0, 0.1, 0.5).unwrap();
+            ends.push(walk.take(100).last().unwrap());
+        }
+        // After 100 steps, the mean is 1 + 100 * 0.1 and the
+        // variance is 100 * 0.5^2
+        let mean = ends.iter().sum::<f64>() / walks as f64;
+        let var = ends.iter().map(|e| (e - mean).powi(2)).sum::<f64>() / walks as f64;
+        assert!((mean - 11.0).abs() < 0.3, "{}", mean);
+        assert!((var - 25.0).abs() < 2.5, "{}", var);
+        let mut simple = RandomWalk::simple(&mut rng, 0.0, 0.0, 1.0).unwrap();
+        let first = simple.next().unwrap();
+        assert!(first == 1.0 || first == -1.0);
+        assert_eq!(simple.position(), first);
+        assert!(RandomWalk::gaussian(&mut rng, f64::NAN, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_poisson_arrivals() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let steady = PoissonProcess::new(&mut rng, 5.0).unwrap();
+        let count = steady.take_while(|t| *t < 1000.0).count();
+        assert!((count as f64 - 5000.0).abs() < 250.0, "{}", count);
+        // Events only happen in the second half of each unit of time
+        let bursts =
+            PoissonProcess::varying(
+                &mut rng,
+                10.0,
+                |t: f64| {
+                    if t.fract() < 0.5 {
+                        0.0
+                    } else {
+                        10.0
+                    }
+                },
+            )
+            .unwrap();
+        let times: Vec<f64> = bursts.take_while(|t| *t < 1000.0).collect();
+        assert!(times.iter().all(|t| t.fract() >= 0.5));
+        assert!(
+            (times.len() as f64 - 5000.0).abs() < 250.0,
+            "{}",
+            times.len()
+        );
+    }
+}
@@ -0,0 +1,141 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Quasi-random points for Monte Carlo integration.
+//!
+//! A Halton sequence covers the unit cube far more evenly
+//! than independent uniform points. Both implement
+//! `PointSource`, so an integrator can switch between them.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// Anything which produces points in the unit cube [0, 1)^d
+pub trait PointSource {
+    /// Fill `out` with the coordinates of the next point
+    fn next_point(&mut self, out: &mut [f64]);
+}
+
+/// Independent uniform points
+impl<T: RngCore + SeedableRng> PointSource for SplittingRng<T> {
+    fn next_point(&mut self, out: &mut [f64]) {
+        self.fill_f64_unit(out);
+    }
+}
+
+/// The Halton sequence, using the first `dims` primes as bases
+///
+/// Quality falls off beyond a few dozen dimensions, as the
+/// larger bases need many points to fill in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Halton {
+    bases: Vec<u64>,
+    shifts: Vec<f64>,
+    index: u64,
+}
+
+impl Halton {
+    /// The plain sequence, starting from its first point
+    pub fn new(dims: usize) -> Self {
+        Halton {
+            bases: first_primes(dims),
+            shifts: vec![0.0; dims],
+            index: 1,
+        }
+    }
+
+    /// A randomized sequence, shifting each coordinate by a
+    /// random amount modulo 1 (a Cranley-Patterson rotation)
+    ///
+    /// Every shift keeps the evenness of the sequence, while
+    /// independent shifts give independent estimates, so the
+    /// error of an integral can be measured.
+    pub fn scrambled<T: RngCore + SeedableRng>(dims: usize, rng: &mut SplittingRng<T>) -> Self {
+        let mut halton = Self::new(dims);
+        rng.fill_f64_unit(&mut halton.shifts);
+        halton
+    }
+
+    /// The number of dimensions of each point
+    pub fn dims(&self) -> usize {
+        self.bases.len()
+    }
+}
+
+impl PointSource for Halton {
+    /// Panics if `out` is longer than the dimensions of the sequence
+    fn next_point(&mut self, out: &mut [f64]) {
+        assert!(
+            out.len() <= self.dims(),
+            "More dimensions than the sequence has"
+        );
+        for ((x, base), shift) in out.iter_mut().zip(&self.bases).zip(&self.shifts) {
+            let value = radical_inverse(self.index, *base) + shift;
+            *x = if value >= 1.0 { value - 1.0 } else { value };
+        }
+        self.index += 1;
+    }
+}
+
+/// The digits of `index` in `base`, mirrored about the point
+fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let inv = 1.0 / base as f64;
+    let mut scale = inv;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inv;
+    }
+    result
+}
+
+fn first_primes(n: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes
+            .iter()
+            .take_while(|p| *p * *p <= candidate)
+            .all(|p| candidate % p != 0)
+        {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    fn quarter_circle_error<P: PointSource>(source: &mut P, n: usize) -> f64 {
+        let mut point = [0.0; 2];
+        let mut inside = 0;
+        for _ in 0..n {
+            source.next_point(&mut point);
+            if point[0] * point[0] + point[1] * point[1] < 1.0 {
+                inside += 1;
+            }
+        }
+        (4.0 * inside as f64 / n as f64 - std::f64::consts::PI).abs()
+    }
+
+    #[test]
+    fn test_halton_beats_uniform() {
+        let mut halton = Halton::new(2);
+        let mut point = [0.0; 2];
+        halton.next_point(&mut point);
+        assert_eq!(point, [0.5, 1.0 / 3.0]);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut scrambled = Halton::scrambled(2, &mut rng);
+        let quasi = quarter_circle_error(&mut scrambled, 4096);
+        assert!(quasi < 0.01, "{}", quasi);
+        // Uniform points err by about 1.6 / sqrt(n)
+        let uniform = quarter_circle_error(&mut rng, 4096);
+        assert!(quasi < uniform, "{} {}", quasi, uniform);
+    }
+}
@@ -0,0 +1,554 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random values of whole types, for test data.
+//!
+//! With the `derive` feature, `#[derive(Random)]`
+//! fills every field of a struct, or picks a variant
+//! of an enum uniformly and fills its fields, and
+//! `#[derive(Variants)]` lists a fieldless enum's variants.
+//!
+//! Newtypes over a bounded integer, such as a percentage,
+//! can implement `BoundedRandom` to be drawn uniformly within
+//! their bounds, as the `NonZero` integers are.
+//!
+//! `weighted_enum!` declares a fieldless enum with a weight
+//! for each variant, for spawn tables and the like.
+
+use crate::distributions::{DiscreteDistribution, Distribution, DistributionError};
+use crate::ranges::RollInt;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::marker::PhantomData;
+use std::num::{NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+
+/// The longest `Vec` or `String` that `Random` will produce
+pub const MAX_RANDOM_LEN: u32 = 16;
+
+/// A type which can produce a random value of itself
+pub trait Random: Sized {
+    /// Draw a value
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self;
+}
+
+macro_rules! random_int {
+    ($($t:ty),*) => {
+        $(impl Random for $t {
+            fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+                // Take the high bits, as the low bits are low entropy
+                (rng.get_u64() >> (64 - <$t>::BITS)) as $t
+            }
+        })*
+    };
+}
+
+random_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Random for u128 {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        ((rng.get_u64() as u128) << 64) | rng.get_u64() as u128
+    }
+}
+
+impl Random for i128 {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        u128::random(rng) as i128
+    }
+}
+
+impl Random for usize {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        // Always draw 64 bits, so the stream is the same on every platform
+        u64::random(rng) as usize
+    }
+}
+
+impl Random for isize {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        i64::random(rng) as isize
+    }
+}
+
+impl Random for bool {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        rng.get_bool()
+    }
+}
+
+/// Uniform in [0, 1)
+impl Random for f64 {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        rng.get_f64()
+    }
+}
+
+/// Uniform in [0, 1)
+impl Random for f32 {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        (rng.get_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+}
+
+/// Any Unicode scalar value
+impl Random for char {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        rng.get_char()
+    }
+}
+
+impl Random for () {
+    fn random<T: RngCore + SeedableRng>(_: &mut SplittingRng<T>) -> Self {}
+}
+
+/// `None` half of the time
+impl<V: Random> Random for Option<V> {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        if rng.get_bool() {
+            Some(V::random(rng))
+        } else {
+            None
+        }
+    }
+}
+
+/// Up to `MAX_RANDOM_LEN` items
+impl<V: Random> Random for Vec<V> {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        let len = rng.fair_roll(MAX_RANDOM_LEN + 1);
+        (0..len).map(|_| V::random(rng)).collect()
+    }
+}
+
+/// Up to `MAX_RANDOM_LEN` alphanumeric characters
+impl Random for String {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        let len = rng.fair_roll(MAX_RANDOM_LEN + 1);
+        rng.get_alphanumeric(len as usize)
+    }
+}
+
+impl<V: Random, const N: usize> Random for [V; N] {
+    fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+        std::array::from_fn(|_| V::random(rng))
+    }
+}
+
+macro_rules! random_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Random),*> Random for ($($name,)*) {
+            fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+                // Fields are drawn left to right
+                ($($name::random(rng),)*)
+            }
+        }
+    };
+}
+
+random_tuple!(A);
+random_tuple!(A, B);
+random_tuple!(A, B, C);
+random_tuple!(A, B, C, D);
+random_tuple!(A, B, C, D, E);
+random_tuple!(A, B, C, D, E, F);
+
+/// A fieldless enum, or any other type with a
+/// short fixed list of values
+///
+/// ```
+/// use splittable_random::Variants;
+///
+/// #[derive(Clone, Copy)]
+/// enum Direction {
+///     North,
+///     East,
+///     South,
+///     West,
+/// }
+///
+/// impl Variants for Direction {
+///     const ALL: &'static [Self] = &[
+///         Direction::North,
+///         Direction::East,
+///         Direction::South,
+///         Direction::West,
+///     ];
+/// }
+/// ```
+pub trait Variants: Copy + 'static {
+    /// Every value, in a fixed order
+    const ALL: &'static [Self];
+}
+
+/// A type holding any integer from `MIN` to `MAX`
+/// inclusive, and nothing else
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::{BoundedRandom, SplittingRng};
+///
+/// struct Percent(u8);
+///
+/// impl BoundedRandom for Percent {
+///     type Repr = u8;
+///     const MIN: u8 = 0;
+///     const MAX: u8 = 100;
+///
+///     fn from_repr(value: u8) -> Self {
+///         Percent(value)
+///     }
+/// }
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let Percent(p) = rng.get_bounded();
+/// assert!(p <= 100);
+/// ```
+pub trait BoundedRandom: Sized {
+    /// The integer type within the bounds
+    type Repr: RollInt;
+    /// The smallest value
+    const MIN: Self::Repr;
+    /// The largest value
+    const MAX: Self::Repr;
+
+    /// Wrap a value which is known to be within the bounds
+    fn from_repr(value: Self::Repr) -> Self;
+}
+
+macro_rules! bounded_non_zero {
+    ($($t:ty => $u:ty),*) => {
+        $(impl BoundedRandom for $t {
+            type Repr = $u;
+            const MIN: $u = 1;
+            const MAX: $u = <$u>::MAX;
+
+            fn from_repr(value: $u) -> Self {
+                <$t>::new(value).expect("Rolled zero within nonzero bounds")
+            }
+        }
+
+        /// Uniform over every nonzero value, from a single fair roll
+        impl Random for $t {
+            fn random<T: RngCore + SeedableRng>(rng: &mut SplittingRng<T>) -> Self {
+                rng.get_bounded()
+            }
+        })*
+    };
+}
+
+bounded_non_zero!(
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32,
+    NonZeroU64 => u64,
+    NonZeroU128 => u128,
+    NonZeroUsize => usize
+);
+
+/// A type with a fixed list of values, each with a weight,
+/// usually declared with `weighted_enum!`
+pub trait WeightedVariants: Variants {
+    /// The weight of each value in `ALL`, in the same order
+    const WEIGHTS: &'static [f64];
+}
+
+/// Declare a fieldless enum with a weight for each variant
+///
+/// The enum gets `Clone`, `Copy`, `Variants` and
+/// `WeightedVariants`, so the weights are kept next to the
+/// variants they belong to. Weights are `f64` expressions.
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::random::VariantTable;
+/// use splittable_random::{weighted_enum, SplittingRng};
+///
+/// weighted_enum! {
+///     #[derive(Debug, PartialEq)]
+///     pub enum Spawn {
+///         Goblin = 10.0,
+///         Orc = 3.0,
+///         Dragon = 0.5,
+///     }
+/// }
+///
+/// let table = VariantTable::<Spawn>::new().unwrap();
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let spawn: Spawn = rng.sample(&table);
+/// assert!(table.probability(Spawn::Goblin) > 0.7);
+/// ```
+#[macro_export]
+macro_rules! weighted_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident = $weight:expr),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy)]
+        $vis enum $name {
+            $($(#[$vmeta])* $variant),+
+        }
+
+        impl $crate::Variants for $name {
+            const ALL: &'static [Self] = &[$($name::$variant),+];
+        }
+
+        impl $crate::random::WeightedVariants for $name {
+            const WEIGHTS: &'static [f64] = &[$($weight),+];
+        }
+    };
+}
+
+/// The weights of a `WeightedVariants` type, checked and
+/// made cumulative once, to pick from in a single step
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantTable<V> {
+    dist: DiscreteDistribution,
+    variants: PhantomData<V>,
+}
+
+impl<V: WeightedVariants> VariantTable<V> {
+    /// Check the weights of a type
+    ///
+    /// Panics if there are not as many weights as values
+    pub fn new() -> Result<Self, DistributionError> {
+        assert_eq!(
+            V::ALL.len(),
+            V::WEIGHTS.len(),
+            "Each variant needs a weight"
+        );
+        Ok(VariantTable {
+            dist: DiscreteDistribution::new(V::WEIGHTS)?,
+            variants: PhantomData,
+        })
+    }
+}
+
+impl<V: WeightedVariants + PartialEq> VariantTable<V> {
+    /// The chance of picking a value
+    pub fn probability(&self, value: V) -> f64 {
+        V::ALL
+            .iter()
+            .position(|v| *v == value)
+            .map_or(0.0, |i| self.dist.probability(i))
+    }
+}
+
+impl<V: WeightedVariants> Distribution<V> for VariantTable<V> {
+    fn sample<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> V {
+        V::ALL[self.dist.sample(rng)]
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a value of a bounded type, with every value
+    /// from its `MIN` to its `MAX` equally likely
+    ///
+    /// Panics if the type's `MIN` is above its `MAX`
+    pub fn get_bounded<V: BoundedRandom>(&mut self) -> V {
+        let value = self
+            .fair_roll_range(V::MIN..=V::MAX)
+            .expect("BoundedRandom has MIN above MAX");
+        V::from_repr(value)
+    }
+
+    /// Provide a random value of any `Random` type
+    pub fn get_random<V: Random>(&mut self) -> V {
+        V::random(self)
+    }
+
+    /// Provide a fixed-size array of random values, without
+    /// allocating, such as `rng.get_array::<u64, 4>()`
+    pub fn get_array<V: Random, const N: usize>(&mut self) -> [V; N] {
+        self.get_random()
+    }
+
+    /// Pick one of a type's variants uniformly, such as
+    /// `rng.pick_variant::<Direction>()`
+    ///
+    /// Panics if the type lists no variants
+    pub fn pick_variant<V: Variants>(&mut self) -> V {
+        assert!(!V::ALL.is_empty(), "Can't pick from no variants");
+        V::ALL[self.fair_roll(V::ALL.len() as u32) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_random_values() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut total_len = 0;
+        for _ in 0..1000 {
+            let (v, s, _): (Vec<u8>, String, Option<[i16; 3]>) = rng.get_random();
+            assert!(v.len() <= MAX_RANDOM_LEN as usize);
+            assert!(s.len() <= MAX_RANDOM_LEN as usize);
+            total_len += v.len();
+            let f = rng.get_random::<f32>();
+            assert!((0.0..1.0).contains(&f));
+        }
+        // Lengths are uniform from 0 to 16
+        let avg = total_len as f64 / 1000.0;
+        assert!((avg - 8.0).abs() < 0.5, "{}", avg);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_random() {
+        use crate::Random;
+
+        #[derive(Random)]
+        struct Config {
+            name: String,
+            limit: Option<u32>,
+            mode: Mode,
+        }
+
+        #[derive(Debug, PartialEq, Random)]
+        enum Mode {
+            Fast,
+            Slow(u8),
+            Custom { level: bool },
+        }
+
+        #[derive(Random)]
+        struct Wrapper<V>(V, ());
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut fast = 0;
+        for _ in 0..3000 {
+            let config: Config = rng.get_random();
+            assert!(config.name.len() <= MAX_RANDOM_LEN as usize);
+            let _ = config.limit;
+            if config.mode == Mode::Fast {
+                fast += 1;
+            }
+        }
+        assert!(fast > 900 && fast < 1100, "{}", fast);
+        let Wrapper(value, ()) = rng.get_random::<Wrapper<u64>>();
+        let _ = value;
+    }
+
+    #[test]
+    fn test_bounded_values() {
+        struct Level(i8);
+
+        impl BoundedRandom for Level {
+            type Repr = i8;
+            const MIN: i8 = -2;
+            const MAX: i8 = 2;
+
+            fn from_repr(value: i8) -> Self {
+                Level(value)
+            }
+        }
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut counts = [0; 5];
+        for _ in 0..10000 {
+            let Level(level) = rng.get_bounded();
+            counts[(level + 2) as usize] += 1;
+        }
+        assert!(
+            counts.iter().all(|c| (1850..2150).contains(c)),
+            "{:?}",
+            counts
+        );
+        let mut ones = 0;
+        for _ in 0..2550 {
+            if rng.get_random::<NonZeroU8>().get() == 1 {
+                ones += 1;
+            }
+        }
+        assert!(ones > 0 && ones < 25, "{}", ones);
+        let _: [NonZeroU64; 4] = rng.get_random();
+    }
+
+    #[test]
+    fn test_weighted_enum() {
+        weighted_enum! {
+            #[derive(Debug, PartialEq)]
+            enum Weather {
+                Sun = 6.0,
+                /// Rare, but not too rare
+                Rain = 2.0,
+                Snow = 0.0,
+            }
+        }
+
+        let table = VariantTable::<Weather>::new().unwrap();
+        assert_eq!(table.probability(Weather::Sun), 0.75);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut rain = 0;
+        for _ in 0..10000 {
+            match rng.sample(&table) {
+                Weather::Rain => rain += 1,
+                Weather::Snow => panic!("Picked a variant with no weight"),
+                Weather::Sun => {}
+            }
+        }
+        assert!((rain as f64 / 10000.0 - 0.25).abs() < 0.015, "{}", rain);
+        assert_eq!(Weather::ALL.len(), 3);
+    }
+
+    #[test]
+    fn test_table_without_partial_eq() {
+        weighted_enum! {
+            #[derive(Debug)]
+            enum Coin {
+                Heads = 1.0,
+                Tails = 1.0,
+            }
+        }
+
+        let table = VariantTable::<Coin>::new().unwrap();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let heads = (0..1000)
+            .filter(|_| matches!(rng.sample(&table), Coin::Heads))
+            .count();
+        assert!((400..600).contains(&heads), "{}", heads);
+    }
+
+    #[test]
+    fn test_pick_variant() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        enum Coin {
+            Heads,
+            Tails,
+        }
+
+        impl Variants for Coin {
+            const ALL: &'static [Self] = &[Coin::Heads, Coin::Tails];
+        }
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let heads = (0..10000)
+            .filter(|_| rng.pick_variant::<Coin>() == Coin::Heads)
+            .count();
+        assert!(heads > 4800 && heads < 5200, "{}", heads);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_variants() {
+        use crate::Variants;
+
+        #[derive(Debug, Clone, Copy, PartialEq, Variants)]
+        enum Direction {
+            North,
+            East,
+            South,
+            West,
+        }
+
+        assert_eq!(Direction::ALL.len(), 4);
+        assert_eq!(Direction::ALL[2], Direction::South);
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let _: Direction = rng.pick_variant();
+    }
+}
@@ -0,0 +1,528 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Rolling within any integer range, including negative bounds.
+//!
+//! Values are mapped in order onto unsigned offsets from the
+//! smallest value of their type, so `-5..=5` is rolled as an
+//! offset below 11 and mapped back.
+//!
+//! Floats can be drawn uniformly from any finite range too,
+//! sub-ranges uniformly from within a range, and cells
+//! uniformly from a grid of any number of dimensions.
+//!
+//! The checked rolls here report a die with no sides
+//! as an error, where `roll`, `fair_roll` and `biased_roll` give 0.
+
+use crate::compat::BiasedRoll;
+use crate::sampling::Rejected;
+use crate::{trace, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+use std::ops::{Bound, Range, RangeBounds};
+
+/// The reasons a checked roll may fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollError {
+    /// The die had no sides, so there is no face to roll
+    NoSides,
+}
+
+impl fmt::Display for RollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollError::NoSides => write!(f, "cannot roll a die with no sides"),
+        }
+    }
+}
+
+impl std::error::Error for RollError {}
+
+/// An integer type which can be rolled within a range
+pub trait RollInt: Copy + Ord {
+    /// The offset of this value from the smallest of its type
+    fn to_offset(self) -> u128;
+    /// The value at an offset from the smallest of its type
+    fn from_offset(offset: u128) -> Self;
+    /// The largest value of the type
+    const MAX: Self;
+}
+
+macro_rules! unsigned_roll_int {
+    ($($t:ty),*) => {
+        $(impl RollInt for $t {
+            fn to_offset(self) -> u128 {
+                self as u128
+            }
+            fn from_offset(offset: u128) -> Self {
+                offset as $t
+            }
+            const MAX: Self = <$t>::MAX;
+        })*
+    };
+}
+
+macro_rules! signed_roll_int {
+    ($($t:ty => $u:ty),*) => {
+        $(impl RollInt for $t {
+            fn to_offset(self) -> u128 {
+                // Flipping the sign bit keeps the order
+                (self as $u ^ (1 << (<$u>::BITS - 1))) as u128
+            }
+            fn from_offset(offset: u128) -> Self {
+                (offset as $u ^ (1 << (<$u>::BITS - 1))) as $t
+            }
+            const MAX: Self = <$t>::MAX;
+        })*
+    };
+}
+
+unsigned_roll_int!(u8, u16, u32, u64, u128, usize);
+signed_roll_int!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+/// The first offset and the number of values in a range,
+/// where a count of zero is the full 128 bit span
+fn span<I: RollInt, R: RangeBounds<I>>(range: &R) -> Option<(u128, u128)> {
+    let low = match range.start_bound() {
+        Bound::Included(v) => v.to_offset(),
+        Bound::Excluded(v) if *v == I::MAX => return None,
+        Bound::Excluded(v) => v.to_offset() + 1,
+        Bound::Unbounded => 0,
+    };
+    let high = match range.end_bound() {
+        Bound::Included(v) => v.to_offset(),
+        Bound::Excluded(v) if v.to_offset() == 0 => return None,
+        Bound::Excluded(v) => v.to_offset() - 1,
+        Bound::Unbounded => I::MAX.to_offset(),
+    };
+    if low > high {
+        return None;
+    }
+    Some((low, (high - low).wrapping_add(1)))
+}
+
+/// A fair roll, with the randomness it took
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DetailedRoll {
+    /// The face rolled, from 0 to `sides - 1`
+    pub value: u32,
+    /// How many draws were rejected as unfair first
+    pub rejections: u64,
+    /// How many steps the roll took in all
+    pub steps: u64,
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// As `fair_roll`, also reporting how many draws were
+    /// rejected and how many steps were taken
+    ///
+    /// Every algorithm takes one step per draw, so the steps are
+    /// one more than the rejections, except that a die with no
+    /// sides takes none.
+    pub fn fair_roll_detailed(&mut self, sides: u32) -> DetailedRoll {
+        let before = self.steps();
+        let value = self.fair_roll(sides);
+        let steps = self.steps() - before;
+        DetailedRoll {
+            value,
+            rejections: steps.saturating_sub(1),
+            steps,
+        }
+    }
+
+    /// As `fair_roll`, but after `max_retries` rejected draws,
+    /// the last draw is used as it is, with a tiny bias
+    ///
+    /// This bounds the work of a roll to `max_retries + 1`
+    /// steps, for loops with a hard deadline. Whenever a fair
+    /// value is found in time, it is the one `fair_roll` gives.
+    /// The bias of the fallback is that of `biased_roll`, and
+    /// even with no retries it is used at most once in 2^29 rolls.
+    pub fn fair_roll_or_biased(&mut self, sides: u32, max_retries: u32) -> u32 {
+        let value = match self.fair_roll_within(sides, max_retries as u64 + 1) {
+            Ok(value) | Err(value) => value,
+        };
+        self.record(trace::Method::FairRoll, value as u64);
+        value
+    }
+
+    /// As `fair_roll`, but an error after `max_retries`
+    /// rejected draws, which have still been stepped past
+    pub fn try_fair_roll_capped(&mut self, sides: u32, max_retries: u32) -> Result<u32, Rejected> {
+        let draws = max_retries as u64 + 1;
+        match self.fair_roll_within(sides, draws) {
+            Ok(value) => {
+                self.record(trace::Method::FairRoll, value as u64);
+                Ok(value)
+            }
+            Err(_) => Err(Rejected {
+                tries: draws as usize,
+            }),
+        }
+    }
+
+    /// As `biased_roll`, but a die with no sides is an error
+    pub fn try_roll(&mut self, sides: u32) -> Result<u32, RollError> {
+        if sides == 0 {
+            return Err(RollError::NoSides);
+        }
+        Ok(BiasedRoll::biased_roll(self, sides))
+    }
+
+    /// As `fair_roll`, but a die with no sides is an error
+    pub fn try_fair_roll(&mut self, sides: u32) -> Result<u32, RollError> {
+        if sides == 0 {
+            return Err(RollError::NoSides);
+        }
+        Ok(self.fair_roll(sides))
+    }
+
+    /// Roll fairly in `0..n`, the same as `try_fair_roll(n)`
+    ///
+    /// `n` itself is never given, so `below(0)` is an error,
+    /// without taking a step.
+    pub fn below(&mut self, n: u32) -> Result<u32, RollError> {
+        self.try_fair_roll(n)
+    }
+
+    /// Roll fairly in `0..=n`, which may give `n` itself
+    pub fn up_to(&mut self, n: u32) -> u32 {
+        match n.checked_add(1) {
+            Some(sides) => self.fair_roll(sides),
+            None => self.get_u32(),
+        }
+    }
+
+    /// Provide a float uniformly within a finite range,
+    /// or `None` if the range is empty or not finite
+    ///
+    /// Works even when the width of the range overflows, as for
+    /// `-f64::MAX..f64::MAX`. Results which round up to the end
+    /// of the range are drawn again, so the end is never given.
+    pub fn get_f64_in(&mut self, range: Range<f64>) -> Option<f64> {
+        let Range { start, end } = range;
+        if !(start < end && start.is_finite() && end.is_finite()) {
+            return None;
+        }
+        let width = end - start;
+        loop {
+            let unit = self.get_f64();
+            let value = if width.is_finite() {
+                start + unit * width
+            } else {
+                // Halving is exact this far from zero
+                2.0 * (start / 2.0 + unit * (end / 2.0 - start / 2.0))
+            };
+            if value < end {
+                return Some(value);
+            }
+        }
+    }
+
+    /// Provide a sub-range of `outer` with a length from `min_len`
+    /// to `max_len`, or `None` if no such sub-range fits
+    ///
+    /// Every fitting sub-range is equally likely, so short windows
+    /// are not favoured over long ones. A start and a length are
+    /// rolled together and drawn again when they run past the end,
+    /// which happens less than half of the time.
+    pub fn get_subrange(
+        &mut self,
+        outer: Range<u64>,
+        min_len: u64,
+        max_len: u64,
+    ) -> Option<Range<u64>> {
+        let width = outer.end.checked_sub(outer.start)?;
+        let max_len = max_len.min(width);
+        if min_len > max_len {
+            return None;
+        }
+        let starts = (width - min_len) as u128 + 1;
+        let lens = (max_len - min_len) as u128 + 1;
+        loop {
+            let offset = self.below_u128(starts) as u64;
+            let len = min_len + self.below_u128(lens) as u64;
+            if len <= width - offset {
+                let start = outer.start + offset;
+                return Some(start..start + len);
+            }
+        }
+    }
+
+    /// Roll fairly within any integer range, such as `-5..=5`,
+    /// or `None` if the range is empty
+    pub fn fair_roll_range<I: RollInt, R: RangeBounds<I>>(&mut self, range: R) -> Option<I> {
+        let (low, count) = span(&range)?;
+        let offset = if count == 0 {
+            ((self.step() as u128) << 64) | self.step() as u128
+        } else {
+            self.below_u128(count)
+        };
+        Some(I::from_offset(low.wrapping_add(offset)))
+    }
+
+    /// Roll fairly within a range of 128-bit values, such as an
+    /// IPv6 subnet, or `None` if the range is empty
+    ///
+    /// Inclusive ranges may end at `u128::MAX`. The whole of
+    /// `0..=u128::MAX` takes two steps, high half first, and is
+    /// traced with the low 64 bits as the value.
+    pub fn roll_range_u128<R: RangeBounds<u128>>(&mut self, range: R) -> Option<u128> {
+        let (low, count) = span(&range)?;
+        if count == 0 {
+            let value = ((self.step() as u128) << 64) | self.step() as u128;
+            self.record(trace::Method::FairRoll, value as u64);
+            return Some(value);
+        }
+        Some(low + self.fair_roll_u128(count))
+    }
+
+    /// Pick a cell of an N-dimensional grid uniformly, as a
+    /// coordinate for each axis
+    ///
+    /// One fair roll is made over every cell at once, then split
+    /// into coordinates with the last axis varying fastest, so no
+    /// bias builds up across the axes. No axes give the one cell
+    /// of a zero-dimensional grid. Gives `None` if any axis is
+    /// empty, or if there are more than 2^128 cells.
+    pub fn get_index_nd(&mut self, dims: &[usize]) -> Option<Vec<usize>> {
+        let cells = dims
+            .iter()
+            .try_fold(1u128, |cells, d| cells.checked_mul(*d as u128))
+            .filter(|cells| *cells > 0)?;
+        let mut index = self.fair_roll_u128(cells);
+        let mut coords = vec![0; dims.len()];
+        for (coord, d) in coords.iter_mut().zip(dims).rev() {
+            *coord = (index % *d as u128) as usize;
+            index /= *d as u128;
+        }
+        Some(coords)
+    }
+
+    /// Roll within any integer range in a single step,
+    /// or `None` if the range is empty
+    ///
+    /// As with `biased_roll`, the bias grows with the size of the
+    /// range. Ranges of more than 2^61 values are rolled fairly.
+    pub fn biased_roll_range<I: RollInt, R: RangeBounds<I>>(&mut self, range: R) -> Option<I> {
+        let (low, count) = span(&range)?;
+        if count == 0 || count > 1 << 61 {
+            return self.fair_roll_range(range);
+        }
+        let offset = (self.step() >> 3) as u128 % count;
+        Some(I::from_offset(low + offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_signed_ranges() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut counts = [0; 11];
+        for _ in 0..11000 {
+            let roll = rng.fair_roll_range(-5i32..=5).unwrap();
+            counts[(roll + 5) as usize] += 1;
+            let biased = rng.biased_roll_range(-3i8..0).unwrap();
+            assert!((-3..0).contains(&biased));
+        }
+        assert!(
+            counts.iter().all(|c| (*c - 1000i32).abs() < 120),
+            "{:?}",
+            counts
+        );
+        assert_eq!(rng.fair_roll_range(3u8..3), None);
+        assert_eq!(rng.fair_roll_range(i64::MAX..=i64::MAX), Some(i64::MAX));
+        assert_eq!(rng.fair_roll_range(..i8::MIN), None);
+        let any: i128 = rng.fair_roll_range(..).unwrap();
+        assert_ne!(any, rng.fair_roll_range(..).unwrap());
+    }
+
+    #[test]
+    fn test_u128_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let subnet = 0x2001_0db8u128 << 96;
+        let mut high = 0;
+        for _ in 0..1000 {
+            let addr = rng.roll_range_u128(subnet..subnet + (1 << 96)).unwrap();
+            assert_eq!(addr >> 96, 0x2001_0db8);
+            // A bound just above a power of two still rolls evenly below it
+            if rng.fair_roll_u128((1 << 100) + 1) >= 1 << 99 {
+                high += 1;
+            }
+        }
+        assert!((high - 500i32).abs() < 60, "{}", high);
+        let before = rng.steps();
+        rng.fair_roll_u128(u128::MAX);
+        assert_eq!(rng.steps() - before, 2);
+        assert_eq!(rng.fair_roll_u128(0), 0);
+        assert_eq!(rng.roll_range_u128(5..5), None);
+        assert_eq!(rng.roll_range_u128(u128::MAX..=u128::MAX), Some(u128::MAX));
+        let mut top = 0;
+        for _ in 0..1000 {
+            if rng.roll_range_u128(u128::MAX - 1..=u128::MAX).unwrap() == u128::MAX {
+                top += 1;
+            }
+        }
+        assert!((top - 500i32).abs() < 60, "{}", top);
+        let before = rng.steps();
+        rng.roll_range_u128(..);
+        assert_eq!(rng.steps() - before, 2);
+    }
+
+    #[test]
+    fn test_wide_floats() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut negative = 0;
+        for _ in 0..1000 {
+            let x = rng.get_f64_in(-f64::MAX..f64::MAX).unwrap();
+            assert!(x.is_finite());
+            if x < 0.0 {
+                negative += 1;
+            }
+            // The only value below the end here is the start
+            let next = 1.0 + f64::EPSILON;
+            assert_eq!(rng.get_f64_in(1.0..next), Some(1.0));
+        }
+        assert!((negative - 500i32).abs() < 60, "{}", negative);
+        assert_eq!(rng.get_f64_in(2.0..2.0), None);
+        assert_eq!(rng.get_f64_in(0.0..f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_subranges() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        // Lengths 1 to 3 within 0..4 give 4 + 3 + 2 windows
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..9000 {
+            let sub = rng.get_subrange(0..4, 1, 3).unwrap();
+            *counts.entry(sub).or_insert(0i32) += 1;
+        }
+        assert_eq!(counts.len(), 9);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 120),
+            "{:?}",
+            counts
+        );
+        let wide = rng.get_subrange(0..u64::MAX, 0, u64::MAX).unwrap();
+        assert!(wide.start <= wide.end);
+        assert_eq!(rng.get_subrange(10..12, 3, 5), None);
+        assert_eq!(rng.get_subrange(10..12, 2, 5), Some(10..12));
+    }
+
+    #[test]
+    fn test_checked_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_eq!(rng.try_roll(0), Err(RollError::NoSides));
+        assert_eq!(rng.try_fair_roll(0), Err(RollError::NoSides));
+        // Errors don't step the rng
+        assert_eq!(rng.try_fair_roll(6), Ok(other.fair_roll(6)));
+        assert_eq!(
+            rng.try_roll(20),
+            Ok(BiasedRoll::biased_roll(&mut other, 20))
+        );
+    }
+
+    #[test]
+    fn test_below_and_up_to() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let (mut below, mut top) = (0, 0);
+        for _ in 0..1000 {
+            below = below.max(rng.below(4).unwrap());
+            top = top.max(rng.up_to(4));
+        }
+        assert_eq!((below, top), (3, 4));
+        let steps = rng.steps();
+        assert_eq!(rng.below(0), Err(RollError::NoSides));
+        assert_eq!(rng.steps(), steps);
+        assert_eq!(rng.up_to(0), 0);
+    }
+
+    // A backend stuck on all ones, whose draws are all
+    // rejected by `Algorithm::V2` rolls of three sides
+    struct Stuck;
+
+    impl RngCore for Stuck {
+        fn next_u32(&mut self) -> u32 {
+            u32::MAX
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::MAX
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(u8::MAX);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl SeedableRng for Stuck {
+        type Seed = [u8; 8];
+
+        fn from_seed(_: [u8; 8]) -> Self {
+            Stuck
+        }
+    }
+
+    #[test]
+    fn test_capped_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = rng.clone();
+        for _ in 0..1000 {
+            assert_eq!(rng.fair_roll_or_biased(6, 0), other.fair_roll(6));
+            assert_eq!(rng.try_fair_roll_capped(6, 2), Ok(other.fair_roll(6)));
+        }
+        let mut stuck = SplittingRng::<Stuck>::with_algorithm(0, crate::Algorithm::V2);
+        let before = stuck.steps();
+        assert_eq!(stuck.try_fair_roll_capped(3, 4), Err(Rejected { tries: 5 }));
+        assert_eq!(stuck.steps() - before, 5);
+        // (2^61 - 1) % 3 is 1
+        assert_eq!(stuck.fair_roll_or_biased(3, 0), 1);
+    }
+
+    #[test]
+    fn test_grid_indices() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut counts = [[[0u32; 5]; 2]; 3];
+        for _ in 0..30000 {
+            let c = rng.get_index_nd(&[3, 2, 5]).unwrap();
+            counts[c[0]][c[1]][c[2]] += 1;
+        }
+        for count in counts.iter().flatten().flatten() {
+            assert!((*count as i32 - 1000).abs() < 130, "{}", count);
+        }
+        assert_eq!(rng.get_index_nd(&[]), Some(vec![]));
+        assert_eq!(rng.get_index_nd(&[4, 0]), None);
+        assert_eq!(rng.get_index_nd(&[usize::MAX; 3]), None);
+        // A single roll, so the same cell as the flat index
+        let mut copy = rng.clone();
+        let c = rng.get_index_nd(&[7, 9]).unwrap();
+        assert_eq!(c[0] * 9 + c[1], copy.fair_roll_u128(63) as usize);
+    }
+
+    #[test]
+    fn test_detailed_rolls() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut other = rng.clone();
+        let before = rng.steps();
+        let mut steps = 0;
+        for _ in 0..1000 {
+            let roll = rng.fair_roll_detailed(3 << 30);
+            assert_eq!(roll.value, other.fair_roll(3 << 30));
+            assert_eq!(roll.steps, roll.rejections + 1);
+            steps += roll.steps;
+        }
+        assert_eq!(rng.steps() - before, steps);
+        assert_eq!(rng.fair_roll_detailed(0).steps, 0);
+    }
+}
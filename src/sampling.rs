@@ -0,0 +1,517 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Sampling from collections, and stratified and antithetic
+//! sampling for variance reduction.
+
+use crate::distributions::DistributionError;
+use crate::{unit_f64, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Every candidate was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rejected {
+    /// The number of candidates drawn
+    pub tries: usize,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "all {} candidates were rejected", self.tries)
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Draw candidates until one is accepted, giving up after `max_tries`
+    ///
+    /// This is rejection sampling, as `fair_roll` does internally.
+    /// Accepted values follow the candidate distribution restricted
+    /// to what is accepted, as long as the check does not depend on
+    /// anything else. The cap turns a check which is rarely or never
+    /// true into an error instead of a hang.
+    pub fn sample_until<V>(
+        &mut self,
+        mut candidate: impl FnMut(&mut Self) -> V,
+        mut accept: impl FnMut(&V) -> bool,
+        max_tries: usize,
+    ) -> Result<V, Rejected> {
+        for _ in 0..max_tries {
+            let value = candidate(self);
+            if accept(&value) {
+                return Ok(value);
+            }
+        }
+        Err(Rejected { tries: max_tries })
+    }
+
+    /// Remove and return a uniformly chosen element,
+    /// or `None` if the list is empty
+    ///
+    /// The last element takes the place of the one removed,
+    /// so the order of the rest is not kept.
+    pub fn draw<V>(&mut self, list: &mut Vec<V>) -> Option<V> {
+        if list.is_empty() {
+            return None;
+        }
+        let index = self.below_u128(list.len() as u128) as usize;
+        Some(list.swap_remove(index))
+    }
+
+    /// Provide `k` independent uniform picks from a list,
+    /// such as a bootstrap resample when `k` is its length
+    ///
+    /// The list is left as it is, and items may be picked more
+    /// than once. An empty list gives no picks.
+    pub fn sample_with_replacement<V: Clone>(&mut self, list: &[V], k: usize) -> Vec<V> {
+        self.iter_picks(list).take(k).cloned().collect()
+    }
+
+    /// Provide endless independent uniform picks from a list,
+    /// ending at once if the list is empty
+    pub fn iter_picks<'a, V>(&'a mut self, list: &'a [V]) -> impl Iterator<Item = &'a V> + 'a {
+        let len = list.len() as u128;
+        std::iter::from_fn(move || match len {
+            0 => None,
+            _ => Some(&list[self.below_u128(len) as usize]),
+        })
+    }
+
+    /// Choose a uniformly random entry of a `HashMap`,
+    /// or `None` if it is empty
+    ///
+    /// A `HashMap` iterates in a different order from one run
+    /// to the next, so the entries are sorted by key before one
+    /// is picked, to keep the choice reproducible. This costs
+    /// O(n log n) time and O(n) space for each call; for repeated
+    /// picks, keep a `Vec` of the keys or use a `BTreeMap`.
+    pub fn choose_from_map<'a, K: Ord, V, S>(
+        &mut self,
+        map: &'a HashMap<K, V, S>,
+    ) -> Option<(&'a K, &'a V)> {
+        self.choose_multiple_from_map(map, 1).pop()
+    }
+
+    /// Choose a uniformly random entry of a `BTreeMap`,
+    /// or `None` if it is empty
+    ///
+    /// The entries are already in order, but can't be indexed,
+    /// so this walks to the chosen one in O(n) time.
+    pub fn choose_from_btree_map<'a, K, V>(
+        &mut self,
+        map: &'a BTreeMap<K, V>,
+    ) -> Option<(&'a K, &'a V)> {
+        match map.len() {
+            0 => None,
+            len => map.iter().nth(self.below_u128(len as u128) as usize),
+        }
+    }
+
+    /// Choose `k` distinct entries of a `HashMap`, in random order
+    ///
+    /// Gives every entry if there are no more than `k`. As with
+    /// `choose_from_map`, the entries are sorted by key first, in
+    /// O(n log n) time, then `k` of them are drawn in O(k).
+    pub fn choose_multiple_from_map<'a, K: Ord, V, S>(
+        &mut self,
+        map: &'a HashMap<K, V, S>,
+        k: usize,
+    ) -> Vec<(&'a K, &'a V)> {
+        let mut entries: Vec<(&K, &V)> = map.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        self.partial_shuffle(entries, k)
+    }
+
+    /// Choose `k` distinct entries of a `BTreeMap`, in random order
+    ///
+    /// Gives every entry if there are no more than `k`. The
+    /// entries are gathered in O(n), then `k` are drawn in O(k).
+    pub fn choose_multiple_from_btree_map<'a, K, V>(
+        &mut self,
+        map: &'a BTreeMap<K, V>,
+        k: usize,
+    ) -> Vec<(&'a K, &'a V)> {
+        self.partial_shuffle(map.iter().collect(), k)
+    }
+
+    /// Provide a uniformly random `k` item subset of `0..n`,
+    /// in increasing order
+    ///
+    /// Every one of the C(n, k) subsets is equally likely. Uses
+    /// Floyd's subset sampling, so it takes `k` rolls however
+    /// large `n` is. Panics if `k` is over `n`
+    pub fn random_combination(&mut self, n: usize, k: usize) -> Vec<usize> {
+        assert!(k <= n, "More items chosen than there are items");
+        let mut chosen = BTreeSet::new();
+        for j in n - k..n {
+            let t = self.below_u128(j as u128 + 1) as usize;
+            if !chosen.insert(t) {
+                chosen.insert(j);
+            }
+        }
+        chosen.into_iter().collect()
+    }
+
+    /// Provide endless independent `random_combination`s
+    /// of `k` items from `0..n`
+    ///
+    /// Panics if `k` is over `n`
+    pub fn iter_combinations(
+        &mut self,
+        n: usize,
+        k: usize,
+    ) -> impl Iterator<Item = Vec<usize>> + '_ {
+        assert!(k <= n, "More items chosen than there are items");
+        std::iter::repeat_with(move || self.random_combination(n, k))
+    }
+
+    // The first `k` steps of a Fisher-Yates shuffle
+    fn partial_shuffle<E>(&mut self, mut entries: Vec<E>, k: usize) -> Vec<E> {
+        let k = k.min(entries.len());
+        for i in 0..k {
+            let j = i + self.below_u128((entries.len() - i) as u128) as usize;
+            entries.swap(i, j);
+        }
+        entries.truncate(k);
+        entries
+    }
+
+    /// Order items so that each is more likely to come early
+    /// in proportion to its weight
+    ///
+    /// Uses exponential keys, the Efraimidis-Spirakis method: each
+    /// item is keyed by an exponential value divided by its weight,
+    /// then sorted. Items with zero weight come last, in their
+    /// original order. The first item is distributed exactly as one
+    /// draw from a `DiscreteDistribution` of the same weights.
+    ///
+    /// Panics if there are not as many weights as items
+    pub fn weighted_shuffle<L: Copy>(
+        &mut self,
+        items: &[L],
+        weights: &[f64],
+    ) -> Result<Vec<L>, DistributionError> {
+        let mut keyed = self.exponential_keys(items, weights)?;
+        // The sort is stable, so zero weights keep their order
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(keyed.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Choose `k` distinct items, each more likely to be chosen
+    /// in proportion to its weight
+    ///
+    /// The result is the first `k` items of `weighted_shuffle`,
+    /// found without sorting the rest. Items with zero weight are
+    /// never chosen, so fewer than `k` may be returned.
+    ///
+    /// Panics if there are not as many weights as items
+    pub fn choose_multiple_weighted<L: Copy>(
+        &mut self,
+        items: &[L],
+        weights: &[f64],
+        k: usize,
+    ) -> Result<Vec<L>, DistributionError> {
+        let mut keyed = self.exponential_keys(items, weights)?;
+        keyed.retain(|(key, _)| key.is_finite());
+        if k < keyed.len() {
+            keyed.select_nth_unstable_by(k, |a, b| a.0.total_cmp(&b.0));
+            keyed.truncate(k);
+        }
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(keyed.into_iter().map(|(_, item)| item).collect())
+    }
+
+    // Key each item by an exponential value divided by its weight
+    fn exponential_keys<L: Copy>(
+        &mut self,
+        items: &[L],
+        weights: &[f64],
+    ) -> Result<Vec<(f64, L)>, DistributionError> {
+        assert_eq!(items.len(), weights.len(), "Each item needs a weight");
+        if weights.iter().any(|w| !w.is_finite()) {
+            return Err(DistributionError::NonFinite);
+        }
+        if weights.iter().any(|w| *w < 0.0) {
+            return Err(DistributionError::NegativeWeight);
+        }
+        Ok(items
+            .iter()
+            .zip(weights)
+            .map(|(item, w)| {
+                // 1 - u is never zero, so the log is finite
+                let exp = -(1.0 - self.get_f64()).ln();
+                let key = if *w == 0.0 { f64::INFINITY } else { exp / w };
+                (key, *item)
+            })
+            .collect())
+    }
+
+    /// Draw `counts[i]` uniform samples within each stratum,
+    /// from `boundaries[i]` to `boundaries[i + 1]`
+    ///
+    /// The boundaries must be finite and strictly increasing.
+    /// Panics if there is not one count per stratum.
+    pub fn stratified(
+        &mut self,
+        boundaries: &[f64],
+        counts: &[usize],
+    ) -> Result<Vec<Vec<f64>>, DistributionError> {
+        assert_eq!(
+            boundaries.len().saturating_sub(1),
+            counts.len(),
+            "Each stratum needs a count"
+        );
+        if boundaries.iter().any(|b| !b.is_finite()) {
+            return Err(DistributionError::NonFinite);
+        }
+        if boundaries.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(DistributionError::BadBounds);
+        }
+        Ok(boundaries
+            .windows(2)
+            .zip(counts)
+            .map(|(w, count)| {
+                (0..*count)
+                    .map(|_| w[0] + (w[1] - w[0]) * self.get_f64())
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Draw one uniform sample from each of `n` equal strata
+    /// of [0, 1), in order
+    pub fn stratified_unit(&mut self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| (i as f64 + self.get_f64()) / n as f64)
+            .collect()
+    }
+
+    /// Provide a Latin hypercube sample, `samples` points
+    /// in [0, 1)^`dims`
+    ///
+    /// Each dimension is split into `samples` equal strata, and
+    /// every stratum holds exactly one point, so each coordinate
+    /// on its own is evenly spread. The strata are paired up
+    /// across dimensions by shuffling.
+    pub fn latin_hypercube(&mut self, dims: usize, samples: usize) -> Vec<Vec<f64>> {
+        let columns: Vec<Vec<f64>> = (0..dims)
+            .map(|_| {
+                let column = self.stratified_unit(samples);
+                self.shuffle(&column)
+            })
+            .collect();
+        (0..samples)
+            .map(|i| columns.iter().map(|c| c[i]).collect())
+            .collect()
+    }
+
+    /// Provide a unit float together with its antithetic
+    /// partner, as `(u, 1 - u)`, from a single step
+    ///
+    /// More precisely the partner is `1 - u - 2^-53`, so that
+    /// both stay within [0, 1)
+    pub fn get_f64_pair(&mut self) -> (f64, f64) {
+        let step = self.step();
+        (unit_f64(step, false), unit_f64(step, true))
+    }
+
+    /// Turn this rng into a pair of rngs for antithetic variates
+    ///
+    /// Both follow the same stream, but the second mirrors every
+    /// unit float, as in `get_f64_pair`. Their children from
+    /// `split` are paired the same way, so two runs of a whole
+    /// simulation, one on each, see antithetic uniforms as long as
+    /// they draw the same way. Only floats are mirrored: integers,
+    /// bools and rolls are the same for both.
+    pub fn antithetic_pair(self) -> (Self, Self)
+    where
+        T: Clone,
+    {
+        let mut mirror = self.clone();
+        mirror.mirrored = !self.mirrored;
+        (self, mirror)
+    }
+
+    /// Whether unit floats are mirrored
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_weighted_shuffle() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 20000;
+        let mut heavy_first = 0;
+        for _ in 0..iter {
+            let order = rng
+                .weighted_shuffle(&['a', 'b', 'c', 'z'], &[3.0, 1.0, 1.0, 0.0])
+                .unwrap();
+            assert_eq!(order[3], 'z');
+            if order[0] == 'a' {
+                heavy_first += 1;
+            }
+        }
+        let frac = heavy_first as f64 / iter as f64;
+        assert!((frac - 0.6).abs() < 0.01, "{}", frac);
+        assert_eq!(
+            rng.weighted_shuffle(&[1], &[-1.0]),
+            Err(DistributionError::NegativeWeight)
+        );
+    }
+
+    #[test]
+    fn test_sample_with_replacement() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let data = [1, 2, 3, 4, 5, 6];
+        let iter = 10000;
+        let resample = rng.sample_with_replacement(&data, iter);
+        assert_eq!(resample.len(), iter);
+        let ones = resample.iter().filter(|x| **x == 1).count();
+        let frac = ones as f64 / iter as f64;
+        assert!((frac - 1.0 / 6.0).abs() < 0.01, "{}", frac);
+        let empty: [u8; 0] = [];
+        assert!(rng.sample_with_replacement(&empty, 3).is_empty());
+        assert_eq!(rng.iter_picks(&data).take(5).count(), 5);
+    }
+
+    #[test]
+    fn test_choose_from_maps() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let hashed: HashMap<u32, char> = (0..26).map(|i| (i, (b'a' + i as u8) as char)).collect();
+        let ordered: BTreeMap<u32, char> = hashed.iter().map(|(k, v)| (*k, *v)).collect();
+        // The same keys give the same choice, whatever the hash order
+        assert_eq!(
+            rng.choose_from_map(&hashed),
+            copy.choose_from_btree_map(&ordered)
+        );
+        let iter = 26000;
+        let mut a = 0;
+        for _ in 0..iter {
+            let (k, v) = rng.choose_from_map(&hashed).unwrap();
+            assert_eq!(hashed[k], *v);
+            if *v == 'a' {
+                a += 1;
+            }
+        }
+        assert!((a as f64 / 1000.0 - 1.0).abs() < 0.1, "{}", a);
+        let mut chosen = rng.choose_multiple_from_map(&hashed, 5);
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen.len(), 5);
+        assert_eq!(rng.choose_multiple_from_btree_map(&ordered, 30).len(), 26);
+        assert_eq!(rng.choose_from_btree_map(&BTreeMap::<u8, u8>::new()), None);
+    }
+
+    #[test]
+    fn test_random_combinations() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut counts = BTreeMap::new();
+        for pick in rng.iter_combinations(5, 2).take(10000) {
+            assert!(pick[0] < pick[1] && pick[1] < 5);
+            *counts.entry(pick).or_insert(0i32) += 1;
+        }
+        // All C(5, 2) = 10 pairs are equally likely
+        assert_eq!(counts.len(), 10);
+        assert!(
+            counts.values().all(|c| (c - 1000).abs() < 100),
+            "{:?}",
+            counts
+        );
+        assert_eq!(rng.random_combination(4, 4), vec![0, 1, 2, 3]);
+        assert!(rng.random_combination(4, 0).is_empty());
+        let before = rng.steps();
+        assert_eq!(rng.random_combination(usize::MAX, 3).len(), 3);
+        assert_eq!(rng.steps() - before, 3);
+    }
+
+    #[test]
+    fn test_choose_multiple_weighted() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut copy = rng.clone();
+        let items = ['a', 'b', 'c', 'd', 'z'];
+        let weights = [4.0, 2.0, 1.0, 1.0, 0.0];
+        let chosen = rng.choose_multiple_weighted(&items, &weights, 2).unwrap();
+        let order = copy.weighted_shuffle(&items, &weights).unwrap();
+        assert_eq!(chosen, order[..2]);
+        let all = rng.choose_multiple_weighted(&items, &weights, 10).unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(!all.contains(&'z'));
+    }
+
+    #[test]
+    fn test_stratified() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let strata = rng.stratified(&[0.0, 1.0, 10.0], &[3, 5]).unwrap();
+        assert_eq!(strata[0].len(), 3);
+        assert!(strata[0].iter().all(|x| (0.0..1.0).contains(x)));
+        assert!(strata[1].iter().all(|x| (1.0..10.0).contains(x)));
+        assert_eq!(
+            rng.stratified(&[1.0, 1.0], &[1]),
+            Err(DistributionError::BadBounds)
+        );
+        let unit = rng.stratified_unit(4);
+        for (i, x) in unit.iter().enumerate() {
+            assert_eq!((x * 4.0) as usize, i);
+        }
+    }
+
+    #[test]
+    fn test_latin_hypercube() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let points = rng.latin_hypercube(3, 10);
+        assert_eq!(points.len(), 10);
+        for d in 0..3 {
+            let mut strata: Vec<usize> = points.iter().map(|p| (p[d] * 10.0) as usize).collect();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_antithetic_pair() {
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let (mut plain, mut mirror) = rng.antithetic_pair();
+        let (u, v) = plain.clone().get_f64_pair();
+        assert_eq!(u + v, 1.0 - 1.0 / (1u64 << 53) as f64);
+        assert_eq!(plain.get_f64(), u);
+        assert_eq!(mirror.get_f64(), v);
+        let mut a = plain.split();
+        let mut b = mirror.split();
+        assert!(b.is_mirrored());
+        assert!((a.get_f64() + b.get_f64() - 1.0).abs() < 1e-15);
+        assert_eq!(a.get_u64(), b.get_u64());
+    }
+
+    #[test]
+    fn test_sample_until() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for _ in 0..100 {
+            let odd = rng.sample_until(|r| r.fair_roll(100), |x| x % 2 == 1, 100);
+            assert_eq!(odd.map(|x| x % 2), Ok(1));
+        }
+        let never = rng.sample_until(|r| r.fair_roll(6), |x| *x == 6, 10);
+        assert_eq!(never, Err(Rejected { tries: 10 }));
+    }
+
+    #[test]
+    fn test_draw_empties_list() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut bag: Vec<u32> = (0..20).collect();
+        let mut drawn: Vec<u32> = std::iter::from_fn(|| rng.draw(&mut bag)).collect();
+        assert!(bag.is_empty());
+        assert_ne!(drawn, (0..20).collect::<Vec<_>>());
+        drawn.sort_unstable();
+        assert_eq!(drawn, (0..20).collect::<Vec<_>>());
+    }
+}
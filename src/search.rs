@@ -0,0 +1,107 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Searching for origins whose streams do something,
+//! such as a world seed with a castle by the spawn point,
+//! or a test seed which takes a rare branch.
+//!
+//! The search always gives the first origin in the range
+//! which satisfies the check, however many threads it runs on,
+//! so it can be rerun to find the same seed again.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Find the first origin in a range whose new rng
+    /// satisfies a check, or `None` if none does
+    ///
+    /// Each origin is checked with `SplittingRng::new(origin)`,
+    /// so the origin found can be used as it is.
+    pub fn find_seed<F>(origins: Range<u64>, mut accept: F) -> Option<u64>
+    where
+        F: FnMut(&mut SplittingRng<T>) -> bool,
+    {
+        origins
+            .into_iter()
+            .find(|origin| accept(&mut Self::new(*origin)))
+    }
+
+    /// Find the first origin which satisfies a check, as
+    /// `find_seed` does, checking across `threads` threads
+    ///
+    /// Thread `i` checks every `threads`-th origin from the `i`-th,
+    /// and stops once it passes the earliest found by any thread,
+    /// so the result is the same as from `find_seed`. No more threads
+    /// are spawned than `std::thread::available_parallelism` gives.
+    pub fn find_seed_parallel<F>(origins: Range<u64>, threads: usize, accept: F) -> Option<u64>
+    where
+        F: Fn(&mut SplittingRng<T>) -> bool + Sync,
+    {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        let threads = threads.clamp(1, cores) as u64;
+        let found = AtomicU64::new(u64::MAX);
+        std::thread::scope(|scope| {
+            for lane in 0..threads {
+                let (accept, found, origins) = (&accept, &found, origins.clone());
+                scope.spawn(move || {
+                    let mut origin = match origins.start.checked_add(lane) {
+                        Some(origin) => origin,
+                        None => return,
+                    };
+                    while origin < origins.end && origin < found.load(Ordering::Relaxed) {
+                        if accept(&mut Self::new(origin)) {
+                            found.fetch_min(origin, Ordering::Relaxed);
+                            return;
+                        }
+                        origin = match origin.checked_add(threads) {
+                            Some(next) => next,
+                            None => return,
+                        };
+                    }
+                });
+            }
+        });
+        // No origin in a range can be u64::MAX, as ranges exclude their end
+        match found.into_inner() {
+            u64::MAX => None,
+            origin => Some(origin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_find_seed() {
+        // A first roll of 20 on a d20 is one seed in twenty
+        let crit = |rng: &mut SplittingRng<Xoshiro256StarStar>| rng.fair_roll(20) == 19;
+        let origin = SplittingRng::find_seed(0..1000, crit).unwrap();
+        assert!(crit(&mut SplittingRng::new(origin)));
+        assert!((0..origin).all(|o| !crit(&mut SplittingRng::new(o))));
+        // Asking for far more threads than cores is capped
+        for threads in [1, 3, 8, usize::MAX] {
+            assert_eq!(
+                SplittingRng::find_seed_parallel(0..1000, threads, crit),
+                Some(origin)
+            );
+        }
+        let never = |rng: &mut SplittingRng<Xoshiro256StarStar>| rng.fair_roll(6) == 6;
+        assert_eq!(SplittingRng::find_seed(0..100, never), None);
+        assert_eq!(SplittingRng::find_seed_parallel(0..100, 4, never), None);
+        assert_eq!(
+            SplittingRng::find_seed_parallel(
+                u64::MAX - 5..u64::MAX,
+                4,
+                |_: &mut SplittingRng<Xoshiro256StarStar>| true
+            ),
+            Some(u64::MAX - 5)
+        );
+    }
+}
@@ -0,0 +1,102 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! A small built-in backend, for projects which
+//! don't want another crate just to pick one.
+//!
+//! SplitMix64 keeps a single word of state, so seeding it
+//! from an origin is free and every child is cheap to make.
+//! It is fast and passes the usual statistical tests, but
+//! a single stream has a period of only 2^64.
+
+use crate::keyed::mix64;
+use rand::{RngCore, SeedableRng};
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The SplitMix64 generator, usable as the backend
+/// of a `SplittingRng`
+///
+/// ```
+/// use splittable_random::{SplitMix64, SplittingRng};
+///
+/// let mut rng = SplittingRng::<SplitMix64>::new(12345);
+/// assert!(rng.fair_roll(6) < 6);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix64(self.state)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for SplitMix64 {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: [u8; 8]) -> Self {
+        SplitMix64 {
+            state: u64::from_le_bytes(seed),
+        }
+    }
+
+    /// Uses the origin as the state directly, as
+    /// every output is already well mixed
+    fn seed_from_u64(state: u64) -> Self {
+        SplitMix64 { state }
+    }
+}
+
+/// A fair value below a nonzero bound, by Lemire's method,
+/// for streams which never leave the crate
+pub(crate) fn below(rng: &mut SplitMix64, bound: u64) -> u64 {
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let product = rng.next_u64() as u128 * bound as u128;
+        if product as u64 >= threshold {
+            return (product >> 64) as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplittingRng;
+
+    #[test]
+    fn test_splitmix64() {
+        // The reference implementation's outputs for this seed
+        let mut backend = SplitMix64::seed_from_u64(1234567);
+        assert_eq!(backend.next_u64(), 6457827717110365317);
+        assert_eq!(backend.next_u64(), 3203168211198807973);
+        assert_eq!(
+            SplitMix64::from_seed(1234567u64.to_le_bytes()),
+            SplitMix64::seed_from_u64(1234567)
+        );
+        let mut rng = SplittingRng::<SplitMix64>::new(12345);
+        let mut child = rng.split();
+        assert_ne!(child.get_u64(), rng.get_u64());
+    }
+}
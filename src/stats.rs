@@ -0,0 +1,599 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Goodness-of-fit tests, for checking that custom
+//! distributions built on this crate behave as intended.
+//!
+//! Samples come from closures, which usually borrow
+//! an rng. With a fixed seed, a test using these is as
+//! reproducible as the rest of the crate.
+//!
+//! Custom shuffles can be checked with `assert_shuffle_uniform`.
+//! A quick self-test of the backend itself is here too,
+//! for catching a broken custom backend at startup.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+/// The outcome of a goodness-of-fit test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestResult {
+    /// The test statistic
+    pub statistic: f64,
+    /// The chance of a statistic at least this extreme,
+    /// if the samples really do follow the distribution
+    pub p_value: f64,
+}
+
+impl TestResult {
+    /// Whether the samples fit at significance level `alpha`
+    /// With `alpha` of 0.01, a correct distribution fails 1% of seeds
+    pub fn passes(&self, alpha: f64) -> bool {
+        self.p_value >= alpha
+    }
+}
+
+/// Pearson's chi-square test of counts against expected counts
+///
+/// Bins with an expected count of zero are skipped. Expected
+/// counts of at least 5 per bin are needed for a good p-value.
+pub fn chi_square(observed: &[u64], expected: &[f64]) -> TestResult {
+    let mut statistic = 0.0;
+    let mut bins = 0;
+    for (o, e) in observed.iter().zip(expected) {
+        if *e > 0.0 {
+            let diff = *o as f64 - e;
+            statistic += diff * diff / e;
+            bins += 1;
+        }
+    }
+    let dof = (bins as f64 - 1.0).max(1.0);
+    TestResult {
+        statistic,
+        p_value: upper_incomplete_gamma(dof / 2.0, statistic / 2.0),
+    }
+}
+
+/// Draw `samples` bin indices and test that every one
+/// of the `bins` is equally likely
+///
+/// Indices outside `0..bins` are counted against the fit.
+pub fn chi_square_uniform<F: FnMut() -> usize>(
+    bins: usize,
+    samples: usize,
+    mut f: F,
+) -> TestResult {
+    let mut observed = vec![0u64; bins];
+    let mut out_of_range = false;
+    for _ in 0..samples {
+        match observed.get_mut(f()) {
+            Some(count) => *count += 1,
+            None => out_of_range = true,
+        }
+    }
+    if out_of_range || bins == 0 {
+        // Out of range samples can't be expected, so fail outright
+        return TestResult {
+            statistic: f64::INFINITY,
+            p_value: 0.0,
+        };
+    }
+    let expected = vec![samples as f64 / bins as f64; bins];
+    chi_square(&observed, &expected)
+}
+
+/// The one-sample Kolmogorov-Smirnov test, comparing
+/// `samples` draws against a continuous CDF
+pub fn kolmogorov_smirnov<F, C>(samples: usize, mut f: F, cdf: C) -> TestResult
+where
+    F: FnMut() -> f64,
+    C: Fn(f64) -> f64,
+{
+    let mut values: Vec<f64> = (0..samples).map(|_| f()).collect();
+    values.sort_unstable_by(|a, b| a.total_cmp(b));
+    let n = values.len() as f64;
+    let mut statistic: f64 = 0.0;
+    for (i, v) in values.iter().enumerate() {
+        let c = cdf(*v);
+        statistic = statistic.max(c - i as f64 / n).max((i + 1) as f64 / n - c);
+    }
+    TestResult {
+        statistic,
+        p_value: kolmogorov_p(n, statistic),
+    }
+}
+
+// The longest slices whose every permutation is counted
+const SHUFFLE_FULL_LEN: usize = 5;
+// The slice length for which only each item's position is counted
+const SHUFFLE_POSITION_LEN: usize = 16;
+
+/// Check that a shuffle of a slice in place makes every
+/// permutation equally likely, panicking if not
+///
+/// Slices of 2 to 5 items are shuffled enough times to count
+/// every permutation, and a slice of 16 to count where each
+/// item lands. Each count is chi-square tested, and the whole
+/// check fails a correct shuffle at most a `tolerance` fraction of
+/// seeds, so 0.001 suits a test run from a fixed seed.
+///
+/// Panics too if the shuffle loses or duplicates an item.
+pub fn assert_shuffle_uniform<T, F>(rng: &mut SplittingRng<T>, mut shuffle: F, tolerance: f64)
+where
+    T: RngCore + SeedableRng,
+    F: FnMut(&mut SplittingRng<T>, &mut [usize]),
+{
+    // Split the tolerance evenly between the tests
+    let alpha = tolerance / (SHUFFLE_FULL_LEN - 1 + SHUFFLE_POSITION_LEN) as f64;
+    let mut run = |rng: &mut SplittingRng<T>, len: usize| {
+        let mut items: Vec<usize> = (0..len).collect();
+        shuffle(rng, &mut items);
+        let mut sorted = items.clone();
+        sorted.sort_unstable();
+        assert!(
+            sorted.iter().copied().eq(0..len),
+            "shuffle of {} items gave {:?}, not a permutation",
+            len,
+            items
+        );
+        items
+    };
+    for len in 2..=SHUFFLE_FULL_LEN {
+        let perms: usize = (1..=len).product();
+        let samples = 200 * perms;
+        let mut observed = vec![0u64; perms];
+        for _ in 0..samples {
+            observed[permutation_rank(&run(rng, len))] += 1;
+        }
+        let result = chi_square(&observed, &vec![200.0; perms]);
+        assert!(
+            result.passes(alpha),
+            "shuffles of {} items are not uniform: {:?}",
+            len,
+            result
+        );
+    }
+    let len = SHUFFLE_POSITION_LEN;
+    let samples = 250 * len;
+    let mut observed = vec![vec![0u64; len]; len];
+    for _ in 0..samples {
+        for (position, item) in run(rng, len).into_iter().enumerate() {
+            observed[position][item] += 1;
+        }
+    }
+    for (position, counts) in observed.iter().enumerate() {
+        let result = chi_square(counts, &vec![250.0; len]);
+        assert!(
+            result.passes(alpha),
+            "shuffles of {} items put uneven items at {}: {:?}",
+            len,
+            position,
+            result
+        );
+    }
+}
+
+// The Lehmer code of a permutation of 0..n, numbering
+// the permutations from 0 to n! - 1
+fn permutation_rank(items: &[usize]) -> usize {
+    items.iter().enumerate().fold(0, |rank, (i, item)| {
+        let smaller_later = items[i + 1..].iter().filter(|x| *x < item).count();
+        rank * (items.len() - i) + smaller_later
+    })
+}
+
+// The number of backend words the self-test reads
+const SELF_TEST_WORDS: usize = 1 << 14;
+
+/// The outcome of `self_test`, from the NIST monobit
+/// and runs tests on the raw bits of the backend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTest {
+    /// How many bits were tested
+    pub bits: u64,
+    /// How many of them were ones
+    pub ones: u64,
+    /// Whether there are as many ones as zeros
+    pub monobit: TestResult,
+    /// Whether the bits change as often as they should
+    pub runs: TestResult,
+}
+
+impl SelfTest {
+    /// Whether both tests pass at significance level `alpha`
+    pub fn passes(&self, alpha: f64) -> bool {
+        self.monobit.passes(alpha) && self.runs.passes(alpha)
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Run quick statistical checks on the backend, without
+    /// advancing this rng
+    ///
+    /// A backend seeded as this one was is read directly, skipping
+    /// any finalizer, and a million of its bits are put through
+    /// the monobit and runs tests. These only catch gross faults,
+    /// such as a counter or a stuck bit posing as a generator, and
+    /// prove nothing about a backend which passes. The result
+    /// depends only on the origin, so a check at startup fails the
+    /// same way every time. Passing at an `alpha` of 0.0001 is a
+    /// fair bar, failed by a sound backend for 1 origin in 5,000.
+    pub fn self_test(&self) -> SelfTest {
+        let mut backend: T = self.seeding.seed(self.origin);
+        let mut ones = 0;
+        let mut runs = 1;
+        let mut last = None;
+        for _ in 0..SELF_TEST_WORDS {
+            let word = backend.next_u64();
+            ones += word.count_ones() as u64;
+            // Each change between neighbouring bits starts a run
+            runs += ((word ^ (word >> 1)) & (u64::MAX >> 1)).count_ones() as u64;
+            if last.is_some_and(|bit| bit != word & 1) {
+                runs += 1;
+            }
+            last = Some(word >> 63);
+        }
+        let bits = (SELF_TEST_WORDS * 64) as u64;
+        let n = bits as f64;
+        let excess = (2.0 * ones as f64 - n).abs() / n.sqrt();
+        let monobit = TestResult {
+            statistic: excess,
+            p_value: erfc(excess / std::f64::consts::SQRT_2),
+        };
+        let pi = ones as f64 / n;
+        let runs = if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+            // Too unbalanced for the runs test to mean anything
+            TestResult {
+                statistic: runs as f64,
+                p_value: 0.0,
+            }
+        } else {
+            let spread = 2.0 * pi * (1.0 - pi);
+            TestResult {
+                statistic: runs as f64,
+                p_value: erfc((runs as f64 - n * spread).abs() / (spread * (2.0 * n).sqrt())),
+            }
+        };
+        SelfTest {
+            bits,
+            ones,
+            monobit,
+            runs,
+        }
+    }
+}
+
+/// Counts how often each value comes up
+///
+/// Useful for a quick look at whether some logic
+/// built on the rng is noticeably skewed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    counts: BTreeMap<u64, u64>,
+    total: u64,
+}
+
+/// An overview of a `Histogram`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    /// How many values were recorded
+    pub total: u64,
+    /// How many distinct values were recorded
+    pub distinct: usize,
+    /// The smallest value recorded
+    pub min: u64,
+    /// The largest value recorded
+    pub max: u64,
+    /// The mean of the values recorded
+    pub mean: f64,
+    /// The most common value and its count
+    pub most_common: (u64, u64),
+    /// The least common recorded value and its count
+    pub least_common: (u64, u64),
+    /// The gap between the most and least common counts,
+    /// as a fraction of the average count. Near zero means
+    /// the recorded values came up evenly
+    pub spread: f64,
+}
+
+impl Histogram {
+    /// Start with no values recorded
+    pub fn new() -> Self {
+        Histogram::default()
+    }
+
+    /// Record one value
+    pub fn record(&mut self, value: u64) {
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Record every value from an iterator
+    pub fn record_all<I: IntoIterator<Item = u64>>(&mut self, values: I) {
+        for value in values {
+            self.record(value);
+        }
+    }
+
+    /// How many times a value was recorded
+    pub fn count(&self, value: u64) -> u64 {
+        self.counts.get(&value).copied().unwrap_or(0)
+    }
+
+    /// How many values were recorded in all
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Each recorded value and its count, in order of value
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.counts.iter().map(|(v, c)| (*v, *c))
+    }
+
+    /// An overview of the counts, or `None` if nothing
+    /// has been recorded
+    pub fn summary(&self) -> Option<Summary> {
+        let (min, _) = self.counts.first_key_value()?;
+        let (max, _) = self.counts.last_key_value()?;
+        let mut most_common = (*min, 0);
+        let mut least_common = (*min, u64::MAX);
+        let mut sum = 0.0;
+        for (value, count) in self.buckets() {
+            sum += value as f64 * count as f64;
+            if count > most_common.1 {
+                most_common = (value, count);
+            }
+            if count < least_common.1 {
+                least_common = (value, count);
+            }
+        }
+        let average = self.total as f64 / self.counts.len() as f64;
+        Some(Summary {
+            total: self.total,
+            distinct: self.counts.len(),
+            min: *min,
+            max: *max,
+            mean: sum / self.total as f64,
+            most_common,
+            least_common,
+            spread: (most_common.1 - least_common.1) as f64 / average,
+        })
+    }
+
+    /// Test the counts against every value in `range`
+    /// being equally likely, including values never recorded
+    ///
+    /// Values recorded outside the range fail the test.
+    pub fn fit_uniform(&self, range: Range<u64>) -> TestResult {
+        let bins = range.end.saturating_sub(range.start) as usize;
+        let mut values = self
+            .buckets()
+            .flat_map(|(v, c)| std::iter::repeat_n(v, c as usize));
+        chi_square_uniform(bins, self.total as usize, || match values.next() {
+            Some(v) if range.contains(&v) => (v - range.start) as usize,
+            _ => bins,
+        })
+    }
+}
+
+/// The asymptotic Kolmogorov distribution, with
+/// Stephens' correction for small samples
+fn kolmogorov_p(n: f64, d: f64) -> f64 {
+    let root = n.sqrt();
+    let lambda = (root + 0.12 + 0.11 / root) * d;
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    let mut sign = 1.0;
+    for j in 1..=100 {
+        let term = sign * (-2.0 * (j * j) as f64 * lambda * lambda).exp();
+        sum += term;
+        if term.abs() < 1e-12 {
+            break;
+        }
+        sign = -sign;
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// The complementary error function, for `x >= 0`
+fn erfc(x: f64) -> f64 {
+    upper_incomplete_gamma(0.5, x * x)
+}
+
+/// The regularized upper incomplete gamma function Q(a, x)
+fn upper_incomplete_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if !x.is_finite() {
+        return 0.0;
+    }
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // The series for P converges quickly here
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut ap = a;
+        for _ in 0..1000 {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        (1.0 - sum * prefix).clamp(0.0, 1.0)
+    } else {
+        // Lentz's continued fraction for Q
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (prefix * h).clamp(0.0, 1.0)
+    }
+}
+
+/// The log of the gamma function, by the Lanczos approximation
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 8] = [
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflect, as the approximation only holds for x >= 0.5
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = 0.999_999_999_999_809_9;
+    for (i, c) in COEFFS.iter().enumerate() {
+        sum += c / (x + i as f64 + 1.0);
+    }
+    let t = x + COEFFS.len() as f64 - 0.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SplittingRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_goodness_of_fit() {
+        // Known chi-square critical value: 16.919 at 9 dof is p = 0.05
+        let q = upper_incomplete_gamma(4.5, 16.919 / 2.0);
+        assert!((q - 0.05).abs() < 1e-3, "{}", q);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-10);
+
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let fair = chi_square_uniform(6, 60000, || rng.fair_roll(6) as usize);
+        assert!(fair.passes(0.01), "{:?}", fair);
+        let loaded = chi_square_uniform(6, 60000, || (rng.fair_roll(7) as usize).min(5));
+        assert!(!loaded.passes(0.01), "{:?}", loaded);
+
+        let uniform = kolmogorov_smirnov(5000, || rng.get_f64(), |x| x.clamp(0.0, 1.0));
+        assert!(uniform.passes(0.01), "{:?}", uniform);
+        let squared = kolmogorov_smirnov(5000, || rng.get_f64().powi(2), |x| x.clamp(0.0, 1.0));
+        assert!(!squared.passes(0.01), "{:?}", squared);
+    }
+
+    #[test]
+    fn test_assert_shuffle_uniform() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        assert_shuffle_uniform(&mut rng, |rng, items| rng.shuffle_slice(items), 0.001);
+        // Swapping each item with any position is a classic mistake
+        let naive = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            assert_shuffle_uniform(
+                &mut rng,
+                |rng, items| {
+                    for i in 0..items.len() {
+                        let j = rng.fair_roll(items.len() as u32) as usize;
+                        items.swap(i, j);
+                    }
+                },
+                0.001,
+            )
+        }));
+        assert!(naive.is_err());
+        let mut ranks: Vec<usize> = [[0, 1, 2], [0, 2, 1], [2, 1, 0]]
+            .iter()
+            .map(|p| permutation_rank(p))
+            .collect();
+        ranks.push(permutation_rank(&[3, 2, 1, 0]));
+        assert_eq!(ranks, vec![0, 1, 5, 23]);
+    }
+
+    // A counter, which no one should mistake for a generator
+    struct Counter(u64);
+
+    impl RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(1);
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(8) {
+                let bytes = self.next_u64().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl SeedableRng for Counter {
+        type Seed = [u8; 8];
+
+        fn from_seed(seed: [u8; 8]) -> Self {
+            Counter(u64::from_le_bytes(seed))
+        }
+    }
+
+    #[test]
+    fn test_self_test() {
+        for origin in [0, 1, 12345] {
+            let report = SplittingRng::<Xoshiro256StarStar>::new(origin).self_test();
+            assert!(report.passes(0.0001), "{:?}", report);
+            assert_eq!(report.bits, 1 << 20);
+        }
+        let report = SplittingRng::<Counter>::new(12345).self_test();
+        assert!(!report.passes(0.0001), "{:?}", report);
+        assert!(report.monobit.p_value < 1e-10);
+    }
+
+    #[test]
+    fn test_histogram() {
+        use crate::compat::BiasedRoll;
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut histogram = Histogram::new();
+        histogram.record_all((0..60000).map(|_| BiasedRoll::biased_roll(&mut rng, 6) as u64));
+        let summary = histogram.summary().unwrap();
+        assert_eq!(summary.total, 60000);
+        assert_eq!((summary.min, summary.max, summary.distinct), (0, 5, 6));
+        assert!((summary.mean - 2.5).abs() < 0.05, "{:?}", summary);
+        assert!(summary.spread < 0.1, "{:?}", summary);
+        assert!(histogram.fit_uniform(0..6).passes(0.01));
+        assert!(!histogram.fit_uniform(0..7).passes(0.01));
+        assert!(!histogram.fit_uniform(1..6).passes(0.01));
+        assert_eq!(Histogram::new().summary(), None);
+    }
+}
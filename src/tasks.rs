@@ -0,0 +1,100 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Handing out rngs to tasks by a stable key.
+//!
+//! Splitting a child for each task as it spawns ties its stream
+//! to the spawn order, which an async runtime is free to change
+//! between runs. Keying each task's rng by what the task is,
+//! such as a customer id, keeps every stream the same however
+//! the tasks are scheduled.
+
+use crate::keyed::Key;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+
+/// Gives out a child rng for each task key
+///
+/// Only needs `&self`, so one seeder can be shared between
+/// tasks and threads in an `Arc`. Asking twice for the same
+/// key gives the same stream, so a restarted task replays.
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::tasks::TaskSeeder;
+/// use splittable_random::SplittingRng;
+/// use std::sync::Arc;
+///
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let seeder = Arc::new(TaskSeeder::new(&mut rng));
+/// let handles: Vec<_> = (0..4u32)
+///     .map(|customer| {
+///         let seeder = seeder.clone();
+///         std::thread::spawn(move || seeder.rng_for(("customer", customer)).get_u64())
+///     })
+///     .collect();
+/// let values: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+/// assert_eq!(values[2], seeder.rng_for(("customer", 2u32)).get_u64());
+/// ```
+#[derive(Debug)]
+pub struct TaskSeeder<T: RngCore + SeedableRng> {
+    base: SplittingRng<T>,
+}
+
+impl<T: RngCore + SeedableRng> TaskSeeder<T> {
+    /// Create a seeder from a child split off from `rng`
+    pub fn new(rng: &mut SplittingRng<T>) -> Self {
+        TaskSeeder { base: rng.split() }
+    }
+
+    /// Create a seeder whose rngs are keyed from `rng` itself,
+    /// without advancing it
+    pub fn from_rng(rng: SplittingRng<T>) -> Self {
+        TaskSeeder { base: rng }
+    }
+
+    /// The rng for the task with the given key, as `split_keyed`
+    pub fn rng_for<K: Key>(&self, key: K) -> SplittingRng<T> {
+        self.base.split_keyed(key)
+    }
+
+    /// The origin the task rngs are keyed from
+    pub fn origin(&self) -> u64 {
+        self.base.origin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::sync::Arc;
+
+    fn run(order: &[u32]) -> Vec<(u32, u64)> {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let seeder = Arc::new(TaskSeeder::new(&mut rng));
+        let handles: Vec<_> = order
+            .iter()
+            .map(|&task| {
+                let seeder = seeder.clone();
+                std::thread::spawn(move || (task, seeder.rng_for(task).get_u64()))
+            })
+            .collect();
+        let mut results: Vec<(u32, u64)> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        results.sort_unstable();
+        results
+    }
+
+    #[test]
+    fn test_spawn_order_does_not_matter() {
+        let a = run(&[0, 1, 2, 3, 4]);
+        let b = run(&[3, 1, 4, 0, 2]);
+        assert_eq!(a, b);
+        assert_ne!(a[0].1, a[1].1);
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let seeder = TaskSeeder::from_rng(rng.clone());
+        assert_eq!(seeder.origin(), rng.origin());
+        assert_eq!(seeder.rng_for(9u8).origin(), rng.split_keyed(9u8).origin());
+    }
+}
@@ -0,0 +1,558 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random strings for test data.
+//!
+//! Every character is picked with `fair_roll`,
+//! so each one in the charset is equally likely.
+//!
+//! Unicode text is drawn from a fixed table of scripts
+//! rather than the standard library's character properties,
+//! which change with each Unicode version. Every character
+//! in the table is a whole grapheme with a known width.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::ops::RangeInclusive;
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// The UTF-16 surrogates, which are not valid chars
+const SURROGATE_START: u32 = 0xD800;
+const SURROGATE_LEN: u32 = 0x800;
+
+/// The most entropy `get_token` draws a token with, far beyond
+/// any real use, so a bad argument can't exhaust memory
+pub const MAX_TOKEN_BITS: f64 = 65536.0;
+
+/// Characters which are easily mistaken for one another
+const AMBIGUOUS: &str = "0O1lI|";
+
+/// The kinds of character Unicode text can be drawn from,
+/// after the Unicode general categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Letters of several alphabets, syllabaries and ideographs
+    Letter,
+    /// Decimal digits of several scripts
+    Number,
+    /// Punctuation such as `?`, `«` and `。`
+    Punctuation,
+    /// Symbols such as `+`, `©` and `€`
+    Symbol,
+    /// A plain space
+    Space,
+}
+
+// A script or family of characters, each a single grapheme
+// taking `width` columns in a monospaced terminal
+struct Group {
+    category: Category,
+    width: usize,
+    ranges: &'static [(char, char)],
+}
+
+const GROUPS: &[Group] = &[
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[
+            ('A', 'Z'),
+            ('a', 'z'),
+            ('À', 'Ö'),
+            ('Ø', 'ö'),
+            ('ø', 'ÿ'),
+            ('Ā', 'ſ'),
+        ],
+    },
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[('Α', 'Ρ'), ('Σ', 'Ω'), ('α', 'ω')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[('А', 'я')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[('Ա', 'Ֆ'), ('ա', 'ֆ')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[('א', 'ת')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 1,
+        ranges: &[('ა', 'ჺ')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 2,
+        ranges: &[('ぁ', 'ゖ'), ('ァ', 'ヺ')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 2,
+        ranges: &[('一', '龥')],
+    },
+    Group {
+        category: Category::Letter,
+        width: 2,
+        ranges: &[('가', '힣')],
+    },
+    Group {
+        category: Category::Number,
+        width: 1,
+        ranges: &[('0', '9')],
+    },
+    Group {
+        category: Category::Number,
+        width: 1,
+        ranges: &[('٠', '٩')],
+    },
+    Group {
+        category: Category::Number,
+        width: 1,
+        ranges: &[('०', '९')],
+    },
+    Group {
+        category: Category::Number,
+        width: 2,
+        ranges: &[('０', '９')],
+    },
+    Group {
+        category: Category::Punctuation,
+        width: 1,
+        ranges: &[
+            ('!', '#'),
+            ('%', '*'),
+            (',', '/'),
+            (':', ';'),
+            ('?', '@'),
+            ('[', ']'),
+            ('_', '_'),
+            ('{', '{'),
+            ('}', '}'),
+        ],
+    },
+    Group {
+        category: Category::Punctuation,
+        width: 1,
+        ranges: &[
+            ('¡', '¡'),
+            ('«', '«'),
+            ('»', '»'),
+            ('¿', '¿'),
+            ('–', '—'),
+            ('‘', '’'),
+            ('“', '”'),
+            ('…', '…'),
+        ],
+    },
+    Group {
+        category: Category::Punctuation,
+        width: 2,
+        ranges: &[('、', '。')],
+    },
+    Group {
+        category: Category::Symbol,
+        width: 1,
+        ranges: &[
+            ('$', '$'),
+            ('+', '+'),
+            ('<', '>'),
+            ('^', '^'),
+            ('`', '`'),
+            ('|', '|'),
+            ('~', '~'),
+        ],
+    },
+    Group {
+        category: Category::Symbol,
+        width: 1,
+        ranges: &[
+            ('¢', '¥'),
+            ('©', '©'),
+            ('®', '®'),
+            ('°', '±'),
+            ('×', '×'),
+            ('÷', '÷'),
+        ],
+    },
+    Group {
+        category: Category::Symbol,
+        width: 1,
+        ranges: &[('€', '€'), ('←', '↙')],
+    },
+    Group {
+        category: Category::Space,
+        width: 1,
+        ranges: &[(' ', ' ')],
+    },
+];
+
+impl Group {
+    fn len(&self) -> u32 {
+        self.ranges
+            .iter()
+            .map(|(a, b)| *b as u32 - *a as u32 + 1)
+            .sum()
+    }
+
+    fn nth(&self, mut n: u32) -> char {
+        for (a, b) in self.ranges {
+            let len = *b as u32 - *a as u32 + 1;
+            if n < len {
+                return char::from_u32(*a as u32 + n).expect("Groups hold no surrogates");
+            }
+            n -= len;
+        }
+        unreachable!("Picked past the end of a group")
+    }
+}
+
+/// A set of characters to build strings from
+///
+/// ```
+/// use splittable_random::text::Charset;
+///
+/// let code = Charset::new()
+///     .range('A'..='Z')
+///     .range('0'..='9')
+///     .exclude_ambiguous();
+/// assert!(!code.contains('O'));
+/// assert_eq!(code.len(), 32);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Charset {
+    // Kept sorted and free of duplicates
+    chars: Vec<char>,
+}
+
+impl Charset {
+    /// Start with an empty set
+    pub fn new() -> Self {
+        Charset::default()
+    }
+
+    /// ASCII letters and digits
+    pub fn alphanumeric() -> Self {
+        Charset::new()
+            .range('A'..='Z')
+            .range('a'..='z')
+            .range('0'..='9')
+    }
+
+    /// Add every character in a range
+    pub fn range(mut self, range: RangeInclusive<char>) -> Self {
+        self.chars.extend(range);
+        self.normalize()
+    }
+
+    /// Add each character of a string
+    pub fn chars(mut self, chars: &str) -> Self {
+        self.chars.extend(chars.chars());
+        self.normalize()
+    }
+
+    /// Remove each character of a string
+    /// Only affects the characters added so far
+    pub fn exclude(mut self, chars: &str) -> Self {
+        self.chars.retain(|c| !chars.contains(*c));
+        self
+    }
+
+    /// Remove characters which are easily confused
+    /// when read back, such as `0`, `O`, `1` and `l`
+    pub fn exclude_ambiguous(self) -> Self {
+        self.exclude(AMBIGUOUS)
+    }
+
+    /// Whether the set holds a character
+    pub fn contains(&self, c: char) -> bool {
+        self.chars.binary_search(&c).is_ok()
+    }
+
+    /// How many characters are in the set
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Whether the set has no characters
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// The entropy, in bits, of one character
+    /// drawn uniformly from this set
+    pub fn bits_per_char(&self) -> f64 {
+        if self.chars.is_empty() {
+            0.0
+        } else {
+            (self.chars.len() as f64).log2()
+        }
+    }
+
+    fn normalize(mut self) -> Self {
+        self.chars.sort_unstable();
+        self.chars.dedup();
+        self
+    }
+}
+
+/// A generated token and the entropy it was drawn with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The token itself
+    pub value: String,
+    /// The entropy of the token in bits, assuming
+    /// every character was drawn uniformly and independently
+    pub entropy_bits: f64,
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a token with at least `min_bits` of entropy
+    /// from a charset, or `None` if the charset has fewer
+    /// than two characters and so can't provide any
+    ///
+    /// Also `None` if `min_bits` is negative, NaN or above
+    /// `MAX_TOKEN_BITS`, without taking any steps.
+    ///
+    /// The entropy is only what the charset and length offer.
+    /// The token can never be less predictable than the stream
+    /// it comes from, and this rng is not cryptographically
+    /// secure: anyone who learns the origin can recreate every
+    /// token. Use these for test fixtures, not real credentials.
+    pub fn get_token(&mut self, charset: &Charset, min_bits: f64) -> Option<Token> {
+        if !(0.0..=MAX_TOKEN_BITS).contains(&min_bits) || charset.len() < 2 {
+            return None;
+        }
+        let per_char = charset.bits_per_char();
+        let len = (min_bits / per_char).ceil() as usize;
+        Some(Token {
+            value: self.get_string_from(charset, len),
+            entropy_bits: len as f64 * per_char,
+        })
+    }
+
+    /// Provide a string of `len` characters from a charset
+    /// An empty charset gives an empty string
+    pub fn get_string_from(&mut self, charset: &Charset, len: usize) -> String {
+        if charset.is_empty() {
+            return String::new();
+        }
+        (0..len)
+            .map(|_| charset.chars[self.fair_roll(charset.len() as u32) as usize])
+            .collect()
+    }
+
+    /// Provide a string of `len` lowercase ASCII letters
+    ///
+    /// Safe to use as a filename or identifier anywhere
+    pub fn get_string(&mut self, len: usize) -> String {
+        self.string_from_ascii(LOWERCASE, len)
+    }
+
+    /// Provide a string of `len` ASCII letters and digits
+    pub fn get_alphanumeric(&mut self, len: usize) -> String {
+        self.string_from_ascii(ALPHANUMERIC, len)
+    }
+
+    /// Provide a char uniformly from a range, or `None`
+    /// if the range is empty
+    ///
+    /// Ranges spanning the surrogate gap skip over it,
+    /// so every valid char in the range is equally likely.
+    pub fn get_char_in(&mut self, range: RangeInclusive<char>) -> Option<char> {
+        let (start, end) = (*range.start() as u32, *range.end() as u32);
+        if start > end {
+            return None;
+        }
+        let spans_gap = start < SURROGATE_START && end > SURROGATE_START;
+        let count = end - start + 1 - if spans_gap { SURROGATE_LEN } else { 0 };
+        let mut value = start + self.fair_roll(count);
+        if spans_gap && value >= SURROGATE_START {
+            value += SURROGATE_LEN;
+        }
+        char::from_u32(value)
+    }
+
+    /// Provide any valid char, uniformly over
+    /// all Unicode scalar values
+    pub fn get_char(&mut self) -> char {
+        self.get_char_in('\0'..=char::MAX)
+            .expect("The full char range is never empty")
+    }
+
+    /// Provide text of the given categories, `width` columns wide
+    /// in a monospaced terminal
+    ///
+    /// Each character picks a category, then a script of that
+    /// category, then a character of that script, so rare scripts
+    /// show up as often as common ones. Every character is its own
+    /// grapheme, with no control or combining characters, and
+    /// ideographs take two columns. No categories gives an empty string.
+    pub fn get_unicode_text(&mut self, categories: &[Category], width: usize) -> String {
+        let mut unique: Vec<Category> = Vec::new();
+        for c in categories {
+            if !unique.contains(c) {
+                unique.push(*c);
+            }
+        }
+        let mut text = String::new();
+        let mut left = width;
+        while left > 0 {
+            let fits = |g: &&Group| g.width <= left;
+            let open: Vec<Category> = unique
+                .iter()
+                .copied()
+                .filter(|c| GROUPS.iter().filter(fits).any(|g| g.category == *c))
+                .collect();
+            if open.is_empty() {
+                break;
+            }
+            let category = open[self.fair_roll(open.len() as u32) as usize];
+            let groups: Vec<&Group> = GROUPS
+                .iter()
+                .filter(fits)
+                .filter(|g| g.category == category)
+                .collect();
+            let group = groups[self.fair_roll(groups.len() as u32) as usize];
+            text.push(group.nth(self.fair_roll(group.len())));
+            left -= group.width;
+        }
+        text
+    }
+
+    fn string_from_ascii(&mut self, charset: &[u8], len: usize) -> String {
+        (0..len)
+            .map(|_| charset[self.fair_roll(charset.len() as u32) as usize] as char)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_strings() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let s = rng.get_string(32);
+        assert_eq!(s.len(), 32);
+        assert!(s.bytes().all(|b| b.is_ascii_lowercase()));
+        let s = rng.get_alphanumeric(10000);
+        assert!(s.bytes().all(|b| b.is_ascii_alphanumeric()));
+        // Digits are 10 of the 62 characters
+        let digits = s.bytes().filter(|b| b.is_ascii_digit()).count();
+        let frac = digits as f64 / 10000.0;
+        assert!((frac - 10.0 / 62.0).abs() < 0.01, "{}", frac);
+    }
+
+    #[test]
+    fn test_charset() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let hex = Charset::new().range('0'..='9').chars("abcdefa");
+        assert_eq!(hex.len(), 16);
+        let s = rng.get_string_from(&hex, 64);
+        assert_eq!(s.chars().count(), 64);
+        assert!(s.chars().all(|c| c.is_ascii_hexdigit()));
+        let greek = Charset::new().range('α'..='ω').exclude("ς");
+        assert!(rng
+            .get_string_from(&greek, 100)
+            .chars()
+            .all(|c| greek.contains(c)));
+        assert_eq!(Charset::alphanumeric().exclude_ambiguous().len(), 57);
+        assert_eq!(rng.get_string_from(&Charset::new(), 5), "");
+    }
+
+    #[test]
+    fn test_chars_skip_surrogates() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        // Exactly two valid chars either side of the gap
+        let range = '\u{D7FF}'..='\u{E000}';
+        let mut low = 0;
+        for _ in 0..10000 {
+            match rng.get_char_in(range.clone()).unwrap() {
+                '\u{D7FF}' => low += 1,
+                c => assert_eq!(c, '\u{E000}'),
+            }
+        }
+        assert!((low as f64 / 10000.0 - 0.5).abs() < 0.02);
+        assert_eq!(rng.get_char_in('b'..='a'), None);
+        assert_eq!(rng.get_char_in('x'..='x'), Some('x'));
+        let any: String = (0..1000).map(|_| rng.get_char()).collect();
+        assert_eq!(any.chars().count(), 1000);
+    }
+
+    #[test]
+    fn test_unicode_text() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let group_of = |c: char| {
+            GROUPS
+                .iter()
+                .find(|g| g.ranges.iter().any(|(a, b)| (*a..=*b).contains(&c)))
+                .unwrap()
+        };
+        let mut wide = 0;
+        for _ in 0..200 {
+            let text = rng.get_unicode_text(&[Category::Letter, Category::Number], 15);
+            let width: usize = text.chars().map(|c| group_of(c).width).sum();
+            assert_eq!(width, 15);
+            assert!(text.chars().all(|c| c.is_alphanumeric()));
+            wide += text.chars().filter(|c| group_of(*c).width == 2).count();
+        }
+        assert!(wide > 100);
+        let punct = rng.get_unicode_text(&[Category::Punctuation, Category::Symbol], 100);
+        assert!(punct
+            .chars()
+            .all(|c| !c.is_alphanumeric() && !c.is_control()));
+        assert_eq!(rng.get_unicode_text(&[Category::Space], 3), "   ");
+        assert_eq!(rng.get_unicode_text(&[], 3), "");
+        // Counting through the ranges reaches the last char
+        for group in GROUPS {
+            assert_eq!(group.nth(group.len() - 1), group.ranges.last().unwrap().1);
+        }
+    }
+
+    #[test]
+    fn test_token_entropy() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let hex = Charset::new().range('0'..='9').range('a'..='f');
+        let token = rng.get_token(&hex, 126.0).unwrap();
+        assert_eq!(token.value.len(), 32);
+        assert_eq!(token.entropy_bits, 128.0);
+        let token = rng.get_token(&Charset::alphanumeric(), 64.0).unwrap();
+        assert_eq!(token.value.len(), 11);
+        assert!(token.entropy_bits >= 64.0);
+        assert_eq!(rng.get_token(&Charset::new().chars("a"), 8.0), None);
+    }
+
+    #[test]
+    fn test_token_rejects_bad_bits() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for bits in [f64::INFINITY, f64::NAN, -1.0, 1e18, MAX_TOKEN_BITS + 1.0] {
+            assert_eq!(
+                rng.get_token(&Charset::alphanumeric(), bits),
+                None,
+                "{}",
+                bits
+            );
+        }
+        assert_eq!(rng.steps(), 0);
+        let longest = rng.get_token(&Charset::alphanumeric(), MAX_TOKEN_BITS);
+        assert!(longest.unwrap().entropy_bits >= MAX_TOKEN_BITS);
+        assert_eq!(
+            rng.get_token(&Charset::alphanumeric(), 0.0).unwrap().value,
+            ""
+        );
+    }
+}
@@ -0,0 +1,168 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Weighted tables of text, for names, dialogue lines
+//! and other procedural content.
+//!
+//! Tables can be written in code or loaded from plain
+//! text, one `weight,entry` per line, so writers can edit
+//! them without touching the code that rolls on them.
+
+use crate::distributions::{DiscreteDistribution, Distribution, DistributionError};
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// The reasons a text table may fail to build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// A line did not start with a weight and a comma,
+    /// numbered from 1
+    BadLine(usize),
+    /// The weights were invalid
+    Weights(DistributionError),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::BadLine(line) => write!(f, "line {} is not weight,entry", line),
+            TableError::Weights(e) => write!(f, "bad weights: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+impl From<DistributionError> for TableError {
+    fn from(e: DistributionError) -> Self {
+        TableError::Weights(e)
+    }
+}
+
+/// A weighted list of strings to pick from
+///
+/// The cumulative weights are worked out once, so each
+/// pick is one step and a binary search.
+///
+/// ```
+/// use rand_xoshiro::Xoshiro256StarStar;
+/// use splittable_random::texttable::TextTable;
+/// use splittable_random::SplittingRng;
+///
+/// let greetings = TextTable::parse(
+///     "# Said by shopkeepers
+///      3,Welcome!
+///      1,Oh, it's you again.",
+/// )
+/// .unwrap();
+/// let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+/// let line = greetings.pick(&mut rng);
+/// assert!(line == "Welcome!" || line == "Oh, it's you again.");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextTable {
+    entries: Vec<String>,
+    weights: Vec<f64>,
+    dist: DiscreteDistribution,
+}
+
+impl TextTable {
+    /// A table of entries with their weights
+    pub fn new<S: Into<String>>(
+        entries: impl IntoIterator<Item = (S, f64)>,
+    ) -> Result<Self, TableError> {
+        let (entries, weights): (Vec<String>, Vec<f64>) =
+            entries.into_iter().map(|(s, w)| (s.into(), w)).unzip();
+        let dist = DiscreteDistribution::new(&weights)?;
+        Ok(TextTable {
+            entries,
+            weights,
+            dist,
+        })
+    }
+
+    /// A table where every entry is equally likely
+    pub fn uniform<S: Into<String>>(
+        entries: impl IntoIterator<Item = S>,
+    ) -> Result<Self, TableError> {
+        Self::new(entries.into_iter().map(|s| (s, 1.0)))
+    }
+
+    /// Load a table from text, with one `weight,entry` per line
+    ///
+    /// Everything after the first comma is the entry, so entries
+    /// may hold commas themselves, and both are trimmed of
+    /// whitespace. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn parse(text: &str) -> Result<Self, TableError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (weight, entry) = line
+                .split_once(',')
+                .and_then(|(w, e)| Some((w.trim().parse::<f64>().ok()?, e.trim())))
+                .ok_or(TableError::BadLine(i + 1))?;
+            entries.push((entry, weight));
+        }
+        Self::new(entries)
+    }
+
+    /// Pick an entry, each in proportion to its weight
+    pub fn pick<T: RngCore + SeedableRng>(&self, rng: &mut SplittingRng<T>) -> &str {
+        &self.entries[self.dist.sample(rng)]
+    }
+
+    /// The entries with their weights, in order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, f64)> + '_ {
+        self.entries
+            .iter()
+            .map(|s| s.as_str())
+            .zip(self.weights.iter().copied())
+    }
+
+    /// The number of entries, which is never zero
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries, which is never true,
+    /// as a table without weights fails to build
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_text_tables() {
+        let table = TextTable::parse("\n# Names\n3, Ada\n1,Grace, the admiral\n0,Never\n").unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.entries().nth(1), Some(("Grace, the admiral", 1.0)));
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let iter = 10000;
+        let mut ada = 0;
+        for _ in 0..iter {
+            match table.pick(&mut rng) {
+                "Ada" => ada += 1,
+                other => assert_eq!(other, "Grace, the admiral"),
+            }
+        }
+        let frac = ada as f64 / iter as f64;
+        assert!((frac - 0.75).abs() < 0.015, "{}", frac);
+        assert_eq!(TextTable::parse("1,a\nb"), Err(TableError::BadLine(2)));
+        assert_eq!(
+            TextTable::parse("# nothing"),
+            Err(TableError::Weights(DistributionError::NoWeights))
+        );
+        assert_eq!(TextTable::uniform(["x", "y"]).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,318 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Random durations and timestamps, for delays
+//! and jitter in simulators and retry logic, and
+//! for event times in test fixtures.
+//!
+//! Jittered exponential backoff schedules come from a
+//! `Backoff`, for simulating clients which retry.
+//!
+//! Timestamps from `chrono` and `time` are supported
+//! behind features of the same name.
+
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
+
+pub(crate) const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A point in time which can be drawn
+/// uniformly from a range with `get_time_in`
+pub trait TimePoint: Sized {
+    /// How many of the finest units of this type lie
+    /// between `start` and `self`, where `start <= self`
+    fn units_since(&self, start: &Self) -> u128;
+
+    /// Step forward from `self` by a number of units
+    /// which is known to stay within range
+    fn add_units(&self, units: u128) -> Self;
+}
+
+impl TimePoint for Duration {
+    fn units_since(&self, start: &Self) -> u128 {
+        (*self - *start).as_nanos()
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + from_nanos(units)
+    }
+}
+
+impl TimePoint for SystemTime {
+    fn units_since(&self, start: &Self) -> u128 {
+        self.duration_since(*start).unwrap_or_default().as_nanos()
+    }
+
+    fn add_units(&self, units: u128) -> Self {
+        *self + from_nanos(units)
+    }
+}
+
+pub(crate) fn from_nanos(nanos: u128) -> Duration {
+    Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    )
+}
+
+/// How a `Backoff` randomizes each delay
+///
+/// These are the schemes compared in the AWS Architecture
+/// Blog's "Exponential Backoff And Jitter", where `exp` is
+/// `base * 2^attempt`, up to the cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BackoffJitter {
+    /// Uniform in `[0, exp]`
+    #[default]
+    Full,
+    /// Half of `exp`, plus uniform in `[0, exp / 2]`
+    Equal,
+    /// Uniform in `[base, 3 * previous delay]`, up to the cap,
+    /// which spreads clients out without counting attempts
+    Decorrelated,
+}
+
+/// An endless schedule of jittered exponential backoff delays
+///
+/// Owns a child split from the rng it was made with, so every
+/// simulated client can take its own schedule from one seed.
+/// The delays are drawn to the nanosecond.
+#[derive(Debug, Clone)]
+pub struct Backoff<T: RngCore + SeedableRng> {
+    rng: SplittingRng<T>,
+    base: Duration,
+    cap: Duration,
+    jitter: BackoffJitter,
+    attempt: u32,
+    last: Duration,
+}
+
+impl<T: RngCore + SeedableRng> Backoff<T> {
+    /// Back off from `base`, doubling each attempt, up to `cap`
+    pub fn new(
+        rng: &mut SplittingRng<T>,
+        base: Duration,
+        cap: Duration,
+        jitter: BackoffJitter,
+    ) -> Self {
+        Backoff {
+            rng: rng.split(),
+            base,
+            cap,
+            jitter,
+            attempt: 0,
+            last: base,
+        }
+    }
+
+    /// How many delays have been given since the start or a reset
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Start again from the first attempt, as after a success
+    ///
+    /// The rng carries on, so the next delays are fresh ones.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+        self.last = self.base;
+    }
+
+    /// Provide the delay before the next retry
+    pub fn next_delay(&mut self) -> Duration {
+        let base = self.base.as_nanos();
+        let cap = self.cap.as_nanos();
+        let exp = base.saturating_mul(1 << self.attempt.min(127)).min(cap);
+        let delay = match self.jitter {
+            BackoffJitter::Full => self.rng.below_u128(exp + 1),
+            BackoffJitter::Equal => exp - exp / 2 + self.rng.below_u128(exp / 2 + 1),
+            BackoffJitter::Decorrelated => {
+                let high = self.last.as_nanos().saturating_mul(3).max(base);
+                (base + self.rng.below_u128(high - base + 1)).min(cap)
+            }
+        };
+        self.attempt = self.attempt.saturating_add(1);
+        self.last = from_nanos(delay);
+        self.last
+    }
+}
+
+impl<T: RngCore + SeedableRng> Iterator for Backoff<T> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        Some(self.next_delay())
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Provide a duration uniformly within a range,
+    /// to the nanosecond
+    ///
+    /// An empty range gives its start
+    pub fn get_duration(&mut self, range: Range<Duration>) -> Duration {
+        self.get_time_in(range)
+    }
+
+    /// Provide a time uniformly within a range, such as
+    /// a date, a timestamp or a time of day
+    ///
+    /// Dates are drawn by the day, and everything else by
+    /// its finest resolution. An empty range gives its start
+    pub fn get_time_in<P: TimePoint + PartialOrd>(&mut self, range: Range<P>) -> P {
+        if range.end <= range.start {
+            return range.start;
+        }
+        let span = range.end.units_since(&range.start);
+        range.start.add_units(self.below_u128(span))
+    }
+
+    /// Provide `n` offsets uniformly within a window, in order,
+    /// as the arrival times of events in a load test
+    ///
+    /// Each offset is drawn as `get_duration(Duration::ZERO..window)`
+    /// would, to the nanosecond, then they are sorted, which gives
+    /// the uniform order statistics exactly. An empty window puts
+    /// every event at zero.
+    pub fn spread_events(&mut self, n: usize, window: Duration) -> Vec<Duration> {
+        let mut times: Vec<Duration> = (0..n)
+            .map(|_| self.get_duration(Duration::ZERO..window))
+            .collect();
+        times.sort_unstable();
+        times
+    }
+
+    /// Perturb a value by up to `fraction` of it either way,
+    /// uniformly in `value * (1 ± fraction)`
+    ///
+    /// The fraction is taken as its magnitude, so a negative
+    /// one jitters just as a positive one does.
+    pub fn jitter(&mut self, value: f64, fraction: f64) -> f64 {
+        value * (1.0 + fraction.abs() * (2.0 * self.get_f64() - 1.0))
+    }
+
+    /// Perturb a duration by up to `fraction` of it either way,
+    /// uniformly to the nanosecond
+    ///
+    /// Every nanosecond from `d * (1 - fraction)` to
+    /// `d * (1 + fraction)` inclusive is equally likely. The
+    /// fraction is clamped to [0, 1], so it never goes negative,
+    /// and the result saturates at `Duration::MAX`.
+    pub fn jitter_duration(&mut self, d: Duration, fraction: f64) -> Duration {
+        let nanos = d.as_nanos();
+        let spread = (nanos as f64 * fraction.clamp(0.0, 1.0)) as u128;
+        let spread = spread.min(nanos);
+        let offset = self.below_u128(2 * spread + 1);
+        let low = nanos - spread;
+        let max = Duration::MAX.as_nanos();
+        from_nanos((low + offset).min(max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_get_duration() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let range = Duration::from_millis(100)..Duration::from_millis(300);
+        let iter = 10000;
+        let mut acc = Duration::ZERO;
+        for _ in 0..iter {
+            let d = rng.get_duration(range.clone());
+            assert!(range.contains(&d));
+            acc += d;
+        }
+        let avg = acc.as_secs_f64() / iter as f64;
+        assert!((avg - 0.2).abs() < 0.002, "{}", avg);
+        // Spans wider than u64 nanoseconds still work
+        let huge = Duration::ZERO..Duration::MAX;
+        assert!(rng.get_duration(huge) > Duration::from_secs(1));
+        let empty = Duration::from_secs(1)..Duration::from_secs(1);
+        assert_eq!(rng.get_duration(empty), Duration::from_secs(1));
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = now + Duration::from_secs(60);
+        assert!((now..later).contains(&rng.get_time_in(now..later)));
+    }
+
+    #[test]
+    fn test_spread_events() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let window = Duration::from_secs(60);
+        let times = rng.spread_events(6000, window);
+        assert_eq!(times.len(), 6000);
+        assert!(times.windows(2).all(|w| w[0] <= w[1]));
+        assert!(times[5999] < window);
+        // About a tenth of the events fall in each tenth of the window
+        let early = times.iter().filter(|t| **t < window / 10).count();
+        assert!((early as f64 - 600.0).abs() < 75.0, "{}", early);
+        assert_eq!(
+            rng.spread_events(3, Duration::ZERO),
+            vec![Duration::ZERO; 3]
+        );
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let base = Duration::from_millis(100);
+        let cap = Duration::from_secs(10);
+        let mut copy = rng.clone();
+        let delays: Vec<Duration> = Backoff::new(&mut rng, base, cap, BackoffJitter::Full)
+            .take(20)
+            .collect();
+        let again: Vec<Duration> = Backoff::new(&mut copy, base, cap, BackoffJitter::Full)
+            .take(20)
+            .collect();
+        assert_eq!(delays, again);
+        for (attempt, delay) in delays.iter().enumerate() {
+            assert!(*delay <= (base * (1 << attempt.min(10))).min(cap));
+        }
+
+        let mut equal = Backoff::new(&mut rng, base, cap, BackoffJitter::Equal);
+        let mut total = Duration::ZERO;
+        for attempt in 0..1000 {
+            let delay = equal.next_delay();
+            let exp = (base * (1 << attempt.min(10))).min(cap);
+            assert!(delay >= exp / 2 && delay <= exp);
+            if attempt >= 10 {
+                total += delay;
+            }
+        }
+        // Delays at the cap average three quarters of it
+        let avg = total.as_secs_f64() / 990.0;
+        assert!((avg - 7.5).abs() < 0.2, "{}", avg);
+        equal.reset();
+        assert_eq!(equal.attempt(), 0);
+        assert!(equal.next_delay() <= base);
+
+        let decorrelated = Backoff::new(&mut rng, base, cap, BackoffJitter::Decorrelated);
+        assert!(decorrelated
+            .take(100)
+            .all(|delay| delay >= base && delay <= cap));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let base = Duration::from_millis(200);
+        let iter = 10000;
+        let mut acc = 0.0;
+        for _ in 0..iter {
+            let x = rng.jitter(10.0, -0.1);
+            assert!((9.0..=11.0).contains(&x));
+            acc += x;
+            let d = rng.jitter_duration(base, 0.25);
+            assert!(Duration::from_millis(150) <= d && d <= Duration::from_millis(250));
+        }
+        assert!((acc / iter as f64 - 10.0).abs() < 0.01, "{}", acc);
+        assert_eq!(rng.jitter_duration(base, 0.0), base);
+        assert!(rng.jitter_duration(Duration::MAX, 1.0) <= Duration::MAX);
+        assert!(rng.jitter_duration(base, 5.0) <= 2 * base);
+    }
+}
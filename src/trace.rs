@@ -0,0 +1,323 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Recording and counting draws, to find where two runs diverge.
+//!
+//! When two runs which should be identical are not, trace
+//! both, then `first_divergence` points at the first call
+//! which differed. Lockstep clients can compare their `audit`
+//! counts instead, which are always kept and cost nothing to send.
+//! Only the core methods of `SplittingRng` are recorded;
+//! everything else is built on them.
+
+use crate::{ShuffleAlgorithm, SplittingRng};
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+
+/// The core methods which are traced and counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Method {
+    GetBool,
+    GetU32,
+    GetU64,
+    GetF64,
+    BiasedRoll,
+    FairRoll,
+    Roll,
+    Shuffle,
+    Split,
+}
+
+impl Method {
+    /// Every method, in order
+    pub const ALL: [Method; 9] = [
+        Method::GetBool,
+        Method::GetU32,
+        Method::GetU64,
+        Method::GetF64,
+        Method::BiasedRoll,
+        Method::FairRoll,
+        Method::Roll,
+        Method::Shuffle,
+        Method::Split,
+    ];
+
+    /// The name of the method, as written in code
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::GetBool => "get_bool",
+            Method::GetU32 => "get_u32",
+            Method::GetU64 => "get_u64",
+            Method::GetF64 => "get_f64",
+            Method::BiasedRoll => "biased_roll",
+            Method::FairRoll => "fair_roll",
+            Method::Roll => "roll",
+            Method::Shuffle => "shuffle",
+            Method::Split => "split",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How many times each core method has been called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Audit {
+    counts: [u64; Method::ALL.len()],
+}
+
+impl Audit {
+    /// The calls made to one method
+    pub fn count(&self, method: Method) -> u64 {
+        self.counts[method as usize]
+    }
+
+    /// The calls made to all methods
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The methods whose counts differ from another audit,
+    /// with this count then the other
+    pub fn diff(&self, other: &Audit) -> Vec<(Method, u64, u64)> {
+        Method::ALL
+            .iter()
+            .map(|&m| (m, self.count(m), other.count(m)))
+            .filter(|(_, a, b)| a != b)
+            .collect()
+    }
+}
+
+impl fmt::Display for Audit {
+    /// Lists the methods which were called, as `name=count`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for m in Method::ALL {
+            if self.count(m) > 0 {
+                if !first {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{}={}", m, self.count(m))?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One recorded call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceEvent {
+    /// The step count just after the call
+    pub step: u64,
+    /// The method which was called
+    pub method: Method,
+    /// The value returned, as bits
+    ///
+    /// Floats are stored by `to_bits`, bools as 0 or 1,
+    /// `split` gives the child's origin and `shuffle`
+    /// gives the length of the list, with the algorithm
+    /// in the top byte
+    pub value: u64,
+}
+
+/// Shuffles keep their algorithm above this bit of the value
+const SHUFFLE_ALGORITHM_SHIFT: u32 = 56;
+
+/// The code for a shuffle by `shuffle_with_hasher`
+const HASHER_SHUFFLE: u64 = 3;
+
+impl TraceEvent {
+    /// The length of the list, for a shuffle
+    pub fn shuffle_len(&self) -> Option<u64> {
+        (self.method == Method::Shuffle)
+            .then_some(self.value & ((1 << SHUFFLE_ALGORITHM_SHIFT) - 1))
+    }
+
+    /// Whether this is a shuffle by `shuffle_with_hasher`,
+    /// which has no `shuffle_algorithm`
+    pub fn is_hasher_shuffle(&self) -> bool {
+        self.method == Method::Shuffle && self.value >> SHUFFLE_ALGORITHM_SHIFT == HASHER_SHUFFLE
+    }
+
+    /// The algorithm which ordered the list, for a shuffle
+    pub fn shuffle_algorithm(&self) -> Option<ShuffleAlgorithm> {
+        if self.method != Method::Shuffle {
+            return None;
+        }
+        match self.value >> SHUFFLE_ALGORITHM_SHIFT {
+            0 => Some(ShuffleAlgorithm::HashSort),
+            1 => Some(ShuffleAlgorithm::FisherYates),
+            2 => Some(ShuffleAlgorithm::BatchedHash),
+            _ => None,
+        }
+    }
+}
+
+/// The value recorded for a shuffle, where the default
+/// algorithm leaves just the length
+pub(crate) fn shuffle_value(len: usize, algorithm: ShuffleAlgorithm) -> u64 {
+    let code = match algorithm {
+        ShuffleAlgorithm::HashSort => 0,
+        ShuffleAlgorithm::FisherYates => 1,
+        ShuffleAlgorithm::BatchedHash => 2,
+    };
+    len as u64 | code << SHUFFLE_ALGORITHM_SHIFT
+}
+
+/// The value recorded for a shuffle by `shuffle_with_hasher`
+pub(crate) fn hasher_shuffle_value(len: usize) -> u64 {
+    len as u64 | HASHER_SHUFFLE << SHUFFLE_ALGORITHM_SHIFT
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {:#x}", self.step, self.method, self.value)
+    }
+}
+
+/// Somewhere to send trace events
+///
+/// Implemented for any closure, so sending events down a
+/// channel or pushing onto a shared `Vec` is a one-liner.
+pub trait TraceSink {
+    /// Record one event
+    fn record(&mut self, event: TraceEvent);
+}
+
+impl<F: FnMut(TraceEvent)> TraceSink for F {
+    fn record(&mut self, event: TraceEvent) {
+        self(event)
+    }
+}
+
+/// The index of the first event which differs between
+/// two traces, or where the shorter one ends early
+pub fn first_divergence(a: &[TraceEvent], b: &[TraceEvent]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(i) => Some(i),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// Send every call from now on to `sink`, replacing any
+    /// earlier sink
+    ///
+    /// Children from `split` are not traced, as they are
+    /// separate streams; trace them individually if needed.
+    pub fn trace_to<S: TraceSink + Send + Sync + 'static>(&mut self, sink: S) {
+        self.tracer = Some(Box::new(sink));
+    }
+
+    /// Stop tracing, handing back the sink
+    pub fn stop_trace(&mut self) -> Option<Box<dyn TraceSink + Send + Sync>> {
+        self.tracer.take()
+    }
+
+    /// Whether calls are being traced
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// The calls made to each core method so far
+    ///
+    /// Counts start at zero for new rngs, children and
+    /// loaded states, as they are not part of the stream
+    pub fn audit(&self) -> &Audit {
+        &self.audit
+    }
+
+    /// Set all audit counts back to zero, for example
+    /// at the start of each tick
+    pub fn reset_audit(&mut self) {
+        self.audit = Audit::default();
+    }
+
+    pub(crate) fn record(&mut self, method: Method, value: u64) {
+        self.audit.counts[method as usize] += 1;
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceEvent {
+                step: self.steps,
+                method,
+                value,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::sync::{Arc, Mutex};
+
+    fn run(extra: bool) -> Vec<TraceEvent> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.trace_to(move |e| sink.lock().unwrap().push(e));
+        rng.get_u64();
+        rng.fair_roll(6);
+        if extra {
+            rng.get_bool();
+        }
+        rng.shuffle(&[1, 2, 3]);
+        rng.split();
+        assert!(rng.stop_trace().is_some());
+        rng.get_u64();
+        let events = events.lock().unwrap().clone();
+        events
+    }
+
+    #[test]
+    fn test_trace_divergence() {
+        let a = run(false);
+        let b = run(true);
+        assert_eq!(a.len(), 4);
+        assert_eq!(a[1].method, Method::FairRoll);
+        assert_eq!(a[3].step, a[2].step + 1);
+        assert_eq!(first_divergence(&a, &a), None);
+        assert_eq!(first_divergence(&a, &b), Some(2));
+        assert_eq!(b[2].method, Method::GetBool);
+    }
+
+    #[test]
+    fn test_audit_counts() {
+        let mut a = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let mut b = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        for rng in [&mut a, &mut b] {
+            rng.fair_roll(6);
+            rng.fair_roll(6);
+            rng.split();
+        }
+        assert_eq!(a.audit(), b.audit());
+        b.get_bool();
+        assert_eq!(b.audit().count(Method::GetBool), 1);
+        assert_eq!(b.audit().total(), 4);
+        assert_eq!(a.audit().diff(b.audit()), vec![(Method::GetBool, 0, 1)]);
+        assert_eq!(b.audit().to_string(), "get_bool=1 fair_roll=2 split=1");
+        b.reset_audit();
+        assert_eq!(b.audit().total(), 0);
+    }
+
+    #[test]
+    fn test_wide_rolls_are_traced() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        rng.trace_to(move |e| sink.lock().unwrap().push(e));
+        let a = rng.fair_roll_u64(1 << 40);
+        let b = rng.fair_roll_u128(3 << 100);
+        let c = rng.fair_roll_usize(10);
+        assert_eq!(rng.audit().count(Method::FairRoll), 3);
+        let values: Vec<u64> = events.lock().unwrap().iter().map(|e| e.value).collect();
+        assert_eq!(values, vec![a, b as u64, c as u64]);
+    }
+}
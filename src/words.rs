@@ -0,0 +1,189 @@
+// This software is licensed under the Apache 2.0
+// or MIT license, at your option; and may be used
+// and reproduced under only those terms.
+
+//! Readable placeholder text from an embedded wordlist.
+//!
+//! The list holds 512 short, common English words, so each
+//! word adds 9 bits of entropy. Changing the list would change
+//! every phrase drawn from it, so it is fixed.
+//!
+//! Origins can be written as seed phrases of 8 words, which
+//! hold the 64 bits of the origin and an 8-bit checksum, so
+//! a seed can be read out loud and a misheard word is caught.
+
+use crate::keyed::mix64;
+use crate::SplittingRng;
+use rand::{RngCore, SeedableRng};
+use std::fmt;
+use std::sync::OnceLock;
+
+// 64 bits of origin and 8 of checksum, at 9 bits a word
+const PHRASE_WORDS: usize = 8;
+
+/// The reasons a seed phrase may not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhraseError {
+    /// The phrase did not have 8 words
+    WrongLength(usize),
+    /// A word was not in the list
+    UnknownWord(String),
+    /// Every word was known, but the checksum did not match
+    BadChecksum,
+}
+
+impl fmt::Display for PhraseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhraseError::WrongLength(n) => {
+                write!(f, "expected {} words, found {}", PHRASE_WORDS, n)
+            }
+            PhraseError::UnknownWord(word) => write!(f, "unknown word {:?}", word),
+            PhraseError::BadChecksum => write!(f, "the checksum did not match"),
+        }
+    }
+}
+
+impl std::error::Error for PhraseError {}
+
+fn checksum(origin: u64) -> u128 {
+    (mix64(origin) >> 56) as u128
+}
+
+/// Write an origin as a seed phrase, such as
+/// `maple-otter-quiz-...`, with eight words
+pub fn origin_phrase(origin: u64) -> String {
+    let words = wordlist();
+    let bits = ((origin as u128) << 8) | checksum(origin);
+    (0..PHRASE_WORDS)
+        .rev()
+        .map(|i| words[((bits >> (9 * i)) & 511) as usize])
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Read the origin from a seed phrase
+///
+/// Words may be separated by dashes, underscores or spaces,
+/// in any case.
+pub fn origin_from_phrase(phrase: &str) -> Result<u64, PhraseError> {
+    let words = wordlist();
+    let given: Vec<String> = phrase
+        .split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+    if given.len() != PHRASE_WORDS {
+        return Err(PhraseError::WrongLength(given.len()));
+    }
+    let mut bits = 0u128;
+    for word in given {
+        let index = words
+            .binary_search(&word.as_str())
+            .map_err(|_| PhraseError::UnknownWord(word))?;
+        bits = (bits << 9) | index as u128;
+    }
+    let origin = (bits >> 8) as u64;
+    if bits & 255 != checksum(origin) {
+        return Err(PhraseError::BadChecksum);
+    }
+    Ok(origin)
+}
+
+static WORDLIST: &str = include_str!("wordlist.txt");
+
+/// Every word in the list, in alphabetical order
+pub fn wordlist() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| WORDLIST.lines().collect())
+}
+
+impl<T: RngCore + SeedableRng> SplittingRng<T> {
+    /// The origin of this rng as a seed phrase, see `origin_phrase`
+    pub fn origin_phrase(&self) -> String {
+        origin_phrase(self.origin)
+    }
+
+    /// Create an rng from a seed phrase, as `new` would
+    /// from the origin it holds
+    pub fn from_phrase(phrase: &str) -> Result<Self, PhraseError> {
+        Ok(Self::new(origin_from_phrase(phrase)?))
+    }
+
+    /// Provide `n` words from the list, which may repeat
+    pub fn get_words(&mut self, n: usize) -> Vec<&'static str> {
+        let words = wordlist();
+        (0..n)
+            .map(|_| words[self.fair_roll(words.len() as u32) as usize])
+            .collect()
+    }
+
+    /// Provide a memorable identifier of `n` words
+    /// joined by dashes, such as `maple-otter-quiz`
+    pub fn get_passphrase(&mut self, n: usize) -> String {
+        self.get_words(n).join("-")
+    }
+
+    /// Provide a sentence of 4 to 10 words, starting with a
+    /// capital letter and ending with a full stop
+    pub fn get_sentence(&mut self) -> String {
+        let len = 4 + self.fair_roll(7) as usize;
+        let mut sentence = self.get_words(len).join(" ");
+        sentence[..1].make_ascii_uppercase();
+        sentence.push('.');
+        sentence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn test_words() {
+        let words = wordlist();
+        assert_eq!(words.len(), 512);
+        assert!(words.windows(2).all(|w| w[0] < w[1]));
+        let mut rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let phrase = rng.get_passphrase(3);
+        assert_eq!(phrase.split('-').count(), 3);
+        assert!(phrase.split('-').all(|w| words.contains(&w)));
+        for _ in 0..100 {
+            let sentence = rng.get_sentence();
+            assert!(sentence.starts_with(|c: char| c.is_ascii_uppercase()));
+            assert!(sentence.ends_with('.'));
+            assert!((4..=10).contains(&sentence.split(' ').count()));
+        }
+    }
+
+    #[test]
+    fn test_seed_phrases() {
+        let rng = SplittingRng::<Xoshiro256StarStar>::new(12345);
+        let phrase = rng.origin_phrase();
+        assert_eq!(phrase.split('-').count(), 8);
+        let restored = SplittingRng::<Xoshiro256StarStar>::from_phrase(&phrase).unwrap();
+        assert_eq!(restored.origin(), 12345);
+        let spoken = phrase.replace('-', " ").to_uppercase();
+        assert_eq!(origin_from_phrase(&spoken), Ok(12345));
+        for origin in [0, 1, u64::MAX, 0x0123_4567_89ab_cdef] {
+            assert_eq!(origin_from_phrase(&origin_phrase(origin)), Ok(origin));
+        }
+        // A changed word is caught by the checksum, bar 1 time in 256
+        let mut words: Vec<&str> = phrase.split('-').collect();
+        let last = words.len() - 1;
+        words[last] = if words[last] == wordlist()[0] {
+            wordlist()[1]
+        } else {
+            wordlist()[0]
+        };
+        assert_eq!(
+            origin_from_phrase(&words.join("-")),
+            Err(PhraseError::BadChecksum)
+        );
+        assert_eq!(
+            origin_from_phrase("maple otter"),
+            Err(PhraseError::WrongLength(2))
+        );
+    }
+}